@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::net::{SocketAddr, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::http;
+use crate::network::{summarize_samples, PingResult};
+use crate::speedtest;
+
+const IDLE_SAMPLES: u32 = 10;
+const SAMPLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+pub struct BufferbloatConfig {
+    // Base URL of a server answering `GET /__down?bytes=N` and `POST /__up`.
+    pub endpoint: String,
+    pub streams: usize,
+    pub load_duration: Duration,
+    pub sample_interval: Duration,
+}
+
+impl Default for BufferbloatConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: speedtest::DEFAULT_SERVER.to_string(),
+            streams: 4,
+            load_duration: Duration::from_secs(10),
+            sample_interval: Duration::from_millis(250),
+        }
+    }
+}
+
+// Latency to the endpoint when idle and while each direction is saturated.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BufferbloatResult {
+    pub endpoint: String,
+    pub idle: PingResult,
+    pub download: PingResult,
+    pub upload: PingResult,
+    pub download_mbps: Option<f64>,
+    pub upload_mbps: Option<f64>,
+    pub download_increase_ms: Option<f64>,
+    pub upload_increase_ms: Option<f64>,
+    pub grade: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn measure(config: &BufferbloatConfig) -> BufferbloatResult {
+    let endpoint = config.endpoint.trim_end_matches('/');
+    let mut result = BufferbloatResult {
+        endpoint: endpoint.to_string(),
+        ..Default::default()
+    };
+
+    let addr = match http::resolve_url(endpoint) {
+        Ok(addr) => addr,
+        Err(e) => {
+            log::debug!("bufferbloat::measure: bad endpoint {}: {}", endpoint, e);
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+
+    result.idle = sample_latency(addr, config.sample_interval, |sent, _| sent < IDLE_SAMPLES);
+    let Some(idle_ms) = result.idle.median_ms else {
+        result.error = Some(format!("{} did not answer", addr));
+        return result;
+    };
+
+    let (download, download_mbps) =
+        under_load(addr, config, speedtest::download_transfer(endpoint));
+    let (upload, upload_mbps) = under_load(addr, config, speedtest::upload_transfer(endpoint));
+
+    // Latency under a load that never materialized says nothing.
+    if download_mbps.is_some() {
+        result.download_increase_ms = download.median_ms.map(|ms| (ms - idle_ms).max(0.0));
+    }
+    if upload_mbps.is_some() {
+        result.upload_increase_ms = upload.median_ms.map(|ms| (ms - idle_ms).max(0.0));
+    }
+    result.download = download;
+    result.upload = upload;
+    result.download_mbps = download_mbps;
+    result.upload_mbps = upload_mbps;
+
+    let worst = match (result.download_increase_ms, result.upload_increase_ms) {
+        (Some(down), Some(up)) => Some(down.max(up)),
+        (down, up) => down.or(up),
+    };
+    result.grade = worst.map(|ms| grade(ms).to_string());
+    if worst.is_none() {
+        result.error = Some(format!("could not load {}", endpoint));
+    }
+
+    log::debug!(
+        "bufferbloat::measure: {} - idle: {:.1}ms, download: +{:?}ms at {:?}Mbps, upload: +{:?}ms at {:?}Mbps, grade: {:?}",
+        endpoint,
+        idle_ms,
+        result.download_increase_ms,
+        result.download_mbps,
+        result.upload_increase_ms,
+        result.upload_mbps,
+        result.grade
+    );
+    result
+}
+
+// Grades the worst latency increase under load, on the scale common
+// bufferbloat tests use.
+pub fn grade(increase_ms: f64) -> &'static str {
+    match increase_ms {
+        ms if ms < 30.0 => "A",
+        ms if ms < 60.0 => "B",
+        ms if ms < 200.0 => "C",
+        ms if ms < 400.0 => "D",
+        _ => "F",
+    }
+}
+
+// Latency while `transfer` saturates the link, and the throughput it reached.
+fn under_load(
+    addr: SocketAddr,
+    config: &BufferbloatConfig,
+    transfer: impl Fn(&AtomicBool, &AtomicU64) -> io::Result<()> + Sync,
+) -> (PingResult, Option<f64>) {
+    let warmup = config.load_duration / 5;
+    speedtest::with_load(config.streams, warmup, transfer, |_| {
+        sample_latency(addr, config.sample_interval, |_, elapsed| {
+            elapsed + warmup < config.load_duration
+        })
+    })
+}
+
+// Times TCP handshakes with `addr` until `more(sent, elapsed)` says stop. A
+// handshake is a round trip through the same queues the load is filling.
+fn sample_latency(
+    addr: SocketAddr,
+    interval: Duration,
+    more: impl Fn(u32, Duration) -> bool,
+) -> PingResult {
+    let start = Instant::now();
+    let mut sent = 0;
+    let mut samples = Vec::new();
+    while more(sent, start.elapsed()) {
+        sent += 1;
+        let attempt = Instant::now();
+        if TcpStream::connect_timeout(&addr, SAMPLE_TIMEOUT).is_ok() {
+            samples.push(attempt.elapsed().as_secs_f64() * 1000.0);
+        }
+        thread::sleep(interval);
+    }
+    summarize_samples(sent, samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::speedtest::tests::serve_speed_endpoint;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_measure_local_endpoint() {
+        let port = serve_speed_endpoint();
+        let config = BufferbloatConfig {
+            endpoint: format!("http://127.0.0.1:{}/", port),
+            streams: 2,
+            load_duration: Duration::from_millis(500),
+            sample_interval: Duration::from_millis(20),
+        };
+
+        let result = measure(&config);
+        assert_eq!(result.error, None);
+        assert_eq!(result.endpoint, format!("http://127.0.0.1:{}", port));
+        assert_eq!(result.idle.sent, Some(IDLE_SAMPLES));
+        assert!(result.idle.median_ms.is_some());
+        assert!(result.download.sent.unwrap() > 0);
+        assert!(result.download_mbps.unwrap() > 0.0);
+        assert!(result.upload_mbps.unwrap() > 0.0);
+        assert!(result.download_increase_ms.unwrap() >= 0.0);
+        assert!(result.grade.is_some());
+    }
+
+    #[test]
+    fn test_measure_unreachable_endpoint() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let config = BufferbloatConfig {
+            endpoint: format!("http://127.0.0.1:{}", port),
+            sample_interval: Duration::from_millis(1),
+            ..Default::default()
+        };
+        let result = measure(&config);
+        assert!(result.error.unwrap().contains("did not answer"));
+        assert_eq!(result.idle.received, Some(0));
+        assert_eq!(result.grade, None);
+
+        let config = BufferbloatConfig {
+            endpoint: "ftp://example.com".to_string(),
+            ..Default::default()
+        };
+        assert!(measure(&config)
+            .error
+            .unwrap()
+            .contains("unsupported scheme"));
+    }
+
+    #[test]
+    fn test_grade() {
+        assert_eq!(grade(0.0), "A");
+        assert_eq!(grade(29.9), "A");
+        assert_eq!(grade(45.0), "B");
+        assert_eq!(grade(150.0), "C");
+        assert_eq!(grade(399.0), "D");
+        assert_eq!(grade(400.0), "F");
+    }
+}
@@ -0,0 +1,134 @@
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+// A result too slow to gather on every metrics poll. Polls get the last value
+// straight away, and once it goes stale one of them starts a refresh in the
+// background for later polls to pick up.
+pub struct BackgroundCache<K, T> {
+    state: Mutex<State<K, T>>,
+}
+
+struct State<K, T> {
+    // What the value was gathered for, such as the interface and targets.
+    key: Option<K>,
+    value: Option<T>,
+    updated: Option<Instant>,
+    refreshing: bool,
+}
+
+// Clears the refreshing flag however a refresh ends, so one that panics does
+// not stop every later refresh.
+struct RefreshGuard<K: 'static, T: 'static>(&'static Mutex<State<K, T>>);
+
+impl<K, T> Drop for RefreshGuard<K, T> {
+    fn drop(&mut self) {
+        let mut state = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        state.refreshing = false;
+    }
+}
+
+impl<K, T> BackgroundCache<K, T>
+where
+    K: PartialEq + Clone + Send + 'static,
+    T: Clone + Send + 'static,
+{
+    pub const fn new() -> Self {
+        BackgroundCache {
+            state: Mutex::new(State {
+                key: None,
+                value: None,
+                updated: None,
+                refreshing: false,
+            }),
+        }
+    }
+
+    // The last value gathered for `key`, if any. A value gathered for another
+    // key is dropped rather than shown for the wrong inputs.
+    pub fn get<F>(&'static self, key: K, max_age: Duration, refresh: F) -> Option<T>
+    where
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let mut state = self.state.lock().unwrap();
+        if state.key.as_ref() != Some(&key) {
+            state.key = Some(key.clone());
+            state.value = None;
+            state.updated = None;
+        }
+
+        let fresh = state
+            .updated
+            .is_some_and(|updated| updated.elapsed() < max_age);
+        if !fresh && !state.refreshing {
+            state.refreshing = true;
+            tokio::task::spawn_blocking(move || {
+                let _guard = RefreshGuard(&self.state);
+                let value = refresh();
+                let mut state = self.state.lock().unwrap();
+                if state.key.as_ref() == Some(&key) {
+                    state.value = Some(value);
+                    state.updated = Some(Instant::now());
+                }
+            });
+        }
+        state.value.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Polls until `key` has a value, refreshing to `value` whenever asked to.
+    async fn wait_for(cache: &'static BackgroundCache<u32, u32>, key: u32, value: u32) -> u32 {
+        for _ in 0..100 {
+            if let Some(value) = cache.get(key, Duration::from_secs(60), move || value) {
+                return value;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("refresh for {} never finished", key);
+    }
+
+    #[tokio::test]
+    async fn test_background_cache() {
+        static CACHE: BackgroundCache<u32, u32> = BackgroundCache::new();
+
+        assert_eq!(CACHE.get(1, Duration::from_secs(60), || 10), None);
+        assert_eq!(wait_for(&CACHE, 1, 10).await, 10);
+        // Fresh values are served without refreshing.
+        assert_eq!(
+            CACHE.get(1, Duration::from_secs(60), || unreachable!()),
+            Some(10)
+        );
+
+        // Stale values are still served while the refresh runs.
+        assert_eq!(CACHE.get(1, Duration::ZERO, || 11), Some(10));
+        for _ in 0..100 {
+            if CACHE.get(1, Duration::from_secs(60), || unreachable!()) == Some(11) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(
+            CACHE.get(1, Duration::from_secs(60), || unreachable!()),
+            Some(11)
+        );
+
+        // Another key starts over.
+        assert_eq!(CACHE.get(2, Duration::from_secs(60), || 20), None);
+        assert_eq!(wait_for(&CACHE, 2, 20).await, 20);
+    }
+
+    #[tokio::test]
+    async fn test_background_cache_after_panic() {
+        static CACHE: BackgroundCache<u32, u32> = BackgroundCache::new();
+
+        assert_eq!(
+            CACHE.get(1, Duration::from_secs(60), || panic!("probe failed")),
+            None
+        );
+        // A later poll starts a new refresh once the failed one is over.
+        assert_eq!(wait_for(&CACHE, 1, 10).await, 10);
+    }
+}
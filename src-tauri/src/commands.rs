@@ -1,33 +1,146 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::ipc::Channel;
 
+use crate::bufferbloat::{self, BufferbloatConfig, BufferbloatResult};
+use crate::cache::BackgroundCache;
+use crate::dhcp::{self, Addressing};
+use crate::dnsbench::{
+    self, default_alternative_resolvers, BenchmarkConfig, DnsBenchmark, ResolverTarget,
+};
+use crate::dualstack::{self, DualStack};
+use crate::http::{self, HttpTiming};
+use crate::interfaces::{list_interfaces, select_wireless_interface, NetworkInterface};
 use crate::interference::{analyze_interference, InterferenceAnalysis};
+use crate::lan::{self, DiscoveryConfig, LanInventory};
 use crate::network::{get_dns_info, get_router_ip, ping_host, DnsInfo, PingResult};
+use crate::portal::{self, CaptivePortal};
+use crate::probes::{
+    best_result, default_internet_targets, probe_target, summarize_targets, InternetSummary,
+    InternetTarget, TargetResult,
+};
+use crate::runner::{self, SharedRunner};
+use crate::speedtest::{self, SpeedTestConfig, SpeedTestProgress, SpeedTestResult};
+use crate::traceroute::{self, TraceUpdate};
 use crate::wifi::{get_wifi_info, WifiInfo};
 
+const DEFAULT_HTTP_TIMING_URL: &str = "https://www.google.com/generate_204";
+const HTTP_TIMING_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_TRACE_HOST: &str = "1.1.1.1";
+const DEFAULT_TRACE_ROUNDS: u32 = 10;
+const DUAL_STACK_MAX_AGE: Duration = Duration::from_secs(30);
+// A portal is what usually keeps every target from answering, so the check
+// repeats often then and rarely otherwise.
+const PORTAL_MAX_AGE: Duration = Duration::from_secs(300);
+const PORTAL_OFFLINE_MAX_AGE: Duration = Duration::from_secs(15);
+
+// Probing IPv6 takes seconds when it is broken, so the comparison runs in the
+// background and polls report the last one, keyed by interface and targets.
+static DUAL_STACK: BackgroundCache<(Option<String>, Vec<InternetTarget>), DualStack> =
+    BackgroundCache::new();
+// The portal check blocks on an HTTP request, so it is cached the same way,
+// keyed by check URL and whether any target answered.
+static CAPTIVE_PORTAL: BackgroundCache<(String, bool), CaptivePortal> = BackgroundCache::new();
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NetworkMetrics {
+    pub interface: Option<NetworkInterface>,
     pub wifi: WifiInfo,
     pub router_ip: Option<String>,
     pub router_ping: Option<PingResult>,
     pub internet_ping: Option<PingResult>,
+    pub internet_targets: Vec<TargetResult>,
+    pub internet_summary: InternetSummary,
     pub dns: DnsInfo,
+    pub dual_stack: DualStack,
+    pub captive_portal: CaptivePortal,
+    pub addressing: Addressing,
+}
+
+#[tauri::command]
+pub async fn list_network_interfaces() -> Result<Vec<NetworkInterface>, String> {
+    let runner = runner::from_env();
+    tokio::task::spawn_blocking(move || list_interfaces(runner.as_ref()))
+        .await
+        .map_err(|e| {
+            log::error!("list_network_interfaces: task failed: {}", e);
+            e.to_string()
+        })
 }
 
 #[tauri::command]
-pub async fn get_network_metrics() -> Result<NetworkMetrics, String> {
+pub async fn get_network_metrics(
+    interface: Option<String>,
+    targets: Option<Vec<InternetTarget>>,
+    portal_url: Option<String>,
+) -> Result<NetworkMetrics, String> {
+    let targets = targets.unwrap_or_else(default_internet_targets);
+    let portal_url = portal_url.unwrap_or_else(|| portal::DEFAULT_CHECK_URL.to_string());
+    collect_network_metrics(runner::from_env(), interface, targets, portal_url).await
+}
+
+async fn resolve_interface(
+    runner: &SharedRunner,
+    preferred: Option<String>,
+) -> Result<Option<NetworkInterface>, String> {
+    let runner = runner.clone();
+    tokio::task::spawn_blocking(move || {
+        let interfaces = list_interfaces(runner.as_ref());
+        select_wireless_interface(&interfaces, preferred.as_deref()).cloned()
+    })
+    .await
+    .map_err(|e| {
+        log::error!("resolve_interface: task failed: {}", e);
+        e.to_string()
+    })
+}
+
+async fn collect_network_metrics(
+    runner: SharedRunner,
+    preferred_interface: Option<String>,
+    targets: Vec<InternetTarget>,
+    portal_url: String,
+) -> Result<NetworkMetrics, String> {
     log::debug!("get_network_metrics: starting data collection");
 
-    let wifi_task = tokio::task::spawn_blocking(get_wifi_info);
-    let router_ip_task = tokio::task::spawn_blocking(get_router_ip);
-    let internet_ping_task = tokio::task::spawn_blocking(|| ping_host("1.1.1.1", 3));
-    let dns_task = tokio::task::spawn_blocking(get_dns_info);
+    let interface = resolve_interface(&runner, preferred_interface).await?;
+    log::debug!(
+        "get_network_metrics: using interface {:?}",
+        interface.as_ref().map(|i| &i.name)
+    );
+
+    let wifi_task = {
+        let runner = runner.clone();
+        let interface = interface.clone();
+        tokio::task::spawn_blocking(move || get_wifi_info(runner.as_ref(), interface.as_ref()))
+    };
+    let router_ip_task = {
+        let runner = runner.clone();
+        let interface = interface.clone();
+        tokio::task::spawn_blocking(move || get_router_ip(runner.as_ref(), interface.as_ref()))
+    };
+    let target_tasks: Vec<_> = targets
+        .iter()
+        .cloned()
+        .map(|target| {
+            let runner = runner.clone();
+            let interface = interface.clone();
+            tokio::task::spawn_blocking(move || {
+                probe_target(runner.as_ref(), &target, interface.as_ref())
+            })
+        })
+        .collect();
+    let dns_task = {
+        let runner = runner.clone();
+        let interface = interface.clone();
+        tokio::task::spawn_blocking(move || get_dns_info(runner.as_ref(), interface.as_ref()))
+    };
 
-    let (wifi_result, router_ip_result, internet_ping_result, dns_result): (
+    let (wifi_result, router_ip_result, dns_result): (
         Result<WifiInfo, _>,
         Result<Option<String>, _>,
-        Result<PingResult, _>,
         Result<DnsInfo, _>,
-    ) = tokio::join!(wifi_task, router_ip_task, internet_ping_task, dns_task);
+    ) = tokio::join!(wifi_task, router_ip_task, dns_task);
 
     let wifi = wifi_result.map_err(|e| {
         log::error!("get_network_metrics: wifi task failed: {}", e);
@@ -37,55 +150,304 @@ pub async fn get_network_metrics() -> Result<NetworkMetrics, String> {
         log::error!("get_network_metrics: router_ip task failed: {}", e);
         e.to_string()
     })?;
-    let internet_ping = internet_ping_result.map_err(|e| {
-        log::error!("get_network_metrics: internet_ping task failed: {}", e);
-        e.to_string()
-    })?;
+    let mut internet_targets = Vec::with_capacity(target_tasks.len());
+    for task in target_tasks {
+        internet_targets.push(task.await.map_err(|e| {
+            log::error!("get_network_metrics: internet target task failed: {}", e);
+            e.to_string()
+        })?);
+    }
     let dns = dns_result.map_err(|e| {
         log::error!("get_network_metrics: dns task failed: {}", e);
         e.to_string()
     })?;
 
+    let addressing_task = {
+        let runner = runner.clone();
+        let interface = interface.clone();
+        let router_ip = router_ip.clone();
+        tokio::task::spawn_blocking(move || {
+            dhcp::inspect(runner.as_ref(), interface.as_ref(), router_ip.as_deref())
+        })
+    };
+
     let router_ping = if let Some(ref ip) = router_ip {
         let ip_clone = ip.clone();
+        let interface = interface.clone();
+        let runner = runner.clone();
         Some(
-            tokio::task::spawn_blocking(move || ping_host(&ip_clone, 3))
-                .await
-                .map_err(|e| {
-                    log::error!("get_network_metrics: router_ping task failed: {}", e);
-                    e.to_string()
-                })?,
+            tokio::task::spawn_blocking(move || {
+                ping_host(runner.as_ref(), &ip_clone, 3, interface.as_ref())
+            })
+            .await
+            .map_err(|e| {
+                log::error!("get_network_metrics: router_ping task failed: {}", e);
+                e.to_string()
+            })?,
         )
     } else {
         log::debug!("get_network_metrics: no router IP found, skipping router ping");
         None
     };
 
+    let dual_stack = {
+        let key = (interface.as_ref().map(|i| i.name.clone()), targets.clone());
+        let results = internet_targets.clone();
+        let router_ip = router_ip.clone();
+        let router_ping = router_ping.clone();
+        let dns_servers = dns.servers.clone();
+        let interface = interface.clone();
+        let runner = runner.clone();
+        DUAL_STACK
+            .get(key, DUAL_STACK_MAX_AGE, move || {
+                dualstack::measure(
+                    runner.as_ref(),
+                    &targets,
+                    &results,
+                    router_ip.as_deref(),
+                    router_ping.as_ref(),
+                    &dns_servers,
+                    interface.as_ref(),
+                )
+            })
+            .unwrap_or_default()
+    };
+    let addressing = addressing_task.await.map_err(|e| {
+        log::error!("get_network_metrics: addressing task failed: {}", e);
+        e.to_string()
+    })?;
+
+    let internet_summary = summarize_targets(&internet_targets);
+    let captive_portal = {
+        let online = internet_summary.reachable_targets > 0;
+        let max_age = if online {
+            PORTAL_MAX_AGE
+        } else {
+            PORTAL_OFFLINE_MAX_AGE
+        };
+        let url = portal_url.clone();
+        CAPTIVE_PORTAL
+            .get((portal_url.clone(), online), max_age, move || {
+                portal::detect(runner.as_ref(), &url)
+            })
+            .unwrap_or_else(|| CaptivePortal {
+                check_url: portal_url,
+                ..Default::default()
+            })
+    };
+    let internet_ping = best_result(&internet_targets)
+        .or(internet_targets.first())
+        .map(|result| result.ping.clone());
+
     log::debug!(
-        "get_network_metrics: complete - wifi connected: {}, router: {:?}, internet ping: {:?}ms",
+        "get_network_metrics: complete - wifi connected: {}, router: {:?}, internet: {}/{} targets reachable, best: {:?}, ipv6: {:?}, portal: {:?}",
         wifi.connected,
         router_ip,
-        internet_ping.latency_ms
+        internet_summary.reachable_targets,
+        internet_summary.total_targets,
+        internet_summary.best_target,
+        dual_stack.ipv6_status,
+        captive_portal.state
     );
 
     Ok(NetworkMetrics {
+        interface,
         wifi,
         router_ip,
         router_ping,
-        internet_ping: Some(internet_ping),
+        internet_ping,
+        internet_targets,
+        internet_summary,
         dns,
+        dual_stack,
+        captive_portal,
+        addressing,
     })
 }
 
 #[tauri::command]
-pub async fn check_interference() -> Result<InterferenceAnalysis, String> {
-    log::debug!("check_interference: starting analysis");
-    let result = tokio::task::spawn_blocking(analyze_interference)
+pub async fn measure_http_timing(url: Option<String>) -> Result<HttpTiming, String> {
+    let url = url.unwrap_or_else(|| DEFAULT_HTTP_TIMING_URL.to_string());
+    log::debug!("measure_http_timing: fetching {}", url);
+    tokio::task::spawn_blocking(move || http::measure(&url, HTTP_TIMING_TIMEOUT))
+        .await
+        .map_err(|e| {
+            log::error!("measure_http_timing: task failed: {}", e);
+            e.to_string()
+        })
+}
+
+#[tauri::command]
+pub async fn measure_bufferbloat(endpoint: Option<String>) -> Result<BufferbloatResult, String> {
+    let mut config = BufferbloatConfig::default();
+    if let Some(endpoint) = endpoint {
+        config.endpoint = endpoint;
+    }
+    log::debug!("measure_bufferbloat: loading {}", config.endpoint);
+    tokio::task::spawn_blocking(move || bufferbloat::measure(&config))
         .await
         .map_err(|e| {
-            log::error!("check_interference: task failed: {}", e);
+            log::error!("measure_bufferbloat: task failed: {}", e);
             e.to_string()
-        })?;
+        })
+}
+
+// Streams a SpeedTestProgress over `on_event` as each phase runs.
+#[tauri::command]
+pub async fn run_speed_test(
+    server: Option<String>,
+    on_event: Channel<SpeedTestProgress>,
+) -> Result<SpeedTestResult, String> {
+    let mut config = SpeedTestConfig::default();
+    if let Some(server) = server {
+        config.server = server;
+    }
+    log::debug!("run_speed_test: testing against {}", config.server);
+    tokio::task::spawn_blocking(move || {
+        speedtest::run(&config, |progress| {
+            if let Err(e) = on_event.send(progress.clone()) {
+                log::debug!("run_speed_test: failed to send progress: {}", e);
+            }
+        })
+    })
+    .await
+    .map_err(|e| {
+        log::error!("run_speed_test: task failed: {}", e);
+        e.to_string()
+    })?
+    .map_err(|e| {
+        log::error!("run_speed_test: {}", e);
+        e.to_string()
+    })
+}
+
+// Streams a TraceUpdate over `on_event` after every round and returns the last.
+#[tauri::command]
+pub async fn trace_route(
+    host: Option<String>,
+    interface: Option<String>,
+    rounds: Option<u32>,
+    on_event: Channel<TraceUpdate>,
+) -> Result<TraceUpdate, String> {
+    let host = host.unwrap_or_else(|| DEFAULT_TRACE_HOST.to_string());
+    let rounds = rounds.unwrap_or(DEFAULT_TRACE_ROUNDS).max(1);
+    collect_trace_route(runner::from_env(), interface, host, rounds, move |update| {
+        if let Err(e) = on_event.send(update.clone()) {
+            log::debug!("trace_route: failed to send update: {}", e);
+        }
+    })
+    .await
+}
+
+async fn collect_trace_route(
+    runner: SharedRunner,
+    preferred_interface: Option<String>,
+    host: String,
+    rounds: u32,
+    on_update: impl FnMut(&TraceUpdate) + Send + 'static,
+) -> Result<TraceUpdate, String> {
+    log::debug!("trace_route: tracing {} over {} rounds", host, rounds);
+    let interface = resolve_interface(&runner, preferred_interface).await?;
+    tokio::task::spawn_blocking(move || {
+        traceroute::trace_route(
+            runner.as_ref(),
+            &host,
+            rounds,
+            interface.as_ref(),
+            on_update,
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("trace_route: task failed: {}", e);
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+pub async fn benchmark_dns(
+    interface: Option<String>,
+    alternatives: Option<Vec<ResolverTarget>>,
+) -> Result<DnsBenchmark, String> {
+    let alternatives = alternatives.unwrap_or_else(default_alternative_resolvers);
+    collect_dns_benchmark(runner::from_env(), interface, alternatives).await
+}
+
+async fn collect_dns_benchmark(
+    runner: SharedRunner,
+    preferred_interface: Option<String>,
+    alternatives: Vec<ResolverTarget>,
+) -> Result<DnsBenchmark, String> {
+    log::debug!("benchmark_dns: starting");
+    let interface = resolve_interface(&runner, preferred_interface).await?;
+    tokio::task::spawn_blocking(move || {
+        let dns = get_dns_info(runner.as_ref(), interface.as_ref());
+        let candidates = dnsbench::candidates(&dns.servers, &alternatives);
+        if !runner.native_probes() {
+            log::debug!("benchmark_dns: lookups need live sockets, skipping");
+            return DnsBenchmark {
+                resolvers: candidates,
+                recommendation: None,
+            };
+        }
+        dnsbench::benchmark(&BenchmarkConfig::default(), candidates)
+    })
+    .await
+    .map_err(|e| {
+        log::error!("benchmark_dns: task failed: {}", e);
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+pub async fn discover_lan(interface: Option<String>) -> Result<LanInventory, String> {
+    collect_lan_inventory(runner::from_env(), interface).await
+}
+
+async fn collect_lan_inventory(
+    runner: SharedRunner,
+    preferred_interface: Option<String>,
+) -> Result<LanInventory, String> {
+    log::debug!("discover_lan: starting");
+    let interface = resolve_interface(&runner, preferred_interface).await?;
+    tokio::task::spawn_blocking(move || {
+        lan::discover(
+            runner.as_ref(),
+            interface.as_ref(),
+            &DiscoveryConfig::default(),
+        )
+    })
+    .await
+    .map_err(|e| {
+        log::error!("discover_lan: task failed: {}", e);
+        e.to_string()
+    })
+}
+
+#[tauri::command]
+pub async fn check_interference(
+    interface: Option<String>,
+    targets: Option<Vec<InternetTarget>>,
+) -> Result<InterferenceAnalysis, String> {
+    let targets = targets.unwrap_or_else(default_internet_targets);
+    collect_interference(runner::from_env(), interface, targets).await
+}
+
+async fn collect_interference(
+    runner: SharedRunner,
+    preferred_interface: Option<String>,
+    targets: Vec<InternetTarget>,
+) -> Result<InterferenceAnalysis, String> {
+    log::debug!("check_interference: starting analysis");
+    let interface = resolve_interface(&runner, preferred_interface).await?;
+    let result = tokio::task::spawn_blocking(move || {
+        analyze_interference(runner.as_ref(), interface.as_ref(), &targets)
+    })
+    .await
+    .map_err(|e| {
+        log::error!("check_interference: task failed: {}", e);
+        e.to_string()
+    })?;
     log::debug!(
         "check_interference: complete - level: {}, nearby networks: {}",
         result.interference_level,
@@ -93,3 +455,230 @@ pub async fn check_interference() -> Result<InterferenceAnalysis, String> {
     );
     Ok(result)
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::dhcp::LeaseSource;
+    use crate::dualstack::Ipv6Status;
+    use crate::portal::PortalState;
+    use crate::runner::ReplayRunner;
+    use std::sync::Arc;
+
+    fn replay(scenario: &str) -> SharedRunner {
+        Arc::new(ReplayRunner::new(format!(
+            "{}/fixtures/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            scenario
+        )))
+    }
+
+    #[tokio::test]
+    async fn test_collect_network_metrics_replay() {
+        let runner = replay("linux-home");
+        let metrics = collect_network_metrics(
+            runner.clone(),
+            None,
+            default_internet_targets(),
+            portal::DEFAULT_CHECK_URL.to_string(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(metrics.interface.as_ref().unwrap().name, "wlp2s0");
+        assert!(metrics.wifi.connected);
+        assert_eq!(metrics.wifi.ssid, Some("HomeNet".to_string()));
+        assert_eq!(
+            metrics.wifi.channel,
+            Some("ch 36, 5 GHz, 80 MHz".to_string())
+        );
+        assert_eq!(metrics.wifi.noise_dbm, Some(-94));
+        assert_eq!(metrics.router_ip, Some("192.168.1.1".to_string()));
+
+        let router_ping = metrics.router_ping.as_ref().unwrap();
+        assert!((router_ping.latency_ms.unwrap() - 3.214).abs() < 0.001);
+        assert_eq!(router_ping.packet_loss_percent, Some(0.0));
+
+        let internet_ping = metrics.internet_ping.as_ref().unwrap();
+        assert!((internet_ping.latency_ms.unwrap() - 14.862).abs() < 0.001);
+
+        let labels: Vec<&str> = metrics
+            .internet_targets
+            .iter()
+            .map(|r| r.target.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["Cloudflare", "Google", "Quad9"]);
+        assert_eq!(
+            metrics.internet_targets[2].ping.packet_loss_percent,
+            Some(100.0)
+        );
+        assert_eq!(metrics.internet_summary.total_targets, 3);
+        assert_eq!(metrics.internet_summary.reachable_targets, 2);
+        assert_eq!(
+            metrics.internet_summary.best_target.as_deref(),
+            Some("Cloudflare")
+        );
+
+        assert_eq!(metrics.dns.servers, vec!["192.168.1.1", "1.1.1.1"]);
+        assert_eq!(metrics.dns.search_domains, vec!["lan"]);
+        assert_eq!(metrics.dns.lookup_latency_ms, Some(12.0));
+
+        // IPv6 is compared in the background, so the first poll has no result.
+        assert_eq!(metrics.dual_stack.ipv6_status, Ipv6Status::Unknown);
+        assert_eq!(metrics.dual_stack.ipv6.router_ip, None);
+
+        // The fixture has no IPv6 default route, so only IPv4 is compared.
+        let dual_stack = dualstack::measure(
+            runner.as_ref(),
+            &default_internet_targets(),
+            &metrics.internet_targets,
+            metrics.router_ip.as_deref(),
+            metrics.router_ping.as_ref(),
+            &metrics.dns.servers,
+            metrics.interface.as_ref(),
+        );
+        assert_eq!(dual_stack.ipv6_status, Ipv6Status::Unavailable);
+        assert_eq!(dual_stack.ipv6.router_ip, None);
+        assert_eq!(dual_stack.ipv4.router_ip.as_deref(), Some("192.168.1.1"));
+        assert_eq!(dual_stack.ipv4.internet_targets.len(), 3);
+        assert_eq!(dual_stack.ipv4.internet_summary.reachable_targets, 2);
+        assert_eq!(dual_stack.ipv4.dns_servers, vec!["192.168.1.1", "1.1.1.1"]);
+
+        // The portal check runs in the background, so the first poll has no
+        // result, and it needs live sockets in any case.
+        assert_eq!(metrics.captive_portal.state, PortalState::Unknown);
+        assert_eq!(metrics.captive_portal.check_url, portal::DEFAULT_CHECK_URL);
+
+        let addressing = metrics.addressing;
+        assert_eq!(addressing.ipv4.len(), 1);
+        assert_eq!(addressing.ipv4[0].address, "192.168.1.23");
+        assert_eq!(addressing.ipv4[0].prefix_len, 24);
+        assert_eq!(addressing.ipv6.len(), 1);
+        // The lease file is a day old; the address still counts down the real lease.
+        let lease = addressing.lease.unwrap();
+        assert_eq!(lease.source, LeaseSource::Dhclient);
+        assert_eq!(lease.server.as_deref(), Some("192.168.1.1"));
+        assert_eq!(lease.lease_time_secs, Some(86400));
+        assert_eq!(lease.remaining_secs, Some(80234));
+        assert!(!addressing.self_assigned);
+        assert!(!addressing.missing_gateway);
+        assert!(addressing.overlaps.is_empty());
+        assert!(addressing.issues.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collect_dns_benchmark_replay() {
+        let benchmark =
+            collect_dns_benchmark(replay("linux-home"), None, default_alternative_resolvers())
+                .await
+                .unwrap();
+
+        let resolvers: Vec<_> = benchmark
+            .resolvers
+            .iter()
+            .map(|r| (r.label.as_str(), r.server.as_str(), r.system))
+            .collect();
+        assert_eq!(
+            resolvers,
+            vec![
+                ("System", "192.168.1.1", true),
+                ("Cloudflare", "1.1.1.1", true),
+                ("Google", "8.8.8.8", false),
+                ("Quad9", "9.9.9.9", false),
+            ]
+        );
+        assert!(benchmark.resolvers.iter().all(|r| r.queries == 0));
+        assert_eq!(benchmark.recommendation, None);
+    }
+
+    #[tokio::test]
+    async fn test_collect_trace_route_replay() {
+        let updates = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = updates.clone();
+        let trace = collect_trace_route(
+            replay("linux-home"),
+            None,
+            DEFAULT_TRACE_HOST.to_string(),
+            DEFAULT_TRACE_ROUNDS,
+            move |update| sink.lock().unwrap().push(update.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert!(trace.complete);
+        assert_eq!(trace.destination.as_deref(), Some("1.1.1.1"));
+        let addresses: Vec<Option<&str>> =
+            trace.hops.iter().map(|h| h.address.as_deref()).collect();
+        assert_eq!(
+            addresses,
+            vec![
+                Some("192.168.1.1"),
+                None,
+                Some("100.64.0.1"),
+                Some("68.86.1.2"),
+                Some("1.1.1.1")
+            ]
+        );
+        assert_eq!(trace.hops[0].hostname.as_deref(), Some("router.lan"));
+        assert_eq!(
+            trace.hops[2].ping.packet_loss_percent.map(f64::round),
+            Some(33.0)
+        );
+
+        let updates = updates.lock().unwrap();
+        assert_eq!(updates.len(), 1);
+        assert!(updates[0].complete);
+    }
+
+    #[tokio::test]
+    async fn test_collect_lan_inventory_replay() {
+        let inventory = collect_lan_inventory(replay("linux-home"), None)
+            .await
+            .unwrap();
+
+        // Browsing needs live sockets, so replay only reads the neighbor table.
+        assert!(!inventory.browsed);
+        assert_eq!(inventory.interface.as_deref(), Some("wlp2s0"));
+        let devices: Vec<_> = inventory
+            .devices
+            .iter()
+            .map(|d| (d.ip.as_str(), d.vendor.as_deref(), d.private_mac, d.gateway))
+            .collect();
+        assert_eq!(
+            devices,
+            vec![
+                ("192.168.1.1", Some("TP-Link"), false, true),
+                ("192.168.1.40", Some("Raspberry Pi"), false, false),
+                ("192.168.1.52", Some("Sonos"), false, false),
+                ("192.168.1.77", None, true, false),
+            ]
+        );
+        assert_eq!(
+            inventory.devices[1].mac.as_deref(),
+            Some("b8:27:eb:4f:20:9a")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_interference_replay() {
+        let analysis = collect_interference(replay("linux-home"), None, default_internet_targets())
+            .await
+            .unwrap();
+
+        assert_eq!(analysis.snr_db, Some(42));
+        assert_eq!(analysis.snr_quality, "Excellent");
+        assert_eq!(analysis.current_channel, Some(36));
+        assert_eq!(analysis.current_frequency_ghz, Some(5.0));
+        assert_eq!(analysis.nearby_networks.len(), 3);
+        assert_eq!(analysis.same_channel_count, 1);
+        assert_eq!(analysis.overlapping_count, 0);
+
+        // Size probes need live sockets, so replay only reports the interface MTU.
+        let hosts: Vec<_> = analysis.path_mtu.iter().map(|p| p.host.as_str()).collect();
+        assert_eq!(hosts, vec!["192.168.1.1", "1.1.1.1", "8.8.8.8", "9.9.9.9"]);
+        assert!(analysis
+            .path_mtu
+            .iter()
+            .all(|p| p.interface_mtu == Some(1500) && p.path_mtu.is_none()));
+    }
+}
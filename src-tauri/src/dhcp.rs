@@ -0,0 +1,725 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::interfaces::NetworkInterface;
+use crate::network::parse_ip;
+use crate::runner::CommandRunner;
+
+static IP_ADDR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\d+:\s+([^\s@]+)(?:@\S+)?\s+inet6?\s+([0-9A-Fa-f.:]+)/(\d+)(.*)$").unwrap()
+});
+static VALID_LFT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"valid_lft (\d+)sec").unwrap());
+static IFCONFIG_HEADER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([A-Za-z0-9.]+):\s*flags=").unwrap());
+static IFCONFIG_INET_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s+inet\s+([\d.]+)\s+netmask\s+0x([0-9A-Fa-f]{8})").unwrap());
+static IFCONFIG_INET6_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s+inet6\s+(\S+)\s+prefixlen\s+(\d+)(.*)$").unwrap());
+static DHCLIENT_LEASE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)lease\s*\{(.*?)\}").unwrap());
+static GETPACKET_ADDRESS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^yiaddr = (\S+)").unwrap());
+static GETPACKET_SERVER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^server_identifier \(ip\): (\S+)").unwrap());
+static GETPACKET_LEASE_TIME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^lease_time \(uint32\): 0x([0-9A-Fa-f]+)").unwrap());
+static GETPACKET_ROUTER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^router \(ip_mult\): \{([^,}\s]+)").unwrap());
+
+const DHCLIENT_LEASE_DIRS: [&str; 2] = ["/var/lib/dhcp", "/var/lib/dhclient"];
+const NETWORKMANAGER_LEASE_DIR: &str = "/var/lib/NetworkManager";
+const NETWORKD_LEASE_DIR: &str = "/run/systemd/netif/leases";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterfaceAddress {
+    pub interface: String,
+    pub address: String,
+    pub prefix_len: u8,
+    // Handed out by DHCP or SLAAC, so it expires unless renewed.
+    pub dynamic: bool,
+    pub valid_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LeaseSource {
+    Dhclient,
+    NetworkManager,
+    Networkd,
+    Ipconfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DhcpLease {
+    pub source: LeaseSource,
+    pub address: Option<String>,
+    pub server: Option<String>,
+    pub router: Option<String>,
+    pub lease_time_secs: Option<u64>,
+    pub remaining_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubnetOverlap {
+    pub subnet: String,
+    pub other_interface: String,
+    pub other_subnet: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Addressing {
+    pub ipv4: Vec<InterfaceAddress>,
+    pub ipv6: Vec<InterfaceAddress>,
+    pub lease: Option<DhcpLease>,
+    pub gateway: Option<String>,
+    // Only 169.254.x.x addresses, which means no DHCP server answered.
+    pub self_assigned: bool,
+    pub missing_gateway: bool,
+    pub overlaps: Vec<SubnetOverlap>,
+    pub issues: Vec<String>,
+}
+
+pub fn inspect(
+    runner: &dyn CommandRunner,
+    interface: Option<&NetworkInterface>,
+    gateway: Option<&str>,
+) -> Addressing {
+    let Some(interface) = interface else {
+        log::debug!("dhcp::inspect: no interface, skipping");
+        return Addressing::default();
+    };
+    let name = interface.name.as_str();
+
+    let addresses = list_addresses(runner);
+    let (ipv4, ipv6): (Vec<_>, Vec<_>) = addresses
+        .iter()
+        .filter(|a| a.interface == name)
+        .cloned()
+        .partition(|a| a.address.parse::<Ipv4Addr>().is_ok());
+
+    let mut lease = if cfg!(target_os = "linux") {
+        read_linux_lease(runner, name, now_secs())
+    } else {
+        read_getpacket_lease(runner, name)
+    };
+    // The kernel counts down the lease on the address itself, which stays
+    // right even when the lease file is stale.
+    if let Some(lease) = lease.as_mut() {
+        if let Some(valid_secs) = ipv4
+            .iter()
+            .find(|a| lease.address.as_deref() == Some(a.address.as_str()))
+            .and_then(|a| a.valid_secs)
+        {
+            lease.remaining_secs = Some(valid_secs);
+        }
+    }
+    let lease = lease.filter(|lease| {
+        ipv4.is_empty()
+            || lease
+                .address
+                .as_ref()
+                .is_none_or(|address| ipv4.iter().any(|a| &a.address == address))
+    });
+
+    let mut addressing = Addressing {
+        overlaps: find_overlaps(name, &addresses),
+        ipv4,
+        ipv6,
+        lease,
+        gateway: gateway.map(str::to_string),
+        ..Default::default()
+    };
+    assess(name, &mut addressing);
+    log::debug!(
+        "dhcp::inspect: {} has {} IPv4 and {} IPv6 addresses, lease from {:?}, issues: {}",
+        name,
+        addressing.ipv4.len(),
+        addressing.ipv6.len(),
+        addressing.lease.as_ref().map(|l| l.source),
+        addressing.issues.len()
+    );
+    addressing
+}
+
+pub fn list_addresses(runner: &dyn CommandRunner) -> Vec<InterfaceAddress> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "linux") {
+        ("ip", &["-o", "addr", "show"])
+    } else {
+        ("ifconfig", &[])
+    };
+    match runner.run(program, args) {
+        Ok(output) if output.success && cfg!(target_os = "linux") => parse_ip_addr(&output.stdout),
+        Ok(output) if output.success => parse_ifconfig_addresses(&output.stdout),
+        Ok(_) => {
+            log::error!("dhcp::list_addresses: {} failed", program);
+            Vec::new()
+        }
+        Err(e) => {
+            log::error!("dhcp::list_addresses: failed to run {}: {}", program, e);
+            Vec::new()
+        }
+    }
+}
+
+fn parse_ip_addr(output: &str) -> Vec<InterfaceAddress> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let caps = IP_ADDR_RE.captures(line)?;
+            let flags = &caps[4];
+            Some(InterfaceAddress {
+                interface: caps[1].to_string(),
+                address: caps[2].to_string(),
+                prefix_len: caps[3].parse().ok()?,
+                dynamic: flags.split_whitespace().any(|flag| flag == "dynamic"),
+                valid_secs: VALID_LFT_RE
+                    .captures(flags)
+                    .and_then(|caps| caps[1].parse().ok()),
+            })
+        })
+        .collect()
+}
+
+fn parse_ifconfig_addresses(output: &str) -> Vec<InterfaceAddress> {
+    let mut addresses = Vec::new();
+    let mut interface = None;
+
+    for line in output.lines() {
+        if let Some(caps) = IFCONFIG_HEADER_RE.captures(line) {
+            interface = Some(caps[1].to_string());
+            continue;
+        }
+        let Some(interface) = interface.clone() else {
+            continue;
+        };
+        if let Some(caps) = IFCONFIG_INET_RE.captures(line) {
+            let Ok(netmask) = u32::from_str_radix(&caps[2], 16) else {
+                continue;
+            };
+            addresses.push(InterfaceAddress {
+                interface,
+                address: caps[1].to_string(),
+                prefix_len: netmask.count_ones() as u8,
+                dynamic: false,
+                valid_secs: None,
+            });
+        } else if let Some(caps) = IFCONFIG_INET6_RE.captures(line) {
+            let Some(address) = parse_ip(&caps[1]) else {
+                continue;
+            };
+            addresses.push(InterfaceAddress {
+                interface,
+                address: address.to_string(),
+                prefix_len: caps[2].parse().unwrap_or(128),
+                dynamic: caps[3].split_whitespace().any(|flag| flag == "autoconf"),
+                valid_secs: None,
+            });
+        }
+    }
+
+    addresses
+}
+
+// NetworkManager first, as it runs its own client or dhclient with its own
+// lease paths, then systemd-networkd, then a standalone dhclient.
+fn read_linux_lease(runner: &dyn CommandRunner, interface: &str, now: u64) -> Option<DhcpLease> {
+    #[cfg(target_os = "linux")]
+    if runner.native_probes() {
+        if let Some(uuid) = crate::networkmanager::get_device_state(interface)
+            .and_then(|state| state.connection_uuid)
+        {
+            let internal = format!(
+                "{}/internal-{}-{}.lease",
+                NETWORKMANAGER_LEASE_DIR, uuid, interface
+            );
+            if let Ok(content) = runner.read_file(&internal) {
+                return Some(parse_key_value_lease(&content, LeaseSource::NetworkManager));
+            }
+            let dhclient = format!(
+                "{}/dhclient-{}-{}.lease",
+                NETWORKMANAGER_LEASE_DIR, uuid, interface
+            );
+            if let Some(mut lease) = runner
+                .read_file(&dhclient)
+                .ok()
+                .and_then(|content| parse_dhclient_leases(&content, interface, now))
+            {
+                lease.source = LeaseSource::NetworkManager;
+                return Some(lease);
+            }
+        }
+    }
+
+    if let Some(lease) = runner
+        .read_file(&format!("/sys/class/net/{}/ifindex", interface))
+        .ok()
+        .and_then(|index| {
+            runner
+                .read_file(&format!("{}/{}", NETWORKD_LEASE_DIR, index.trim()))
+                .ok()
+        })
+        .map(|content| parse_key_value_lease(&content, LeaseSource::Networkd))
+    {
+        return Some(lease);
+    }
+
+    DHCLIENT_LEASE_DIRS
+        .iter()
+        .flat_map(|dir| {
+            [
+                format!("{}/dhclient.{}.leases", dir, interface),
+                format!("{}/dhclient-{}.leases", dir, interface),
+                format!("{}/dhclient.leases", dir),
+            ]
+        })
+        .find_map(|path| {
+            let content = runner.read_file(&path).ok()?;
+            log::debug!("dhcp::read_linux_lease: reading {}", path);
+            parse_dhclient_leases(&content, interface, now)
+        })
+}
+
+// The KEY=VALUE format shared by systemd-networkd and NetworkManager's
+// internal client. It has no timestamps, so the remaining time comes from the
+// address instead.
+fn parse_key_value_lease(content: &str, source: LeaseSource) -> DhcpLease {
+    let value = |key: &str| {
+        content.lines().find_map(|line| {
+            let (k, v) = line.split_once('=')?;
+            (k.trim() == key).then(|| v.trim().to_string())
+        })
+    };
+    DhcpLease {
+        source,
+        address: value("ADDRESS"),
+        server: value("SERVER_ADDRESS"),
+        router: value("ROUTER").and_then(|r| r.split_whitespace().next().map(str::to_string)),
+        lease_time_secs: value("LIFETIME").and_then(|v| v.parse().ok()),
+        remaining_secs: None,
+    }
+}
+
+// dhclient appends a block per lease, so the last one for the interface is
+// the current one.
+fn parse_dhclient_leases(content: &str, interface: &str, now: u64) -> Option<DhcpLease> {
+    DHCLIENT_LEASE_RE
+        .captures_iter(content)
+        .filter_map(|caps| {
+            let mut lease = DhcpLease {
+                source: LeaseSource::Dhclient,
+                address: None,
+                server: None,
+                router: None,
+                lease_time_secs: None,
+                remaining_secs: None,
+            };
+            let mut lease_interface = None;
+            for line in caps[1].lines() {
+                let line = line.split(';').next().unwrap_or_default().trim();
+                let Some((key, value)) = line.split_once(' ') else {
+                    continue;
+                };
+                let value = value.trim();
+                match key {
+                    "interface" => lease_interface = Some(value.trim_matches('"').to_string()),
+                    "fixed-address" => lease.address = Some(value.to_string()),
+                    "expire" => {
+                        lease.remaining_secs =
+                            parse_dhclient_time(value).map(|expiry| expiry.saturating_sub(now))
+                    }
+                    "option" => {
+                        let Some((option, value)) = value.split_once(' ') else {
+                            continue;
+                        };
+                        match option {
+                            "routers" => {
+                                lease.router = value.split(',').next().map(|r| r.trim().to_string())
+                            }
+                            "dhcp-server-identifier" => lease.server = Some(value.to_string()),
+                            "dhcp-lease-time" => lease.lease_time_secs = value.parse().ok(),
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            lease_interface
+                .as_deref()
+                .is_none_or(|name| name == interface)
+                .then_some(lease)
+        })
+        .last()
+}
+
+// Either "epoch <secs>" or "<weekday> YYYY/MM/DD HH:MM:SS" in UTC.
+fn parse_dhclient_time(value: &str) -> Option<u64> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    match parts.as_slice() {
+        ["epoch", secs, ..] => secs.parse().ok(),
+        [_, date, time, ..] => {
+            let date: Vec<i64> = date
+                .split('/')
+                .map(|p| p.parse().ok())
+                .collect::<Option<_>>()?;
+            let time: Vec<i64> = time
+                .split(':')
+                .map(|p| p.parse().ok())
+                .collect::<Option<_>>()?;
+            let ([year, month, day], [hour, minute, second]) = (date.as_slice(), time.as_slice())
+            else {
+                return None;
+            };
+            let secs =
+                days_from_civil(*year, *month, *day) * 86400 + hour * 3600 + minute * 60 + second;
+            u64::try_from(secs).ok()
+        }
+        _ => None,
+    }
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn read_getpacket_lease(runner: &dyn CommandRunner, interface: &str) -> Option<DhcpLease> {
+    let output = runner.run("ipconfig", &["getpacket", interface]).ok()?;
+    if !output.success {
+        log::debug!(
+            "dhcp::read_getpacket_lease: no DHCP packet for {}",
+            interface
+        );
+        return None;
+    }
+    Some(parse_getpacket(&output.stdout))
+}
+
+fn parse_getpacket(output: &str) -> DhcpLease {
+    let capture = |re: &Regex| re.captures(output).map(|caps| caps[1].to_string());
+    DhcpLease {
+        source: LeaseSource::Ipconfig,
+        address: capture(&GETPACKET_ADDRESS_RE),
+        server: capture(&GETPACKET_SERVER_RE),
+        router: capture(&GETPACKET_ROUTER_RE),
+        lease_time_secs: capture(&GETPACKET_LEASE_TIME_RE)
+            .and_then(|hex| u64::from_str_radix(&hex, 16).ok()),
+        remaining_secs: None,
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// IPv4 subnets on the interface that another interface also claims, as with
+// a VPN or container bridge that reuses the home network's range.
+fn find_overlaps(interface: &str, addresses: &[InterfaceAddress]) -> Vec<SubnetOverlap> {
+    let subnets: Vec<(&str, Ipv4Addr, u8)> = addresses
+        .iter()
+        .filter_map(|a| {
+            let ip: Ipv4Addr = a.address.parse().ok()?;
+            (!ip.is_loopback() && !ip.is_link_local()).then_some((
+                a.interface.as_str(),
+                network(ip, a.prefix_len),
+                a.prefix_len,
+            ))
+        })
+        .collect();
+
+    let mut overlaps = Vec::new();
+    for &(_, net, prefix) in subnets.iter().filter(|s| s.0 == interface) {
+        for &(other, other_net, other_prefix) in subnets.iter().filter(|s| s.0 != interface) {
+            let shorter = prefix.min(other_prefix);
+            if network(net, shorter) == network(other_net, shorter) {
+                overlaps.push(SubnetOverlap {
+                    subnet: format!("{}/{}", net, prefix),
+                    other_interface: other.to_string(),
+                    other_subnet: format!("{}/{}", other_net, other_prefix),
+                });
+            }
+        }
+    }
+    overlaps
+}
+
+fn network(ip: Ipv4Addr, prefix_len: u8) -> Ipv4Addr {
+    let mask = u32::MAX
+        .checked_shl(32 - prefix_len.min(32) as u32)
+        .unwrap_or(0);
+    Ipv4Addr::from(u32::from(ip) & mask)
+}
+
+fn assess(interface: &str, addressing: &mut Addressing) {
+    let routable: Vec<&InterfaceAddress> = addressing
+        .ipv4
+        .iter()
+        .filter(|a| {
+            a.address
+                .parse::<Ipv4Addr>()
+                .is_ok_and(|ip| !ip.is_link_local())
+        })
+        .collect();
+
+    addressing.self_assigned = !addressing.ipv4.is_empty() && routable.is_empty();
+    if addressing.self_assigned {
+        addressing.issues.push(format!(
+            "{} gave itself {} because no DHCP server answered. Reconnect, or restart the router if it keeps happening",
+            interface, addressing.ipv4[0].address
+        ));
+    }
+
+    let ipv4_gateway = addressing
+        .gateway
+        .as_deref()
+        .is_some_and(|gateway| gateway.parse::<Ipv4Addr>().is_ok());
+    addressing.missing_gateway = !routable.is_empty() && !ipv4_gateway;
+    if addressing.missing_gateway {
+        addressing.issues.push(format!(
+            "{} has the address {} but no default gateway, so nothing beyond the local network is reachable",
+            interface, routable[0].address
+        ));
+    }
+
+    if let Some(DhcpLease {
+        lease_time_secs: Some(lease_time),
+        remaining_secs: Some(remaining),
+        ..
+    }) = addressing.lease
+    {
+        // Clients renew at half the lease and rebind at seven eighths, so
+        // less than an eighth left means both attempts went unanswered.
+        if remaining == 0 {
+            addressing
+                .issues
+                .push("The DHCP lease has expired and was not renewed".to_string());
+        } else if remaining.saturating_mul(8) < lease_time {
+            addressing.issues.push(format!(
+                "The DHCP lease runs out in {} minutes and renewing it has failed; the DHCP server may not be answering",
+                remaining.div_ceil(60)
+            ));
+        }
+    }
+
+    for overlap in &addressing.overlaps {
+        addressing.issues.push(format!(
+            "{} on {} overlaps {} on {}, so some local addresses are sent to the wrong interface",
+            overlap.subnet, interface, overlap.other_subnet, overlap.other_interface
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IP_ADDR: &str = "\
+1: lo    inet 127.0.0.1/8 scope host lo\\       valid_lft forever preferred_lft forever
+2: enp0s31f6    inet 192.168.254.23/24 brd 192.168.254.255 scope global dynamic noprefixroute enp0s31f6\\       valid_lft 3412sec preferred_lft 3412sec
+3: wlp2s0    inet 192.168.1.23/24 brd 192.168.1.255 scope global dynamic noprefixroute wlp2s0\\       valid_lft 80234sec preferred_lft 80234sec
+3: wlp2s0    inet6 fe80::8ec6:81ff:fe4a:52e1/64 scope link noprefixroute \\       valid_lft forever preferred_lft forever
+7: wg0@NONE    inet 192.168.0.2/16 scope global wg0\\       valid_lft forever preferred_lft forever
+";
+
+    fn address(interface: &str, address: &str, prefix_len: u8) -> InterfaceAddress {
+        InterfaceAddress {
+            interface: interface.to_string(),
+            address: address.to_string(),
+            prefix_len,
+            dynamic: false,
+            valid_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_ip_addr() {
+        let addresses = parse_ip_addr(IP_ADDR);
+        assert_eq!(addresses.len(), 5);
+        assert_eq!(
+            addresses[2],
+            InterfaceAddress {
+                interface: "wlp2s0".to_string(),
+                address: "192.168.1.23".to_string(),
+                prefix_len: 24,
+                dynamic: true,
+                valid_secs: Some(80234),
+            }
+        );
+        assert_eq!(addresses[3].address, "fe80::8ec6:81ff:fe4a:52e1");
+        assert!(!addresses[3].dynamic);
+        assert_eq!(addresses[3].valid_secs, None);
+        assert_eq!(addresses[4].interface, "wg0");
+    }
+
+    #[test]
+    fn test_parse_ifconfig_addresses() {
+        let output = "\
+lo0: flags=8049<UP,LOOPBACK,RUNNING,MULTICAST> mtu 16384
+\tinet 127.0.0.1 netmask 0xff000000
+en0: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500
+\tether a0:b1:c2:d3:e4:f5
+\tinet6 fe80::1c2b:3d4e:5f60:7182%en0 prefixlen 64 secured scopeid 0x6
+\tinet 192.168.1.23 netmask 0xffffff00 broadcast 192.168.1.255
+\tinet6 2001:db8::1c2b prefixlen 64 autoconf secured
+\tstatus: active
+";
+        let addresses = parse_ifconfig_addresses(output);
+        assert_eq!(addresses.len(), 4);
+        assert_eq!(addresses[0], address("lo0", "127.0.0.1", 8));
+        assert_eq!(addresses[1].address, "fe80::1c2b:3d4e:5f60:7182");
+        assert_eq!(addresses[2], address("en0", "192.168.1.23", 24));
+        assert!(addresses[3].dynamic);
+    }
+
+    #[test]
+    fn test_parse_dhclient_leases() {
+        let content = r#"
+lease {
+  interface "wlp2s0";
+  fixed-address 192.168.1.50;
+  option dhcp-lease-time 3600;
+  expire 4 2026/10/15 08:00:00;
+}
+lease {
+  interface "eth0";
+  fixed-address 10.0.0.9;
+}
+lease {
+  interface "wlp2s0";
+  fixed-address 192.168.1.23;
+  option subnet-mask 255.255.255.0;
+  option routers 192.168.1.1,192.168.1.2;
+  option dhcp-lease-time 86400;
+  option dhcp-server-identifier 192.168.1.1;
+  renew 6 2026/10/17 19:02:11;
+  expire 0 2026/10/18 07:12:44;
+}
+"#;
+        let now = 1792307564 - 600;
+        let lease = parse_dhclient_leases(content, "wlp2s0", now).unwrap();
+        assert_eq!(
+            lease,
+            DhcpLease {
+                source: LeaseSource::Dhclient,
+                address: Some("192.168.1.23".to_string()),
+                server: Some("192.168.1.1".to_string()),
+                router: Some("192.168.1.1".to_string()),
+                lease_time_secs: Some(86400),
+                remaining_secs: Some(600),
+            }
+        );
+        assert!(parse_dhclient_leases(content, "wlan1", now).is_none());
+
+        assert_eq!(parse_dhclient_time("epoch 1792307564"), Some(1792307564));
+        assert_eq!(
+            parse_dhclient_time("2 2000/02/29 00:00:00"),
+            Some(951782400)
+        );
+        assert_eq!(parse_dhclient_time("never"), None);
+    }
+
+    #[test]
+    fn test_parse_key_value_lease() {
+        let content = "\
+# This is private data. Do not parse.
+ADDRESS=192.168.1.23
+NETMASK=255.255.255.0
+ROUTER=192.168.1.1 192.168.1.2
+SERVER_ADDRESS=192.168.1.1
+T1=43200
+LIFETIME=86400
+";
+        let lease = parse_key_value_lease(content, LeaseSource::Networkd);
+        assert_eq!(lease.address.as_deref(), Some("192.168.1.23"));
+        assert_eq!(lease.router.as_deref(), Some("192.168.1.1"));
+        assert_eq!(lease.server.as_deref(), Some("192.168.1.1"));
+        assert_eq!(lease.lease_time_secs, Some(86400));
+        assert_eq!(lease.remaining_secs, None);
+    }
+
+    #[test]
+    fn test_parse_getpacket() {
+        let output = "\
+op = BOOTREPLY
+yiaddr = 192.168.1.23
+siaddr = 0.0.0.0
+options:
+Options count is 6
+dhcp_message_type (uint8): ACK 0x5
+server_identifier (ip): 192.168.1.1
+lease_time (uint32): 0x15180
+subnet_mask (ip): 255.255.255.0
+router (ip_mult): {192.168.1.1}
+end (none):
+";
+        let lease = parse_getpacket(output);
+        assert_eq!(lease.source, LeaseSource::Ipconfig);
+        assert_eq!(lease.address.as_deref(), Some("192.168.1.23"));
+        assert_eq!(lease.server.as_deref(), Some("192.168.1.1"));
+        assert_eq!(lease.router.as_deref(), Some("192.168.1.1"));
+        assert_eq!(lease.lease_time_secs, Some(86400));
+    }
+
+    #[test]
+    fn test_find_overlaps() {
+        let addresses = parse_ip_addr(IP_ADDR);
+        let overlaps = find_overlaps("wlp2s0", &addresses);
+        assert_eq!(
+            overlaps,
+            vec![SubnetOverlap {
+                subnet: "192.168.1.0/24".to_string(),
+                other_interface: "wg0".to_string(),
+                other_subnet: "192.168.0.0/16".to_string(),
+            }]
+        );
+        assert!(find_overlaps("enp0s31f6", &addresses[..3]).is_empty());
+    }
+
+    #[test]
+    fn test_assess() {
+        let mut addressing = Addressing {
+            ipv4: vec![address("wlp2s0", "169.254.12.7", 16)],
+            ..Default::default()
+        };
+        assess("wlp2s0", &mut addressing);
+        assert!(addressing.self_assigned);
+        assert!(!addressing.missing_gateway);
+        assert!(addressing.issues[0].contains("169.254.12.7"));
+
+        let mut addressing = Addressing {
+            ipv4: vec![address("wlp2s0", "192.168.1.23", 24)],
+            gateway: Some("fe80::1".to_string()),
+            lease: Some(DhcpLease {
+                source: LeaseSource::Dhclient,
+                address: Some("192.168.1.23".to_string()),
+                server: None,
+                router: None,
+                lease_time_secs: Some(86400),
+                remaining_secs: Some(600),
+            }),
+            ..Default::default()
+        };
+        assess("wlp2s0", &mut addressing);
+        assert!(!addressing.self_assigned);
+        assert!(addressing.missing_gateway);
+        assert_eq!(addressing.issues.len(), 2);
+        assert!(addressing.issues[1].contains("10 minutes"));
+
+        let mut addressing = Addressing {
+            ipv4: vec![address("wlp2s0", "192.168.1.23", 24)],
+            gateway: Some("192.168.1.1".to_string()),
+            ..Default::default()
+        };
+        assess("wlp2s0", &mut addressing);
+        assert!(addressing.issues.is_empty());
+    }
+}
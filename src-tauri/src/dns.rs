@@ -0,0 +1,761 @@
+use rustls::RootCertStore;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::http;
+
+const DNS_PORT: u16 = 53;
+const DOT_PORT: u16 = 853;
+const DNS_MESSAGE_TYPE: &str = "application/dns-message";
+const HEADER_LEN: usize = 12;
+const MAX_LABEL_LEN: usize = 63;
+const MAX_UDP_RESPONSE: usize = 4096;
+const CLASS_IN: u16 = 1;
+const FLAG_QR: u16 = 0x8000;
+const FLAG_TC: u16 = 0x0200;
+const FLAG_RD: u16 = 0x0100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RecordType {
+    A,
+    Aaaa,
+    Https,
+}
+
+impl RecordType {
+    fn code(self) -> u16 {
+        match self {
+            RecordType::A => 1,
+            RecordType::Aaaa => 28,
+            RecordType::Https => 65,
+        }
+    }
+}
+
+// How queries reach a resolver. Classic DNS is `Udp`, which becomes `Tcp` for
+// a single query when the answer was truncated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DnsTransport {
+    #[default]
+    Udp,
+    Tcp,
+    Tls,
+    Https,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DnsServer {
+    Plain(SocketAddr),
+    // DNS over TLS (RFC 7858); the certificate must match `server_name`.
+    Tls {
+        addr: SocketAddr,
+        server_name: String,
+    },
+    // DNS over HTTPS (RFC 8484).
+    Https(String),
+}
+
+impl DnsServer {
+    pub fn transport(&self) -> DnsTransport {
+        match self {
+            DnsServer::Plain(_) => DnsTransport::Udp,
+            DnsServer::Tls { .. } => DnsTransport::Tls,
+            DnsServer::Https(_) => DnsTransport::Https,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsResponse {
+    pub rcode: u8,
+    pub answer_count: u16,
+    pub transport: DnsTransport,
+    // Opening the connection, TLS handshake included. None over UDP.
+    pub connect_ms: Option<f64>,
+    pub query_ms: f64,
+    pub latency_ms: f64,
+}
+
+impl DnsResponse {
+    pub fn rcode_name(&self) -> String {
+        match self.rcode {
+            0 => "NOERROR".to_string(),
+            1 => "FORMERR".to_string(),
+            2 => "SERVFAIL".to_string(),
+            3 => "NXDOMAIN".to_string(),
+            4 => "NOTIMP".to_string(),
+            5 => "REFUSED".to_string(),
+            other => format!("RCODE{}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ResponseHeader {
+    id: u16,
+    truncated: bool,
+    rcode: u8,
+    answer_count: u16,
+}
+
+// Accepts a bare address or name, which uses port 53, or an explicit
+// `addr:port` such as a local stub resolver.
+pub fn resolve_server(server: &str) -> io::Result<SocketAddr> {
+    if let Ok(addr) = server.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+    (server, DNS_PORT).to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("invalid DNS server {}", server),
+        )
+    })
+}
+
+// Accepts everything `resolve_server` does, plus `https://` URLs for DoH and
+// `tls://host[:port]` or systemd-resolved's `addr#name` for DoT.
+pub fn parse_server(server: &str) -> io::Result<DnsServer> {
+    if server.starts_with("https://") {
+        return Ok(DnsServer::Https(server.to_string()));
+    }
+
+    let tls = server.strip_prefix("tls://");
+    let spec = tls.unwrap_or(server);
+    let (host, server_name) = match spec.split_once('#') {
+        Some((host, name)) => (host, Some(name)),
+        None if tls.is_some() => (spec, None),
+        None => return resolve_server(server).map(DnsServer::Plain),
+    };
+
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid DNS server {}", server),
+        )
+    };
+    let (addr, host_name) = match host.parse::<SocketAddr>() {
+        Ok(addr) => (addr, addr.ip().to_string()),
+        Err(_) => {
+            let (name, port) = match host.rsplit_once(':') {
+                Some((name, port)) if !name.contains(':') => {
+                    (name, port.parse().map_err(|_| invalid())?)
+                }
+                _ => (host.trim_start_matches('[').trim_end_matches(']'), DOT_PORT),
+            };
+            let addr = (name, port).to_socket_addrs()?.next().ok_or_else(invalid)?;
+            (addr, name.to_string())
+        }
+    };
+    let server_name = server_name.map_or(host_name, str::to_string);
+    Ok(DnsServer::Tls { addr, server_name })
+}
+
+pub fn lookup(
+    server: &DnsServer,
+    name: &str,
+    record_type: RecordType,
+    timeout: Duration,
+) -> io::Result<DnsResponse> {
+    lookup_with_roots(server, name, record_type, timeout, http::default_roots())
+}
+
+fn lookup_with_roots(
+    server: &DnsServer,
+    name: &str,
+    record_type: RecordType,
+    timeout: Duration,
+    roots: RootCertStore,
+) -> io::Result<DnsResponse> {
+    match server {
+        DnsServer::Plain(addr) => query(*addr, name, record_type, timeout),
+        DnsServer::Tls { addr, server_name } => {
+            query_tls(*addr, server_name, name, record_type, timeout, roots)
+        }
+        DnsServer::Https(url) => query_https(url, name, record_type, timeout, roots),
+    }
+}
+
+// RFC 7858: the same length-prefixed messages as over TCP, inside TLS.
+fn query_tls(
+    addr: SocketAddr,
+    server_name: &str,
+    name: &str,
+    record_type: RecordType,
+    timeout: Duration,
+    roots: RootCertStore,
+) -> io::Result<DnsResponse> {
+    let id = query_id();
+    let packet = build_query(id, name, record_type)?;
+    let start = Instant::now();
+    let stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let mut stream = http::tls_handshake(stream, server_name, roots)?;
+    let connected = Instant::now();
+
+    let header = exchange_framed(&mut stream, &packet, id, addr)?;
+    let response = timed_response(header, DnsTransport::Tls, start, Some(connected));
+    log_response(name, record_type, &addr.to_string(), &response);
+    Ok(response)
+}
+
+// RFC 8484 POST of a wire-format query. The ID is zero so that caches along
+// the way can share answers.
+fn query_https(
+    url: &str,
+    name: &str,
+    record_type: RecordType,
+    timeout: Duration,
+    roots: RootCertStore,
+) -> io::Result<DnsResponse> {
+    let packet = build_query(0, name, record_type)?;
+    let start = Instant::now();
+    let conn = http::connect(url, timeout, roots)?;
+    let connected = Instant::now();
+
+    let reply = conn.post(DNS_MESSAGE_TYPE, &packet)?;
+    if reply.status != 200 {
+        return Err(io::Error::other(format!(
+            "{} answered HTTP {}",
+            url, reply.status
+        )));
+    }
+    let header = match parse_response_header(&reply.body) {
+        Some(header) if header.id == 0 => header,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("malformed DoH response from {}", url),
+            ))
+        }
+    };
+
+    let response = timed_response(header, DnsTransport::Https, start, Some(connected));
+    log_response(name, record_type, url, &response);
+    Ok(response)
+}
+
+pub fn query(
+    server: SocketAddr,
+    name: &str,
+    record_type: RecordType,
+    timeout: Duration,
+) -> io::Result<DnsResponse> {
+    let id = query_id();
+    let packet = build_query(id, name, record_type)?;
+    let start = Instant::now();
+
+    let header = query_udp(server, &packet, id, timeout)?;
+    let response = if header.truncated {
+        log::debug!(
+            "dns::query: truncated UDP response from {}, retrying over TCP",
+            server
+        );
+        let remaining = timeout.saturating_sub(start.elapsed());
+        let (header, connected) = query_tcp(server, &packet, id, remaining)?;
+        timed_response(header, DnsTransport::Tcp, start, Some(connected))
+    } else {
+        timed_response(header, DnsTransport::Udp, start, None)
+    };
+    log_response(name, record_type, &server.to_string(), &response);
+    Ok(response)
+}
+
+// Splits the time since `start` at the moment the connection was ready, if
+// one had to be opened.
+fn timed_response(
+    header: ResponseHeader,
+    transport: DnsTransport,
+    start: Instant,
+    connected: Option<Instant>,
+) -> DnsResponse {
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    let connect = connected.map(|at| at.duration_since(start));
+    let latency = start.elapsed();
+    DnsResponse {
+        rcode: header.rcode,
+        answer_count: header.answer_count,
+        transport,
+        connect_ms: connect.map(ms),
+        query_ms: ms(latency - connect.unwrap_or_default()),
+        latency_ms: ms(latency),
+    }
+}
+
+fn log_response(name: &str, record_type: RecordType, server: &str, response: &DnsResponse) {
+    log::debug!(
+        "dns::query: {} {:?} via {} over {:?} - {}, {} answers in {:.1}ms (connect: {:?}ms)",
+        name,
+        record_type,
+        server,
+        response.transport,
+        response.rcode_name(),
+        response.answer_count,
+        response.latency_ms,
+        response.connect_ms
+    );
+}
+
+fn query_udp(
+    server: SocketAddr,
+    packet: &[u8],
+    id: u16,
+    timeout: Duration,
+) -> io::Result<ResponseHeader> {
+    let bind_addr: SocketAddr = if server.is_ipv6() {
+        "[::]:0".parse().unwrap()
+    } else {
+        "0.0.0.0:0".parse().unwrap()
+    };
+    let socket = UdpSocket::bind(bind_addr)?;
+    socket.connect(server)?;
+    socket.send(packet)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; MAX_UDP_RESPONSE];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("no response from {}", server),
+            ));
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        let len = socket.recv(&mut buf)?;
+
+        // Ignore stray datagrams that don't answer this query.
+        match parse_response_header(&buf[..len]) {
+            Some(header) if header.id == id => return Ok(header),
+            _ => log::debug!("dns::query: ignoring unrelated datagram from {}", server),
+        }
+    }
+}
+
+fn query_tcp(
+    server: SocketAddr,
+    packet: &[u8],
+    id: u16,
+    timeout: Duration,
+) -> io::Result<(ResponseHeader, Instant)> {
+    if timeout.is_zero() {
+        return Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("no time left for TCP query to {}", server),
+        ));
+    }
+    let mut stream = TcpStream::connect_timeout(&server, timeout)?;
+    let connected = Instant::now();
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let header = exchange_framed(&mut stream, packet, id, server)?;
+    Ok((header, connected))
+}
+
+// One query over a stream, each message prefixed with its length as over TCP
+// and TLS.
+fn exchange_framed(
+    stream: &mut (impl Read + Write),
+    packet: &[u8],
+    id: u16,
+    server: SocketAddr,
+) -> io::Result<ResponseHeader> {
+    let mut framed = (packet.len() as u16).to_be_bytes().to_vec();
+    framed.extend_from_slice(packet);
+    stream.write_all(&framed)?;
+    stream.flush()?;
+
+    let mut len_buf = [0u8; 2];
+    stream.read_exact(&mut len_buf)?;
+    let mut buf = vec![0u8; u16::from_be_bytes(len_buf) as usize];
+    stream.read_exact(&mut buf)?;
+
+    match parse_response_header(&buf) {
+        Some(header) if header.id == id => Ok(header),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("malformed response from {}", server),
+        )),
+    }
+}
+
+fn query_id() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    (nanos ^ std::process::id()) as u16
+}
+
+fn build_query(id: u16, name: &str, record_type: RecordType) -> io::Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(HEADER_LEN + name.len() + 6);
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&FLAG_RD.to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes());
+    packet.extend_from_slice(&[0; 6]);
+    encode_name(&mut packet, name)?;
+    packet.extend_from_slice(&record_type.code().to_be_bytes());
+    packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(packet)
+}
+
+// Appends `name` as uncompressed labels.
+pub(crate) fn encode_name(packet: &mut Vec<u8>, name: &str) -> io::Result<()> {
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > MAX_LABEL_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid query name {}", name),
+            ));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    Ok(())
+}
+
+fn parse_response_header(packet: &[u8]) -> Option<ResponseHeader> {
+    if packet.len() < HEADER_LEN {
+        return None;
+    }
+    let read_u16 = |offset: usize| u16::from_be_bytes([packet[offset], packet[offset + 1]]);
+    let flags = read_u16(2);
+    if flags & FLAG_QR == 0 {
+        return None;
+    }
+
+    Some(ResponseHeader {
+        id: read_u16(0),
+        truncated: flags & FLAG_TC != 0,
+        rcode: (flags & 0x000f) as u8,
+        answer_count: read_u16(6),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::tests::{server_config, trusted_roots};
+    use rustls::{ServerConnection, StreamOwned};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn stub_response(query: &[u8], flags: u16, answer_count: u16) -> Vec<u8> {
+        let mut response = query.to_vec();
+        response[2..4].copy_from_slice(&(FLAG_QR | FLAG_RD | flags).to_be_bytes());
+        response[6..8].copy_from_slice(&answer_count.to_be_bytes());
+        response
+    }
+
+    fn spawn_udp_stub(flags: u16, answer_count: u16) -> (SocketAddr, thread::JoinHandle<Vec<u8>>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        let handle = thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            // A stray datagram first, which the client must skip.
+            let (len, peer) = socket.recv_from(&mut buf).unwrap();
+            let query = buf[..len].to_vec();
+            let mut stray = stub_response(&query, 0, 9);
+            stray[0] ^= 0xff;
+            socket.send_to(&stray, peer).unwrap();
+            socket
+                .send_to(&stub_response(&query, flags, answer_count), peer)
+                .unwrap();
+            query
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn test_build_query() {
+        let packet = build_query(0xbeef, "google.com.", RecordType::Aaaa).unwrap();
+        assert_eq!(
+            packet,
+            vec![
+                0xbe, 0xef, 0x01, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 6, b'g',
+                b'o', b'o', b'g', b'l', b'e', 3, b'c', b'o', b'm', 0, 0x00, 0x1c, 0x00, 0x01,
+            ]
+        );
+
+        assert!(build_query(1, "bad..name", RecordType::A).is_err());
+        assert!(build_query(1, &"a".repeat(64), RecordType::A).is_err());
+    }
+
+    #[test]
+    fn test_parse_response_header() {
+        let query = build_query(7, "example.com", RecordType::A).unwrap();
+        assert_eq!(parse_response_header(&query), None);
+
+        let response = stub_response(&query, FLAG_TC | 3, 0);
+        assert_eq!(
+            parse_response_header(&response),
+            Some(ResponseHeader {
+                id: 7,
+                truncated: true,
+                rcode: 3,
+                answer_count: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_server() {
+        assert_eq!(
+            resolve_server("1.1.1.1").unwrap(),
+            "1.1.1.1:53".parse().unwrap()
+        );
+        assert_eq!(
+            resolve_server("2606:4700:4700::1111").unwrap(),
+            "[2606:4700:4700::1111]:53".parse().unwrap()
+        );
+        assert_eq!(
+            resolve_server("127.0.0.1:5353").unwrap(),
+            "127.0.0.1:5353".parse().unwrap()
+        );
+    }
+
+    // Accepts one TLS connection and hands the decrypted stream to `serve`.
+    fn spawn_tls_stub(
+        serve: impl FnOnce(&mut StreamOwned<ServerConnection, TcpStream>) + Send + 'static,
+    ) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (tcp, _) = listener.accept().unwrap();
+            let conn = ServerConnection::new(server_config()).unwrap();
+            let mut stream = StreamOwned::new(conn, tcp);
+            serve(&mut stream);
+            stream.conn.send_close_notify();
+            let _ = stream.flush();
+        });
+        addr
+    }
+
+    #[test]
+    fn test_parse_server() {
+        assert_eq!(
+            parse_server("1.1.1.1").unwrap(),
+            DnsServer::Plain("1.1.1.1:53".parse().unwrap())
+        );
+        assert_eq!(
+            parse_server("1.1.1.1#cloudflare-dns.com").unwrap(),
+            DnsServer::Tls {
+                addr: "1.1.1.1:853".parse().unwrap(),
+                server_name: "cloudflare-dns.com".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_server("tls://[2606:4700:4700::1111]:8853").unwrap(),
+            DnsServer::Tls {
+                addr: "[2606:4700:4700::1111]:8853".parse().unwrap(),
+                server_name: "2606:4700:4700::1111".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_server("tls://localhost:8853").unwrap(),
+            DnsServer::Tls {
+                addr: ("localhost", 8853)
+                    .to_socket_addrs()
+                    .unwrap()
+                    .next()
+                    .unwrap(),
+                server_name: "localhost".to_string(),
+            }
+        );
+        assert_eq!(
+            parse_server("https://dns.google/dns-query")
+                .unwrap()
+                .transport(),
+            DnsTransport::Https
+        );
+        assert!(parse_server("tls://localhost:dns").is_err());
+    }
+
+    #[test]
+    fn test_lookup_tls_stub() {
+        let addr = spawn_tls_stub(|stream| {
+            let mut len_buf = [0u8; 2];
+            stream.read_exact(&mut len_buf).unwrap();
+            let mut query = vec![0u8; u16::from_be_bytes(len_buf) as usize];
+            stream.read_exact(&mut query).unwrap();
+            let response = stub_response(&query, 0, 3);
+            stream
+                .write_all(&(response.len() as u16).to_be_bytes())
+                .unwrap();
+            stream.write_all(&response).unwrap();
+        });
+
+        let server = DnsServer::Tls {
+            addr,
+            server_name: "localhost".to_string(),
+        };
+        let response = lookup_with_roots(
+            &server,
+            "google.com",
+            RecordType::A,
+            Duration::from_secs(2),
+            trusted_roots(),
+        )
+        .unwrap();
+        assert_eq!(response.transport, DnsTransport::Tls);
+        assert_eq!(response.answer_count, 3);
+        let connect_ms = response.connect_ms.unwrap();
+        assert!((connect_ms + response.query_ms - response.latency_ms).abs() < 0.001);
+
+        // The certificate is for localhost, not for the address.
+        let addr = spawn_tls_stub(|_| {});
+        let server = DnsServer::Tls {
+            addr,
+            server_name: addr.ip().to_string(),
+        };
+        assert!(lookup_with_roots(
+            &server,
+            "google.com",
+            RecordType::A,
+            Duration::from_secs(2),
+            trusted_roots(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_lookup_https_stub() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let addr = spawn_tls_stub(move |stream| {
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            let body_start = loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(i) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break i + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&request[..body_start]).to_string();
+            let length: usize = head
+                .lines()
+                .find_map(|line| line.strip_prefix("Content-Length: "))
+                .unwrap()
+                .parse()
+                .unwrap();
+            while request.len() < body_start + length {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+
+            let query = &request[body_start..];
+            let response = stub_response(query, 3, 0);
+            let reply = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/dns-message\r\nContent-Length: {}\r\n\r\n",
+                response.len()
+            );
+            stream.write_all(reply.as_bytes()).unwrap();
+            stream.write_all(&response).unwrap();
+            tx.send((head, query.to_vec())).unwrap();
+        });
+
+        let server = DnsServer::Https(format!("https://localhost:{}/dns-query", addr.port()));
+        let response = lookup_with_roots(
+            &server,
+            "missing.example",
+            RecordType::Aaaa,
+            Duration::from_secs(2),
+            trusted_roots(),
+        )
+        .unwrap();
+        assert_eq!(response.transport, DnsTransport::Https);
+        assert_eq!(response.rcode_name(), "NXDOMAIN");
+        assert!(response.connect_ms.is_some());
+
+        let (head, query) = rx.recv().unwrap();
+        assert!(head.starts_with("POST /dns-query HTTP/1.1\r\n"));
+        assert!(head.contains("Content-Type: application/dns-message\r\n"));
+        assert_eq!(&query[..2], &[0, 0]);
+        assert_eq!(&query[query.len() - 4..], &[0x00, 0x1c, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_query_udp_stub() {
+        let (addr, handle) = spawn_udp_stub(0, 2);
+        let response = query(addr, "google.com", RecordType::A, Duration::from_secs(2)).unwrap();
+        assert_eq!(response.rcode_name(), "NOERROR");
+        assert_eq!(response.answer_count, 2);
+        assert_eq!(response.transport, DnsTransport::Udp);
+        assert_eq!(response.connect_ms, None);
+        assert!(response.latency_ms > 0.0);
+        assert_eq!(response.query_ms, response.latency_ms);
+
+        let received = handle.join().unwrap();
+        assert_eq!(&received[received.len() - 4..], &[0x00, 0x01, 0x00, 0x01]);
+
+        let (addr, handle) = spawn_udp_stub(3, 0);
+        let response = query(
+            addr,
+            "missing.example",
+            RecordType::Https,
+            Duration::from_secs(2),
+        )
+        .unwrap();
+        assert_eq!(response.rcode_name(), "NXDOMAIN");
+        assert_eq!(response.answer_count, 0);
+        let received = handle.join().unwrap();
+        assert_eq!(&received[received.len() - 4..], &[0x00, 0x41, 0x00, 0x01]);
+    }
+
+    #[test]
+    fn test_query_tcp_fallback() {
+        // TCP first: other tests hold far more TCP ports than UDP ones.
+        let tcp = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = tcp.local_addr().unwrap();
+        let udp = UdpSocket::bind(addr).unwrap();
+
+        let udp_handle = thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            let (len, peer) = udp.recv_from(&mut buf).unwrap();
+            udp.send_to(&stub_response(&buf[..len], FLAG_TC, 0), peer)
+                .unwrap();
+        });
+        let tcp_handle = thread::spawn(move || {
+            let (mut stream, _) = tcp.accept().unwrap();
+            let mut len_buf = [0u8; 2];
+            stream.read_exact(&mut len_buf).unwrap();
+            let mut query = vec![0u8; u16::from_be_bytes(len_buf) as usize];
+            stream.read_exact(&mut query).unwrap();
+            let response = stub_response(&query, 0, 12);
+            stream
+                .write_all(&(response.len() as u16).to_be_bytes())
+                .unwrap();
+            stream.write_all(&response).unwrap();
+        });
+
+        let response = query(addr, "google.com", RecordType::Aaaa, Duration::from_secs(2)).unwrap();
+        assert_eq!(response.transport, DnsTransport::Tcp);
+        assert!(response.connect_ms.is_some());
+        assert_eq!(response.rcode, 0);
+        assert_eq!(response.answer_count, 12);
+
+        udp_handle.join().unwrap();
+        tcp_handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_query_timeout() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let err = query(
+            silent.local_addr().unwrap(),
+            "google.com",
+            RecordType::A,
+            Duration::from_millis(100),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+        ));
+    }
+}
@@ -0,0 +1,501 @@
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::Duration;
+
+use crate::dns::{self, DnsTransport, RecordType};
+use crate::network::summarize_samples;
+
+const RCODE_NOERROR: u8 = 0;
+const RCODE_NXDOMAIN: u8 = 3;
+// Resolvers failing more lookups than this are never recommended.
+const MAX_FAILURE_PERCENT: f64 = 5.0;
+// Smaller gains per lookup are not worth changing resolver over.
+const MIN_GAIN_MS: f64 = 10.0;
+// Popular zones without wildcard records, so random names under them are
+// guaranteed to be missing.
+const BENCHMARK_NAMES: &[&str] = &[
+    "google.com",
+    "youtube.com",
+    "facebook.com",
+    "amazon.com",
+    "wikipedia.org",
+    "apple.com",
+    "microsoft.com",
+    "netflix.com",
+    "github.com",
+    "cloudflare.com",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolverTarget {
+    pub label: String,
+    pub server: String,
+}
+
+impl ResolverTarget {
+    fn new(label: &str, server: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            server: server.to_string(),
+        }
+    }
+}
+
+pub fn default_alternative_resolvers() -> Vec<ResolverTarget> {
+    vec![
+        ResolverTarget::new("Cloudflare", "1.1.1.1"),
+        ResolverTarget::new("Google", "8.8.8.8"),
+        ResolverTarget::new("Quad9", "9.9.9.9"),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NxdomainBehavior {
+    Correct,
+    // Missing names resolve to an address, usually an ISP search or ad page.
+    Hijacked,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolverBenchmark {
+    pub label: String,
+    pub server: String,
+    // Configured on this machine rather than an alternative.
+    pub system: bool,
+    pub cached_median_ms: Option<f64>,
+    pub cached_p95_ms: Option<f64>,
+    pub uncached_median_ms: Option<f64>,
+    pub uncached_p95_ms: Option<f64>,
+    pub queries: u32,
+    pub failures: u32,
+    pub failure_rate_percent: Option<f64>,
+    pub nxdomain: NxdomainBehavior,
+    pub transport: DnsTransport,
+}
+
+impl ResolverBenchmark {
+    fn new(label: &str, server: &str, system: bool) -> Self {
+        Self {
+            label: label.to_string(),
+            server: server.to_string(),
+            system,
+            cached_median_ms: None,
+            cached_p95_ms: None,
+            uncached_median_ms: None,
+            uncached_p95_ms: None,
+            queries: 0,
+            failures: 0,
+            failure_rate_percent: None,
+            nxdomain: NxdomainBehavior::Unknown,
+            transport: DnsTransport::Udp,
+        }
+    }
+
+    // Browsing mixes cached and uncached lookups, so both count equally.
+    fn score(&self) -> Option<f64> {
+        Some(self.cached_median_ms? + self.uncached_median_ms?)
+    }
+
+    fn reliable(&self) -> bool {
+        self.nxdomain != NxdomainBehavior::Hijacked
+            && self
+                .failure_rate_percent
+                .is_some_and(|rate| rate <= MAX_FAILURE_PERCENT)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DnsBenchmark {
+    pub resolvers: Vec<ResolverBenchmark>,
+    pub recommendation: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BenchmarkConfig {
+    pub names: Vec<String>,
+    pub cached_rounds: u32,
+    pub timeout: Duration,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            names: BENCHMARK_NAMES.iter().map(|n| n.to_string()).collect(),
+            cached_rounds: 2,
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+// The configured servers followed by the alternatives, without duplicates.
+pub fn candidates(system: &[String], alternatives: &[ResolverTarget]) -> Vec<ResolverBenchmark> {
+    let mut resolvers: Vec<ResolverBenchmark> = Vec::new();
+    for server in system {
+        if !resolvers.iter().any(|r| &r.server == server) {
+            resolvers.push(ResolverBenchmark::new("System", server, true));
+        }
+    }
+    for alternative in alternatives {
+        match resolvers
+            .iter_mut()
+            .find(|r| r.server == alternative.server)
+        {
+            Some(existing) => existing.label = alternative.label.clone(),
+            None => resolvers.push(ResolverBenchmark::new(
+                &alternative.label,
+                &alternative.server,
+                false,
+            )),
+        }
+    }
+    resolvers
+}
+
+pub fn benchmark(config: &BenchmarkConfig, candidates: Vec<ResolverBenchmark>) -> DnsBenchmark {
+    let resolvers: Vec<ResolverBenchmark> = thread::scope(|scope| {
+        let handles: Vec<_> = candidates
+            .into_iter()
+            .map(|resolver| scope.spawn(move || measure_resolver(config, resolver)))
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    });
+    let recommendation = recommend(&resolvers);
+    log::debug!(
+        "dnsbench::benchmark: {} resolvers - {:?}",
+        resolvers.len(),
+        recommendation
+    );
+    DnsBenchmark {
+        resolvers,
+        recommendation,
+    }
+}
+
+fn measure_resolver(config: &BenchmarkConfig, mut result: ResolverBenchmark) -> ResolverBenchmark {
+    let server = match dns::parse_server(&result.server) {
+        Ok(server) => server,
+        Err(e) => {
+            log::debug!("dnsbench::measure_resolver: {}: {}", result.server, e);
+            return result;
+        }
+    };
+    result.transport = server.transport();
+    let mut lookup = |name: &str| {
+        result.queries += 1;
+        let response = dns::lookup(&server, name, RecordType::A, config.timeout);
+        if let Err(e) = &response {
+            log::debug!(
+                "dnsbench::measure_resolver: {} via {}: {}",
+                name,
+                result.server,
+                e
+            );
+        }
+        response.ok()
+    };
+
+    // The first pass fills the resolver's cache. One that answers none of it
+    // is down, and waiting out every remaining timeout would tell us nothing.
+    let mut failures = 0;
+    let mut answered = false;
+    for name in &config.names {
+        match lookup(name) {
+            Some(response) if response.rcode == RCODE_NOERROR => answered = true,
+            _ => failures += 1,
+        }
+    }
+
+    let mut cached = Vec::new();
+    let mut uncached = Vec::new();
+    let mut nxdomain = NxdomainBehavior::Unknown;
+    if answered {
+        for _ in 0..config.cached_rounds {
+            for name in &config.names {
+                match lookup(name) {
+                    Some(response) if response.rcode == RCODE_NOERROR => {
+                        cached.push(response.latency_ms)
+                    }
+                    _ => failures += 1,
+                }
+            }
+        }
+
+        // Nobody has asked for these names before, so the resolver has to go
+        // all the way to the authoritative servers, which answer NXDOMAIN.
+        for name in &config.names {
+            match lookup(&format!("{}.{}", random_label(), name)) {
+                Some(response) if response.rcode == RCODE_NXDOMAIN => {
+                    uncached.push(response.latency_ms);
+                    if nxdomain == NxdomainBehavior::Unknown {
+                        nxdomain = NxdomainBehavior::Correct;
+                    }
+                }
+                Some(response) if response.rcode == RCODE_NOERROR => {
+                    uncached.push(response.latency_ms);
+                    if response.answer_count > 0 {
+                        nxdomain = NxdomainBehavior::Hijacked;
+                    }
+                }
+                _ => failures += 1,
+            }
+        }
+    }
+
+    let cached = summarize_samples(cached.len() as u32, cached);
+    let uncached = summarize_samples(uncached.len() as u32, uncached);
+    result.cached_median_ms = cached.median_ms;
+    result.cached_p95_ms = cached.p95_ms;
+    result.uncached_median_ms = uncached.median_ms;
+    result.uncached_p95_ms = uncached.p95_ms;
+    result.failures = failures;
+    result.failure_rate_percent =
+        (result.queries > 0).then(|| failures as f64 / result.queries as f64 * 100.0);
+    result.nxdomain = nxdomain;
+
+    log::debug!(
+        "dnsbench::measure_resolver: {} - cached: {:?}ms, uncached: {:?}ms, failures: {}/{}, nxdomain: {:?}",
+        result.server,
+        result.cached_median_ms,
+        result.uncached_median_ms,
+        result.failures,
+        result.queries,
+        result.nxdomain
+    );
+    result
+}
+
+fn random_label() -> String {
+    format!("yfi-{:016x}", RandomState::new().build_hasher().finish())
+}
+
+fn recommend(resolvers: &[ResolverBenchmark]) -> Option<String> {
+    let by_score =
+        |a: &(&ResolverBenchmark, f64), b: &(&ResolverBenchmark, f64)| a.1.total_cmp(&b.1);
+    let (best, best_score) = resolvers
+        .iter()
+        .filter(|r| r.reliable())
+        .filter_map(|r| Some((r, r.score()?)))
+        .min_by(by_score)?;
+    // The user's own resolver counts even when it answered nothing, as that
+    // is when switching matters most.
+    let current = resolvers
+        .iter()
+        .filter(|r| r.system)
+        .map(|r| (r, r.score()))
+        .min_by(|a, b| {
+            let score = |r: &(&ResolverBenchmark, Option<f64>)| r.1.unwrap_or(f64::INFINITY);
+            score(a).total_cmp(&score(b))
+        });
+
+    let switch = format!("{} ({})", best.label, best.server);
+    let recommendation = match current {
+        None => format!("{} was the fastest reliable resolver", switch),
+        Some((current, _)) if current.server == best.server => format!(
+            "Keep your current resolver {}; it was the fastest reliable one",
+            current.server
+        ),
+        Some((current, _)) if current.nxdomain == NxdomainBehavior::Hijacked => format!(
+            "Your resolver {} answers for domains that do not exist, which breaks typo detection and some apps. Switch to {}",
+            current.server, switch
+        ),
+        Some((current, Some(score))) if current.reliable() => {
+            // The score adds a cached and an uncached lookup.
+            let gain = (score - best_score) / 2.0;
+            if gain < MIN_GAIN_MS {
+                format!(
+                    "Keep your current resolver {}; {} was only {:.0} ms faster",
+                    current.server, switch, gain
+                )
+            } else {
+                format!(
+                    "Switching to {} would make lookups about {:.0} ms faster",
+                    switch, gain
+                )
+            }
+        }
+        Some((current, _)) => format!(
+            "Your resolver {} failed {:.0}% of lookups. Switch to {}",
+            current.server,
+            current.failure_rate_percent.unwrap_or(100.0),
+            switch
+        ),
+    };
+    Some(recommendation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::UdpSocket;
+
+    #[derive(Clone, Copy)]
+    enum Stub {
+        Honest,
+        Hijacking,
+        Failing,
+    }
+
+    // Answers every query after `delay`: one record for known names and, for
+    // the random ones, whatever `behavior` calls for.
+    fn spawn_stub_resolver(behavior: Stub, delay: Duration) -> String {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buf = [0u8; 512];
+            while let Ok((len, peer)) = socket.recv_from(&mut buf) {
+                let mut response = buf[..len].to_vec();
+                let random = response.windows(4).any(|w| w == b"yfi-");
+                let (rcode, answers) = match behavior {
+                    Stub::Failing => (2u16, 0u16),
+                    Stub::Hijacking if random => (0, 1),
+                    _ if random => (3, 0),
+                    _ => (0, 1),
+                };
+                response[2..4].copy_from_slice(&(0x8180 | rcode).to_be_bytes());
+                response[6..8].copy_from_slice(&answers.to_be_bytes());
+                thread::sleep(delay);
+                let _ = socket.send_to(&response, peer);
+            }
+        });
+        addr.to_string()
+    }
+
+    fn config() -> BenchmarkConfig {
+        BenchmarkConfig {
+            names: vec!["example.com".to_string(), "example.org".to_string()],
+            cached_rounds: 2,
+            timeout: Duration::from_millis(500),
+        }
+    }
+
+    #[test]
+    fn test_candidates() {
+        let system = vec!["192.168.1.1".to_string(), "1.1.1.1".to_string()];
+        let resolvers = candidates(&system, &default_alternative_resolvers());
+        let summary: Vec<_> = resolvers
+            .iter()
+            .map(|r| (r.label.as_str(), r.server.as_str(), r.system))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("System", "192.168.1.1", true),
+                ("Cloudflare", "1.1.1.1", true),
+                ("Google", "8.8.8.8", false),
+                ("Quad9", "9.9.9.9", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_benchmark_stub_resolvers() {
+        let slow = spawn_stub_resolver(Stub::Honest, Duration::from_millis(40));
+        let fast = spawn_stub_resolver(Stub::Honest, Duration::ZERO);
+        let hijacking = spawn_stub_resolver(Stub::Hijacking, Duration::ZERO);
+        let failing = spawn_stub_resolver(Stub::Failing, Duration::ZERO);
+
+        let resolvers = candidates(
+            std::slice::from_ref(&slow),
+            &[
+                ResolverTarget::new("Fast", &fast),
+                ResolverTarget::new("Hijacking", &hijacking),
+                ResolverTarget::new("Failing", &failing),
+            ],
+        );
+        let result = benchmark(&config(), resolvers);
+        let find = |server: &str| {
+            result
+                .resolvers
+                .iter()
+                .find(|r| r.server == server)
+                .unwrap()
+        };
+
+        let slow = find(&slow);
+        assert!(slow.system);
+        assert_eq!(slow.queries, 8);
+        assert_eq!(slow.failures, 0);
+        assert_eq!(slow.failure_rate_percent, Some(0.0));
+        assert_eq!(slow.nxdomain, NxdomainBehavior::Correct);
+        assert!(slow.cached_median_ms.unwrap() >= 40.0);
+        assert!(slow.uncached_p95_ms.unwrap() >= 40.0);
+
+        let fast = find(&fast);
+        assert!(fast.cached_median_ms.unwrap() < slow.cached_median_ms.unwrap());
+        assert_eq!(find(&hijacking).nxdomain, NxdomainBehavior::Hijacked);
+
+        // A resolver failing its warm-up pass is not queried further.
+        let failing = find(&failing);
+        assert_eq!(failing.queries, 2);
+        assert_eq!(failing.failure_rate_percent, Some(100.0));
+        assert_eq!(failing.cached_median_ms, None);
+
+        let recommendation = result.recommendation.unwrap();
+        assert!(
+            recommendation.starts_with(&format!("Switching to Fast ({})", fast.server)),
+            "{}",
+            recommendation
+        );
+
+        // A system resolver that answers nothing is still the one to replace.
+        let resolvers = candidates(
+            std::slice::from_ref(&failing.server),
+            &[ResolverTarget::new("Fast", &fast.server)],
+        );
+        let recommendation = benchmark(&config(), resolvers).recommendation.unwrap();
+        assert_eq!(
+            recommendation,
+            format!(
+                "Your resolver {} failed 100% of lookups. Switch to Fast ({})",
+                failing.server, fast.server
+            )
+        );
+    }
+
+    #[test]
+    fn test_recommend() {
+        let resolver = |label: &str, system: bool, cached: f64, uncached: f64| ResolverBenchmark {
+            cached_median_ms: Some(cached),
+            uncached_median_ms: Some(uncached),
+            failure_rate_percent: Some(0.0),
+            nxdomain: NxdomainBehavior::Correct,
+            ..ResolverBenchmark::new(label, label, system)
+        };
+
+        assert_eq!(recommend(&[]), None);
+
+        let mut current = resolver("router", true, 5.0, 40.0);
+        let alternative = resolver("quad9", false, 8.0, 35.0);
+        assert!(recommend(&[current.clone(), alternative.clone()])
+            .unwrap()
+            .starts_with("Keep your current resolver router; quad9 (quad9) was only 1 ms"));
+
+        // Gains are per lookup, in the decision and the message alike.
+        let slower = resolver("router", true, 5.0, 60.0);
+        assert_eq!(
+            recommend(&[slower, alternative.clone()]).unwrap(),
+            "Switching to quad9 (quad9) would make lookups about 11 ms faster"
+        );
+
+        current.nxdomain = NxdomainBehavior::Hijacked;
+        assert!(recommend(&[current.clone(), alternative.clone()])
+            .unwrap()
+            .contains("answers for domains that do not exist"));
+
+        current.nxdomain = NxdomainBehavior::Correct;
+        current.failure_rate_percent = Some(20.0);
+        assert!(recommend(&[current.clone(), alternative.clone()])
+            .unwrap()
+            .starts_with("Your resolver router failed 20% of lookups"));
+
+        assert_eq!(
+            recommend(&[alternative]).unwrap(),
+            "quad9 (quad9) was the fastest reliable resolver"
+        );
+    }
+}
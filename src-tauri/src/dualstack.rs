@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, ToSocketAddrs};
+use std::thread;
+
+use crate::interfaces::NetworkInterface;
+use crate::network::{get_ipv6_router_ip, parse_ip, ping_host, PingResult};
+use crate::probes::{
+    probe_target, summarize_targets, InternetSummary, InternetTarget, TargetResult,
+};
+use crate::runner::CommandRunner;
+
+const ROUTER_PING_COUNT: u32 = 3;
+// How much worse IPv6 may be than IPv4 before it is reported as degraded.
+const DEGRADED_LATENCY_MS: f64 = 30.0;
+const DEGRADED_LOSS_PERCENT: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IpFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl IpFamily {
+    fn matches(self, ip: &IpAddr) -> bool {
+        match self {
+            IpFamily::Ipv4 => ip.is_ipv4(),
+            IpFamily::Ipv6 => ip.is_ipv6(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FamilyMetrics {
+    pub router_ip: Option<String>,
+    pub router_ping: Option<PingResult>,
+    pub dns_servers: Vec<String>,
+    pub internet_targets: Vec<TargetResult>,
+    pub internet_summary: InternetSummary,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ipv6Status {
+    // No IPv6 default route, so applications use IPv4 straight away.
+    Unavailable,
+    Working,
+    Degraded,
+    // A default route that goes nowhere, which makes happy-eyeballs clients
+    // wait on IPv6 before falling back.
+    Broken,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DualStack {
+    pub ipv4: FamilyMetrics,
+    pub ipv6: FamilyMetrics,
+    pub ipv6_status: Ipv6Status,
+    pub issue: Option<String>,
+}
+
+// The target as reached over one family: its IPv6 host when asking for IPv6,
+// a literal of the right family, or the first address its name resolves to.
+// Names are only resolved for live runs, as replays have nothing to look up.
+pub fn family_target(
+    runner: &dyn CommandRunner,
+    target: &InternetTarget,
+    family: IpFamily,
+) -> Option<InternetTarget> {
+    let host = match (&target.ipv6_host, family) {
+        (Some(host), IpFamily::Ipv6) => host,
+        _ => &target.host,
+    };
+
+    let address = match parse_ip(host) {
+        Some(ip) if family.matches(&ip) => host.clone(),
+        Some(_) => return None,
+        None if runner.native_probes() => (host.as_str(), 0)
+            .to_socket_addrs()
+            .ok()?
+            .find(|addr| family.matches(&addr.ip()))?
+            .ip()
+            .to_string(),
+        None => {
+            log::debug!("family_target: not resolving {} without live sockets", host);
+            return None;
+        }
+    };
+
+    Some(InternetTarget {
+        host: address,
+        ..target.clone()
+    })
+}
+
+// Pings the IPv6 router and probes every target over IPv6. Without a default
+// route there is nothing to probe.
+pub fn probe_ipv6(
+    runner: &dyn CommandRunner,
+    targets: &[InternetTarget],
+    interface: Option<&NetworkInterface>,
+) -> FamilyMetrics {
+    let Some(router_ip) = get_ipv6_router_ip(runner, interface) else {
+        log::debug!("probe_ipv6: no IPv6 router, skipping probes");
+        return FamilyMetrics::default();
+    };
+
+    let targets: Vec<InternetTarget> = targets
+        .iter()
+        .filter_map(|target| family_target(runner, target, IpFamily::Ipv6))
+        .collect();
+    let (router_ping, internet_targets) = thread::scope(|scope| {
+        let router_ping =
+            scope.spawn(|| ping_host(runner, &router_ip, ROUTER_PING_COUNT, interface));
+        let results = probe_all(runner, &targets, interface);
+        (router_ping.join().ok(), results)
+    });
+
+    FamilyMetrics {
+        router_ip: Some(router_ip),
+        router_ping,
+        internet_summary: summarize_targets(&internet_targets),
+        internet_targets,
+        ..Default::default()
+    }
+}
+
+// The IPv4 side of the comparison, reusing the main probes wherever they
+// already went over IPv4 and probing the rest.
+pub fn probe_ipv4(
+    runner: &dyn CommandRunner,
+    results: &[TargetResult],
+    router_ip: Option<&str>,
+    router_ping: Option<&PingResult>,
+    interface: Option<&NetworkInterface>,
+) -> FamilyMetrics {
+    let mut internet_targets = Vec::with_capacity(results.len());
+    let mut pending = Vec::new();
+    for result in results {
+        if parse_ip(&result.target.host).is_some_and(|ip| ip.is_ipv4()) {
+            internet_targets.push(result.clone());
+        } else if let Some(target) = family_target(runner, &result.target, IpFamily::Ipv4) {
+            pending.push(target);
+        }
+    }
+    internet_targets.extend(probe_all(runner, &pending, interface));
+
+    let router_ip = router_ip.filter(|ip| parse_ip(ip).is_some_and(|ip| ip.is_ipv4()));
+    FamilyMetrics {
+        router_ip: router_ip.map(str::to_string),
+        router_ping: router_ip.and(router_ping.cloned()),
+        internet_summary: summarize_targets(&internet_targets),
+        internet_targets,
+        ..Default::default()
+    }
+}
+
+// Compares the two families, reusing the main probe results for IPv4.
+pub fn measure(
+    runner: &dyn CommandRunner,
+    targets: &[InternetTarget],
+    results: &[TargetResult],
+    router_ip: Option<&str>,
+    router_ping: Option<&PingResult>,
+    dns_servers: &[String],
+    interface: Option<&NetworkInterface>,
+) -> DualStack {
+    let ipv6 = probe_ipv6(runner, targets, interface);
+    let ipv4 = probe_ipv4(runner, results, router_ip, router_ping, interface);
+    compare(ipv4, ipv6, dns_servers)
+}
+
+fn probe_all(
+    runner: &dyn CommandRunner,
+    targets: &[InternetTarget],
+    interface: Option<&NetworkInterface>,
+) -> Vec<TargetResult> {
+    thread::scope(|scope| {
+        let handles: Vec<_> = targets
+            .iter()
+            .map(|target| scope.spawn(move || probe_target(runner, target, interface)))
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    })
+}
+
+pub fn compare(
+    mut ipv4: FamilyMetrics,
+    mut ipv6: FamilyMetrics,
+    dns_servers: &[String],
+) -> DualStack {
+    for server in dns_servers {
+        match parse_ip(server) {
+            Some(IpAddr::V4(_)) => ipv4.dns_servers.push(server.clone()),
+            Some(IpAddr::V6(_)) => ipv6.dns_servers.push(server.clone()),
+            None => {}
+        }
+    }
+
+    let (ipv6_status, issue) = assess(&ipv4, &ipv6);
+    log::debug!("compare: IPv6 is {:?} ({:?})", ipv6_status, issue);
+    DualStack {
+        ipv4,
+        ipv6,
+        ipv6_status,
+        issue,
+    }
+}
+
+fn assess(ipv4: &FamilyMetrics, ipv6: &FamilyMetrics) -> (Ipv6Status, Option<String>) {
+    if ipv6.router_ip.is_none() {
+        return (Ipv6Status::Unavailable, None);
+    }
+    let (v4, v6) = (&ipv4.internet_summary, &ipv6.internet_summary);
+    if v6.total_targets == 0 {
+        return (Ipv6Status::Unknown, None);
+    }
+
+    if v6.reachable_targets == 0 {
+        let issue = if v4.reachable_targets > 0 {
+            "IPv6 has a default route but no target answered over it. Apps that try IPv6 first will stall before falling back to IPv4."
+        } else {
+            "IPv6 has a default route but no target answered over it."
+        };
+        return (Ipv6Status::Broken, Some(issue.to_string()));
+    }
+
+    if let (Some(v4_ms), Some(v6_ms)) = (v4.median_latency_ms, v6.median_latency_ms) {
+        if v6_ms - v4_ms > DEGRADED_LATENCY_MS {
+            return (
+                Ipv6Status::Degraded,
+                Some(format!(
+                    "IPv6 latency is {:.0} ms higher than IPv4, so apps preferring IPv6 are slower.",
+                    v6_ms - v4_ms
+                )),
+            );
+        }
+    }
+    if let (Some(v4_loss), Some(v6_loss)) = (v4.packet_loss_percent, v6.packet_loss_percent) {
+        if v6_loss - v4_loss > DEGRADED_LOSS_PERCENT {
+            return (
+                Ipv6Status::Degraded,
+                Some(format!(
+                    "IPv6 loses {:.0}% of packets against {:.0}% over IPv4.",
+                    v6_loss, v4_loss
+                )),
+            );
+        }
+    }
+
+    (Ipv6Status::Working, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::ReplayRunner;
+
+    fn target(host: &str, ipv6_host: Option<&str>) -> InternetTarget {
+        InternetTarget {
+            label: "Target".to_string(),
+            host: host.to_string(),
+            protocol: crate::probes::ProbeProtocol::Icmp,
+            port: None,
+            ipv6_host: ipv6_host.map(str::to_string),
+        }
+    }
+
+    fn metrics(router_ip: Option<&str>, summary: InternetSummary) -> FamilyMetrics {
+        FamilyMetrics {
+            router_ip: router_ip.map(str::to_string),
+            internet_summary: summary,
+            ..Default::default()
+        }
+    }
+
+    fn summary(reachable: u32, median_ms: Option<f64>, loss: f64) -> InternetSummary {
+        InternetSummary {
+            total_targets: 3,
+            reachable_targets: reachable,
+            median_latency_ms: median_ms,
+            packet_loss_percent: Some(loss),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_family_target() {
+        let runner = ReplayRunner::new("/nonexistent");
+        let dual = target("1.1.1.1", Some("2606:4700:4700::1111"));
+        assert_eq!(
+            family_target(&runner, &dual, IpFamily::Ipv4).unwrap().host,
+            "1.1.1.1"
+        );
+        assert_eq!(
+            family_target(&runner, &dual, IpFamily::Ipv6).unwrap().host,
+            "2606:4700:4700::1111"
+        );
+
+        let v4_only = target("8.8.8.8", None);
+        assert!(family_target(&runner, &v4_only, IpFamily::Ipv6).is_none());
+
+        let v6_only = target("fe80::1%en0", None);
+        assert!(family_target(&runner, &v6_only, IpFamily::Ipv4).is_none());
+        assert_eq!(
+            family_target(&runner, &v6_only, IpFamily::Ipv6)
+                .unwrap()
+                .host,
+            "fe80::1%en0"
+        );
+
+        // Replays cannot resolve names.
+        let named = target("example.com", None);
+        assert!(family_target(&runner, &named, IpFamily::Ipv4).is_none());
+    }
+
+    #[test]
+    fn test_assess() {
+        let ipv4 = metrics(Some("192.168.1.1"), summary(3, Some(15.0), 0.0));
+
+        let ipv6 = metrics(None, InternetSummary::default());
+        assert_eq!(assess(&ipv4, &ipv6), (Ipv6Status::Unavailable, None));
+
+        let ipv6 = metrics(Some("fe80::1"), InternetSummary::default());
+        assert_eq!(assess(&ipv4, &ipv6), (Ipv6Status::Unknown, None));
+
+        let ipv6 = metrics(Some("fe80::1"), summary(3, Some(18.0), 0.0));
+        assert_eq!(assess(&ipv4, &ipv6), (Ipv6Status::Working, None));
+
+        let ipv6 = metrics(Some("fe80::1"), summary(0, None, 100.0));
+        let (status, issue) = assess(&ipv4, &ipv6);
+        assert_eq!(status, Ipv6Status::Broken);
+        assert!(issue.unwrap().contains("falling back to IPv4"));
+
+        let ipv6 = metrics(Some("fe80::1"), summary(3, Some(60.0), 0.0));
+        let (status, issue) = assess(&ipv4, &ipv6);
+        assert_eq!(status, Ipv6Status::Degraded);
+        assert!(issue.unwrap().contains("45 ms"));
+
+        let ipv6 = metrics(Some("fe80::1"), summary(2, Some(16.0), 33.3));
+        assert_eq!(assess(&ipv4, &ipv6).0, Ipv6Status::Degraded);
+    }
+
+    #[test]
+    fn test_compare_splits_dns_servers() {
+        let servers = vec![
+            "192.168.1.1".to_string(),
+            "2001:4860:4860::8888".to_string(),
+            "fe80::1%en0".to_string(),
+        ];
+        let dual = compare(FamilyMetrics::default(), FamilyMetrics::default(), &servers);
+        assert_eq!(dual.ipv4.dns_servers, vec!["192.168.1.1"]);
+        assert_eq!(
+            dual.ipv6.dns_servers,
+            vec!["2001:4860:4860::8888", "fe80::1%en0"]
+        );
+        assert_eq!(dual.ipv6_status, Ipv6Status::Unavailable);
+    }
+}
@@ -0,0 +1,620 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+const READ_BUFFER_SIZE: usize = 16 * 1024;
+const USER_AGENT: &str = "yfi";
+
+// Durations of each phase of a single GET, in the spirit of curl's `-w`
+// timings, except that every field is the length of its own phase rather than
+// a running total. Phases that never started are None.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HttpTiming {
+    pub url: String,
+    pub remote_address: Option<String>,
+    pub status: Option<u16>,
+    pub dns_ms: Option<f64>,
+    pub connect_ms: Option<f64>,
+    pub tls_ms: Option<f64>,
+    pub first_byte_ms: Option<f64>,
+    pub transfer_ms: Option<f64>,
+    pub total_ms: Option<f64>,
+    pub bytes_received: u64,
+    pub error: Option<String>,
+}
+
+pub fn measure(url: &str, timeout: Duration) -> HttpTiming {
+    measure_with_roots(url, timeout, default_roots())
+}
+
+pub(crate) fn default_roots() -> RootCertStore {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    roots
+}
+
+fn measure_with_roots(url: &str, timeout: Duration, roots: RootCertStore) -> HttpTiming {
+    let mut timing = HttpTiming {
+        url: url.to_string(),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    if let Err(e) = run_request(url, timeout, roots, &mut timing) {
+        log::debug!("http::measure: {} failed: {}", url, e);
+        timing.error = Some(e.to_string());
+    }
+    timing.total_ms = Some(elapsed_ms(start));
+
+    log::debug!(
+        "http::measure: {} - status: {:?}, dns: {:?}ms, connect: {:?}ms, tls: {:?}ms, first byte: {:?}ms, total: {:?}ms",
+        url,
+        timing.status,
+        timing.dns_ms,
+        timing.connect_ms,
+        timing.tls_ms,
+        timing.first_byte_ms,
+        timing.total_ms
+    );
+    timing
+}
+
+fn run_request(
+    url: &str,
+    timeout: Duration,
+    roots: RootCertStore,
+    timing: &mut HttpTiming,
+) -> io::Result<()> {
+    let url = parse_url(url)?;
+    let (tls, host, port) = origin(&url)?;
+
+    let phase = Instant::now();
+    let addr = resolve(host, port)?;
+    timing.dns_ms = Some(elapsed_ms(phase));
+    timing.remote_address = Some(addr.to_string());
+
+    let phase = Instant::now();
+    let stream = TcpStream::connect_timeout(&addr, timeout)?;
+    timing.connect_ms = Some(elapsed_ms(phase));
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let request = request_head("GET", &url, "");
+
+    if tls {
+        let phase = Instant::now();
+        let mut stream = tls_handshake(stream, host, roots)?;
+        timing.tls_ms = Some(elapsed_ms(phase));
+        exchange(&mut stream, &request, timing)
+    } else {
+        let mut stream = stream;
+        exchange(&mut stream, &request, timing)
+    }
+}
+
+// Streams the body of a GET for `url` until it ends or `stop` is set, adding
+// every byte read to `received`. Used to saturate the downlink.
+pub fn download(
+    url: &str,
+    timeout: Duration,
+    stop: &AtomicBool,
+    received: &AtomicU64,
+) -> io::Result<()> {
+    let url = parse_url(url)?;
+    let mut conn = open(&url, timeout, default_roots())?;
+    conn.write_all(request_head("GET", &url, "").as_bytes())?;
+    conn.flush()?;
+
+    let mut buf = [0u8; READ_BUFFER_SIZE];
+    while !stop.load(Ordering::Relaxed) {
+        match conn.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => {
+                received.fetch_add(n as u64, Ordering::Relaxed);
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+// POSTs `size` bytes to `url`, stopping early when `stop` is set, and adds
+// every byte written to `sent`. Used to saturate the uplink.
+pub fn upload(
+    url: &str,
+    size: u64,
+    timeout: Duration,
+    stop: &AtomicBool,
+    sent: &AtomicU64,
+) -> io::Result<()> {
+    let url = parse_url(url)?;
+    let mut conn = open(&url, timeout, default_roots())?;
+    let headers = format!(
+        "Content-Type: application/octet-stream\r\nContent-Length: {}\r\n",
+        size
+    );
+    conn.write_all(request_head("POST", &url, &headers).as_bytes())?;
+
+    let chunk = [0u8; READ_BUFFER_SIZE];
+    let mut remaining = size;
+    while remaining > 0 && !stop.load(Ordering::Relaxed) {
+        let n = remaining.min(chunk.len() as u64) as usize;
+        conn.write_all(&chunk[..n])?;
+        remaining -= n as u64;
+        sent.fetch_add(n as u64, Ordering::Relaxed);
+    }
+    conn.flush()
+}
+
+// The address a URL's requests go to.
+pub fn resolve_url(url: &str) -> io::Result<SocketAddr> {
+    let url = parse_url(url)?;
+    let (_, host, port) = origin(&url)?;
+    resolve(host, port)
+}
+
+// A connection to a URL's origin, ready for a single request.
+pub(crate) struct Client {
+    url: Url,
+    conn: Connection,
+}
+
+pub(crate) struct Reply {
+    pub status: u16,
+    pub location: Option<String>,
+    pub body: Vec<u8>,
+}
+
+pub(crate) fn connect(url: &str, timeout: Duration, roots: RootCertStore) -> io::Result<Client> {
+    let url = parse_url(url)?;
+    let conn = open(&url, timeout, roots)?;
+    Ok(Client { url, conn })
+}
+
+impl Client {
+    // GETs the URL without following redirects.
+    pub(crate) fn get(self) -> io::Result<Reply> {
+        let request = request_head("GET", &self.url, "");
+        self.send(request.as_bytes())
+    }
+
+    pub(crate) fn post(self, content_type: &str, body: &[u8]) -> io::Result<Reply> {
+        let headers = format!(
+            "Content-Type: {0}\r\nAccept: {0}\r\nContent-Length: {1}\r\n",
+            content_type,
+            body.len()
+        );
+        let mut request = request_head("POST", &self.url, &headers).into_bytes();
+        request.extend_from_slice(body);
+        self.send(&request)
+    }
+
+    // Reads the whole reply, which ends when the server closes the connection.
+    fn send(mut self, request: &[u8]) -> io::Result<Reply> {
+        self.conn.write_all(request)?;
+        self.conn.flush()?;
+
+        let mut response = Vec::new();
+        let mut buf = [0u8; READ_BUFFER_SIZE];
+        loop {
+            match self.conn.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => response.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !response.is_empty() => break,
+                Err(e) => return Err(e),
+            }
+        }
+        parse_reply(&response)
+    }
+}
+
+enum Connection {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.read(buf),
+            Connection::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.write(buf),
+            Connection::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Connection::Plain(stream) => stream.flush(),
+            Connection::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+fn open(url: &Url, timeout: Duration, roots: RootCertStore) -> io::Result<Connection> {
+    let (tls, host, port) = origin(url)?;
+    let addr = resolve(host, port)?;
+    let stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    if tls {
+        let stream = tls_handshake(stream, host, roots)?;
+        Ok(Connection::Tls(Box::new(stream)))
+    } else {
+        Ok(Connection::Plain(stream))
+    }
+}
+
+fn parse_url(url: &str) -> io::Result<Url> {
+    Url::parse(url).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+// Whether the URL needs TLS, plus its host and port.
+fn origin(url: &Url) -> io::Result<(bool, &str, u16)> {
+    let tls = match url.scheme() {
+        "https" => true,
+        "http" => false,
+        scheme => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unsupported scheme {}", scheme),
+            ))
+        }
+    };
+    let host = url
+        .host_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "URL has no host"))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    Ok((tls, host, port))
+}
+
+// Request line and headers; `headers` holds extra CRLF-terminated lines.
+fn request_head(method: &str, url: &Url, headers: &str) -> String {
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    let host = url.host_str().unwrap_or_default();
+    let host_header = match url.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    };
+    format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nAccept: */*\r\n{}Connection: close\r\n\r\n",
+        method, path, host_header, USER_AGENT, headers
+    )
+}
+
+fn resolve(host: &str, port: u16) -> io::Result<SocketAddr> {
+    // Url keeps the brackets around IPv6 literals.
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    (host, port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no address for {}", host)))
+}
+
+pub(crate) fn tls_handshake(
+    mut stream: TcpStream,
+    host: &str,
+    roots: RootCertStore,
+) -> io::Result<StreamOwned<ClientConnection, TcpStream>> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(io::Error::other)?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(host.trim_start_matches('[').trim_end_matches(']'))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?
+        .to_owned();
+    let mut conn =
+        ClientConnection::new(Arc::new(config), server_name).map_err(io::Error::other)?;
+
+    while conn.is_handshaking() {
+        conn.complete_io(&mut stream)?;
+    }
+    Ok(StreamOwned::new(conn, stream))
+}
+
+fn exchange(
+    stream: &mut (impl Read + Write),
+    request: &str,
+    timing: &mut HttpTiming,
+) -> io::Result<()> {
+    let phase = Instant::now();
+    stream.write_all(request.as_bytes())?;
+    stream.flush()?;
+
+    let mut buf = [0u8; READ_BUFFER_SIZE];
+    let mut head = Vec::new();
+    let mut first_byte_at = None;
+    loop {
+        let n = match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            // Plenty of servers close TLS connections without close_notify.
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && first_byte_at.is_some() => break,
+            Err(e) => return Err(e),
+        };
+        if first_byte_at.is_none() {
+            let now = Instant::now();
+            timing.first_byte_ms = Some(now.duration_since(phase).as_secs_f64() * 1000.0);
+            first_byte_at = Some(now);
+        }
+        timing.bytes_received += n as u64;
+        if timing.status.is_none() && head.len() < READ_BUFFER_SIZE {
+            head.extend_from_slice(&buf[..n]);
+            timing.status = parse_status(&head);
+        }
+    }
+
+    let Some(first_byte_at) = first_byte_at else {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "connection closed before a response",
+        ));
+    };
+    timing.transfer_ms = Some(elapsed_ms(first_byte_at));
+    if timing.status.is_none() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "malformed HTTP response",
+        ));
+    }
+    Ok(())
+}
+
+fn parse_status(head: &[u8]) -> Option<u16> {
+    let line_end = head.windows(2).position(|w| w == b"\r\n")?;
+    let line = std::str::from_utf8(&head[..line_end]).ok()?;
+    let mut parts = line.split_whitespace();
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    parts.next()?.parse().ok()
+}
+
+fn parse_reply(response: &[u8]) -> io::Result<Reply> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed HTTP response");
+    let head_end = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(malformed)?;
+    let status = parse_status(response).ok_or_else(malformed)?;
+    let raw_head = String::from_utf8_lossy(&response[..head_end]);
+    let head = raw_head.to_ascii_lowercase();
+    let body = &response[head_end + 4..];
+
+    let chunked = head
+        .lines()
+        .any(|line| line.starts_with("transfer-encoding:") && line.contains("chunked"));
+    let body = if chunked {
+        decode_chunked(body).ok_or_else(malformed)?
+    } else {
+        body.to_vec()
+    };
+    let location = raw_head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("location")
+            .then(|| value.trim().to_string())
+    });
+    Ok(Reply {
+        status,
+        location,
+        body,
+    })
+}
+
+fn decode_chunked(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&body[..line_end]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        if size == 0 {
+            return Some(decoded);
+        }
+        let chunk = body.get(line_end + 2..line_end + 2 + size)?;
+        decoded.extend_from_slice(chunk);
+        body = body.get(line_end + 4 + size..)?;
+    }
+}
+
+fn elapsed_ms(since: Instant) -> f64 {
+    since.elapsed().as_secs_f64() * 1000.0
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    use rustls::{ServerConfig, ServerConnection};
+    use std::net::TcpListener;
+    use std::thread;
+
+    const RESPONSE: &[u8] = b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n";
+
+    fn fixture(name: &str) -> String {
+        format!("{}/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn certificate() -> CertificateDer<'static> {
+        CertificateDer::from_pem_file(fixture("localhost.crt")).unwrap()
+    }
+
+    pub(crate) fn trusted_roots() -> RootCertStore {
+        let mut roots = RootCertStore::empty();
+        roots.add(certificate()).unwrap();
+        roots
+    }
+
+    fn read_request(stream: &mut impl Read) -> String {
+        let mut request = Vec::new();
+        let mut buf = [0u8; 1024];
+        while !request.ends_with(b"\r\n\r\n") {
+            let n = stream.read(&mut buf).unwrap_or(0);
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        String::from_utf8(request).unwrap()
+    }
+
+    fn serve_http_once() -> (u16, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let request = read_request(&mut stream);
+            thread::sleep(Duration::from_millis(20));
+            stream.write_all(RESPONSE).unwrap();
+            request
+        });
+        (port, handle)
+    }
+
+    // Serves the self-signed `localhost` certificate.
+    pub(crate) fn server_config() -> Arc<ServerConfig> {
+        let config =
+            ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .unwrap()
+                .with_no_client_auth()
+                .with_single_cert(
+                    vec![certificate()],
+                    PrivateKeyDer::from_pem_file(fixture("localhost.key")).unwrap(),
+                )
+                .unwrap();
+        Arc::new(config)
+    }
+
+    fn serve_https_once() -> (u16, thread::JoinHandle<String>) {
+        let config = server_config();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (tcp, _) = listener.accept().unwrap();
+            let conn = ServerConnection::new(config).unwrap();
+            let mut stream = StreamOwned::new(conn, tcp);
+            let request = read_request(&mut stream);
+            thread::sleep(Duration::from_millis(20));
+            // The client may have rejected the certificate; nothing to answer then.
+            let _ = stream.write_all(RESPONSE);
+            stream.conn.send_close_notify();
+            let _ = stream.flush();
+            request
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_measure_http() {
+        let (port, server) = serve_http_once();
+        let url = format!("http://127.0.0.1:{}/generate_204?probe=1", port);
+
+        let timing = measure_with_roots(&url, Duration::from_secs(2), trusted_roots());
+        let request = server.join().unwrap();
+
+        assert!(request.starts_with("GET /generate_204?probe=1 HTTP/1.1\r\n"));
+        assert!(request.contains(&format!("Host: 127.0.0.1:{}\r\n", port)));
+        assert_eq!(timing.error, None);
+        assert_eq!(timing.status, Some(204));
+        assert_eq!(timing.remote_address, Some(format!("127.0.0.1:{}", port)));
+        assert!(timing.dns_ms.is_some());
+        assert!(timing.connect_ms.is_some());
+        assert_eq!(timing.tls_ms, None);
+        assert!(timing.first_byte_ms.unwrap() >= 20.0);
+        assert!(timing.total_ms.unwrap() >= timing.first_byte_ms.unwrap());
+        assert_eq!(timing.bytes_received, RESPONSE.len() as u64);
+    }
+
+    #[test]
+    fn test_measure_https_self_signed() {
+        let (port, server) = serve_https_once();
+        let url = format!("https://localhost:{}/", port);
+
+        let timing = measure_with_roots(&url, Duration::from_secs(2), trusted_roots());
+        let request = server.join().unwrap();
+
+        assert!(request.starts_with("GET / HTTP/1.1\r\n"));
+        assert_eq!(timing.error, None);
+        assert_eq!(timing.status, Some(204));
+        assert!(timing.tls_ms.is_some());
+        assert!(timing.first_byte_ms.unwrap() >= 20.0);
+        assert_eq!(timing.bytes_received, RESPONSE.len() as u64);
+    }
+
+    #[test]
+    fn test_measure_https_untrusted() {
+        let (port, _server) = serve_https_once();
+        let url = format!("https://localhost:{}/", port);
+
+        let timing = measure_with_roots(&url, Duration::from_secs(2), RootCertStore::empty());
+        assert!(timing.error.is_some());
+        assert!(timing.connect_ms.is_some());
+        assert_eq!(timing.tls_ms, None);
+        assert_eq!(timing.status, None);
+    }
+
+    #[test]
+    fn test_measure_rejects_bad_urls() {
+        let timing = measure("ftp://example.com/", Duration::from_secs(1));
+        assert!(timing.error.unwrap().contains("unsupported scheme"));
+        assert_eq!(timing.dns_ms, None);
+        assert!(measure("not a url", Duration::from_secs(1)).error.is_some());
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        let reply = parse_reply(b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\n\r\nabc").unwrap();
+        assert_eq!(reply.status, 200);
+        assert_eq!(reply.location, None);
+        assert_eq!(reply.body, b"abc");
+
+        let reply =
+            parse_reply(b"HTTP/1.1 302 Found\r\nlocation: http://portal.example/Login?x=1\r\n\r\n")
+                .unwrap();
+        assert_eq!(reply.status, 302);
+        assert_eq!(
+            reply.location.as_deref(),
+            Some("http://portal.example/Login?x=1")
+        );
+
+        let reply = parse_reply(
+            b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2;x=1\r\nde\r\n0\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(reply.body, b"abcde");
+
+        assert!(
+            parse_reply(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n").is_err()
+        );
+        assert!(parse_reply(b"HTTP/1.1 200 OK\r\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(parse_status(b"HTTP/1.1 200 OK\r\n"), Some(200));
+        assert_eq!(parse_status(b"HTTP/2 301\r\nLocation: /\r\n"), Some(301));
+        assert_eq!(parse_status(b"HTTP/1.1 200"), None);
+        assert_eq!(parse_status(b"SSH-2.0-OpenSSH\r\n"), None);
+    }
+}
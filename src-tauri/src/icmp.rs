@@ -0,0 +1,1183 @@
+use std::io;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const ICMP_ECHO_REQUEST: u8 = 8;
+const ICMP_ECHO_REPLY: u8 = 0;
+const ICMPV6_ECHO_REQUEST: u8 = 128;
+const ICMPV6_ECHO_REPLY: u8 = 129;
+const ICMP_DEST_UNREACHABLE: u8 = 3;
+const ICMP_TIME_EXCEEDED: u8 = 11;
+const ICMPV6_DEST_UNREACHABLE: u8 = 1;
+const ICMPV6_PACKET_TOO_BIG: u8 = 2;
+const ICMPV6_TIME_EXCEEDED: u8 = 3;
+const ICMP_FRAG_NEEDED: u8 = 4;
+const ICMP_HEADER_LEN: usize = 8;
+const IPV4_HEADER_LEN: usize = 20;
+const IPV6_HEADER_LEN: usize = 40;
+const TOKEN_LEN: usize = 8;
+const PAYLOAD_LEN: usize = 56;
+const RECV_BUFFER_SIZE: usize = 2048;
+const CONTROL_BUFFER_SIZE: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EchoReply {
+    pub sequence: u16,
+    pub rtt_ms: f64,
+    pub ttl: Option<u8>,
+    pub duplicate: bool,
+    pub out_of_order: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EchoReport {
+    pub sent: u32,
+    pub replies: Vec<EchoReply>,
+}
+
+impl EchoReport {
+    pub fn rtts_ms(&self) -> Vec<f64> {
+        self.replies
+            .iter()
+            .filter(|r| !r.duplicate)
+            .map(|r| r.rtt_ms)
+            .collect()
+    }
+
+    pub fn received(&self) -> u32 {
+        self.replies.iter().filter(|r| !r.duplicate).count() as u32
+    }
+
+    pub fn duplicates(&self) -> u32 {
+        self.replies.iter().filter(|r| r.duplicate).count() as u32
+    }
+
+    pub fn out_of_order(&self) -> u32 {
+        self.replies.iter().filter(|r| r.out_of_order).count() as u32
+    }
+
+    fn record(&mut self, sequence: u16, rtt_ms: f64, ttl: Option<u8>) {
+        let duplicate = self.replies.iter().any(|r| r.sequence == sequence);
+        let out_of_order = !duplicate && self.replies.iter().any(|r| r.sequence > sequence);
+        self.replies.push(EchoReply {
+            sequence,
+            rtt_ms,
+            ttl,
+            duplicate,
+            out_of_order,
+        });
+    }
+}
+
+// One answer to a TTL-limited probe. `terminal` is set when the probe went no
+// further: the destination replied or a router reported it unreachable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HopReply {
+    pub ttl: u8,
+    pub address: IpAddr,
+    pub rtt_ms: f64,
+    pub terminal: bool,
+}
+
+// The outcome of sending one don't-fragment packet of a given size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SizeProbe {
+    Fits,
+    // Refused by the local stack or a router, with the MTU a router reported.
+    TooBig(Option<u32>),
+    NoReply,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ParsedReply {
+    sequence: u16,
+    ttl: Option<u8>,
+}
+
+// An ICMP error quoting one of our requests. `info` is the second header word,
+// which carries the next-hop MTU in fragmentation-needed and packet-too-big.
+#[derive(Debug, Clone, PartialEq)]
+struct IcmpError {
+    sequence: u16,
+    icmp_type: u8,
+    code: u8,
+    info: u32,
+}
+
+enum Incoming {
+    Packet {
+        data: Vec<u8>,
+        ttl: Option<u8>,
+        source: Option<IpAddr>,
+    },
+    // An ICMP error taken from the Linux socket error queue. `data` is the
+    // request it refers to rather than the error itself.
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    QueuedError {
+        data: Vec<u8>,
+        icmp_type: u8,
+        code: u8,
+        info: u32,
+        offender: Option<IpAddr>,
+    },
+}
+
+pub fn echo(
+    host: &str,
+    count: u32,
+    interval: Duration,
+    timeout: Duration,
+    interface: Option<&str>,
+) -> io::Result<EchoReport> {
+    let addr = resolve(host)?;
+    let socket = IcmpSocket::open(&addr)?;
+    if let Some(interface) = interface {
+        bind_to_interface(socket.fd.as_raw_fd(), interface, addr.is_ipv6())?;
+    }
+    let token = make_token();
+    let identifier = (std::process::id() & 0xffff) as u16;
+
+    let mut report = EchoReport::default();
+    let mut sent_at: Vec<Instant> = Vec::with_capacity(count as usize);
+    let start = Instant::now();
+    let deadline = start + interval * count.saturating_sub(1) + timeout;
+
+    loop {
+        let now = Instant::now();
+        let next_send = start + interval * sent_at.len() as u32;
+
+        if (sent_at.len() as u32) < count && now >= next_send {
+            let sequence = sent_at.len() as u16;
+            let packet = build_echo_request(addr.is_ipv6(), identifier, sequence, &token);
+            if let Err(e) = socket.send_to(&packet, &addr) {
+                log::debug!("icmp::echo: send to {} failed: {}", addr, e);
+            }
+            sent_at.push(now);
+            report.sent += 1;
+            continue;
+        }
+
+        let all_sent = report.sent >= count;
+        if now >= deadline || (all_sent && report.received() >= count) {
+            break;
+        }
+
+        let wake_at = if all_sent {
+            deadline
+        } else {
+            next_send.min(deadline)
+        };
+        let Some(Incoming::Packet {
+            data: packet,
+            ttl: cmsg_ttl,
+            ..
+        }) = socket.recv(wake_at - now)?
+        else {
+            continue;
+        };
+        let received_at = Instant::now();
+
+        let Some(reply) = parse_echo_reply(&packet, addr.is_ipv6(), &token) else {
+            continue;
+        };
+        if let Some(sent) = sent_at.get(reply.sequence as usize) {
+            let rtt_ms = received_at.duration_since(*sent).as_secs_f64() * 1000.0;
+            report.record(reply.sequence, rtt_ms, reply.ttl.or(cmsg_ttl));
+        }
+    }
+
+    log::debug!(
+        "icmp::echo: {} - sent {}, received {}, duplicates {}, out of order {}",
+        host,
+        report.sent,
+        report.received(),
+        report.duplicates(),
+        report.out_of_order()
+    );
+    Ok(report)
+}
+
+// Sends one echo request per TTL from 1 to `max_hops` and collects the routers'
+// time-exceeded errors and the destination's reply, like a single MTR cycle.
+pub fn trace_round(
+    host: &str,
+    max_hops: u8,
+    timeout: Duration,
+    interface: Option<&str>,
+) -> io::Result<Vec<HopReply>> {
+    let addr = resolve(host)?;
+    let ipv6 = addr.is_ipv6();
+    let socket = IcmpSocket::open(&addr)?;
+    if let Some(interface) = interface {
+        bind_to_interface(socket.fd.as_raw_fd(), interface, ipv6)?;
+    }
+    #[cfg(target_os = "linux")]
+    socket.enable_error_queue(ipv6);
+
+    let token = make_token();
+    let identifier = (std::process::id() & 0xffff) as u16;
+    // Sequence numbers start from a per-round base so late answers to an
+    // earlier round are not mistaken for this one.
+    let base = u16::from_be_bytes([token[6], token[7]]);
+
+    let mut sent_at: Vec<Instant> = Vec::with_capacity(max_hops as usize);
+    for ttl in 1..=max_hops {
+        socket.set_ttl(ttl, ipv6)?;
+        let sequence = base.wrapping_add(ttl as u16);
+        let packet = build_echo_request(ipv6, identifier, sequence, &token);
+        if let Err(e) = socket.send_to(&packet, &addr) {
+            log::debug!(
+                "icmp::trace_round: send to {} with ttl {} failed: {}",
+                addr,
+                ttl,
+                e
+            );
+        }
+        sent_at.push(Instant::now());
+    }
+
+    let mut hops: Vec<HopReply> = Vec::new();
+    let deadline = Instant::now() + timeout;
+    loop {
+        let now = Instant::now();
+        if now >= deadline || round_complete(&hops) {
+            break;
+        }
+        let Some(incoming) = socket.recv(deadline - now)? else {
+            continue;
+        };
+        let received_at = Instant::now();
+
+        let parsed = match incoming {
+            Incoming::Packet { data, source, .. } => {
+                if let Some(reply) = parse_echo_reply(&data, ipv6, &token) {
+                    Some((reply.sequence, source.unwrap_or(addr.ip()), true))
+                } else {
+                    parse_icmp_error(&data, ipv6, identifier, &token).and_then(|error| {
+                        Some((
+                            error.sequence,
+                            source?,
+                            is_unreachable(error.icmp_type, ipv6),
+                        ))
+                    })
+                }
+            }
+            Incoming::QueuedError {
+                data,
+                icmp_type,
+                offender,
+                ..
+            } => parse_quoted_request(&data, ipv6, &token, None)
+                .and_then(|sequence| Some((sequence, offender?, is_unreachable(icmp_type, ipv6)))),
+        };
+        let Some((sequence, address, terminal)) = parsed else {
+            continue;
+        };
+
+        let ttl = sequence.wrapping_sub(base);
+        if ttl == 0 || ttl > max_hops as u16 || hops.iter().any(|h| h.ttl as u16 == ttl) {
+            continue;
+        }
+        let rtt_ms = received_at
+            .duration_since(sent_at[ttl as usize - 1])
+            .as_secs_f64()
+            * 1000.0;
+        hops.push(HopReply {
+            ttl: ttl as u8,
+            address,
+            rtt_ms,
+            terminal,
+        });
+    }
+
+    hops.sort_by_key(|h| h.ttl);
+    log::debug!(
+        "icmp::trace_round: {} - probed {} hops, {} answered",
+        host,
+        max_hops,
+        hops.len()
+    );
+    Ok(hops)
+}
+
+// Every hop up to the first terminal one has answered.
+fn round_complete(hops: &[HopReply]) -> bool {
+    let Some(last) = hops.iter().filter(|h| h.terminal).map(|h| h.ttl).min() else {
+        return false;
+    };
+    (1..=last).all(|ttl| hops.iter().any(|h| h.ttl == ttl))
+}
+
+// Sends a single don't-fragment echo request whose IP packet is `size` bytes
+// and reports whether it got through.
+pub fn probe_size(
+    host: &str,
+    size: u32,
+    timeout: Duration,
+    interface: Option<&str>,
+) -> io::Result<SizeProbe> {
+    let addr = resolve(host)?;
+    let ipv6 = addr.is_ipv6();
+    let socket = IcmpSocket::open(&addr)?;
+    if let Some(interface) = interface {
+        bind_to_interface(socket.fd.as_raw_fd(), interface, ipv6)?;
+    }
+    socket.set_dont_fragment(ipv6)?;
+    #[cfg(target_os = "linux")]
+    socket.enable_error_queue(ipv6);
+
+    let ip_header_len = if ipv6 {
+        IPV6_HEADER_LEN
+    } else {
+        IPV4_HEADER_LEN
+    };
+    let payload_len = (size as usize)
+        .checked_sub(ip_header_len + ICMP_HEADER_LEN)
+        .filter(|len| *len >= TOKEN_LEN)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} bytes is too small to probe", size),
+            )
+        })?;
+
+    let token = make_token();
+    let identifier = (std::process::id() & 0xffff) as u16;
+    let packet = build_sized_echo_request(ipv6, identifier, 0, &token, payload_len);
+    if let Err(e) = socket.send_to(&packet, &addr) {
+        if e.raw_os_error() == Some(libc::EMSGSIZE) {
+            return Ok(SizeProbe::TooBig(None));
+        }
+        return Err(e);
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(SizeProbe::NoReply);
+        }
+        let Some(incoming) = socket.recv(deadline - now)? else {
+            continue;
+        };
+
+        let error = match incoming {
+            Incoming::Packet { data, .. } => {
+                if parse_echo_reply(&data, ipv6, &token).is_some() {
+                    return Ok(SizeProbe::Fits);
+                }
+                parse_icmp_error(&data, ipv6, identifier, &token)
+            }
+            Incoming::QueuedError {
+                data,
+                icmp_type,
+                code,
+                info,
+                ..
+            } => parse_quoted_request(&data, ipv6, &token, None).map(|sequence| IcmpError {
+                sequence,
+                icmp_type,
+                code,
+                info,
+            }),
+        };
+        if let Some(error) = error.filter(|e| is_too_big(e, ipv6)) {
+            return Ok(SizeProbe::TooBig((error.info > 0).then_some(error.info)));
+        }
+    }
+}
+
+fn is_too_big(error: &IcmpError, ipv6: bool) -> bool {
+    if ipv6 {
+        error.icmp_type == ICMPV6_PACKET_TOO_BIG
+    } else {
+        error.icmp_type == ICMP_DEST_UNREACHABLE && error.code == ICMP_FRAG_NEEDED
+    }
+}
+
+fn is_unreachable(icmp_type: u8, ipv6: bool) -> bool {
+    if ipv6 {
+        icmp_type == ICMPV6_DEST_UNREACHABLE
+    } else {
+        icmp_type == ICMP_DEST_UNREACHABLE
+    }
+}
+
+fn resolve(host: &str) -> io::Result<SocketAddr> {
+    (host, 0)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no address for {}", host)))
+}
+
+fn make_token() -> [u8; TOKEN_LEN] {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    (nanos ^ ((std::process::id() as u64) << 32)).to_be_bytes()
+}
+
+fn build_echo_request(
+    ipv6: bool,
+    identifier: u16,
+    sequence: u16,
+    token: &[u8; TOKEN_LEN],
+) -> Vec<u8> {
+    build_sized_echo_request(ipv6, identifier, sequence, token, PAYLOAD_LEN)
+}
+
+fn build_sized_echo_request(
+    ipv6: bool,
+    identifier: u16,
+    sequence: u16,
+    token: &[u8; TOKEN_LEN],
+    payload_len: usize,
+) -> Vec<u8> {
+    let mut packet = vec![0u8; ICMP_HEADER_LEN + payload_len];
+    packet[0] = if ipv6 {
+        ICMPV6_ECHO_REQUEST
+    } else {
+        ICMP_ECHO_REQUEST
+    };
+    packet[4..6].copy_from_slice(&identifier.to_be_bytes());
+    packet[6..8].copy_from_slice(&sequence.to_be_bytes());
+    packet[ICMP_HEADER_LEN..ICMP_HEADER_LEN + TOKEN_LEN].copy_from_slice(token);
+    for (i, byte) in packet[ICMP_HEADER_LEN + TOKEN_LEN..].iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+
+    // The kernel fills in the ICMPv6 checksum since it covers the IPv6 pseudo-header.
+    if !ipv6 {
+        let checksum = internet_checksum(&packet);
+        packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+    }
+    packet
+}
+
+fn internet_checksum(data: &[u8]) -> u16 {
+    let mut sum: u32 = data
+        .chunks(2)
+        .map(|chunk| {
+            let hi = chunk[0] as u32;
+            let lo = chunk.get(1).copied().unwrap_or(0) as u32;
+            (hi << 8) | lo
+        })
+        .sum();
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+fn parse_echo_reply(packet: &[u8], ipv6: bool, token: &[u8; TOKEN_LEN]) -> Option<ParsedReply> {
+    // Raw IPv4 sockets (and datagram ICMP sockets on macOS) hand us the IP header too.
+    let (icmp, header_ttl) = if !ipv6 && packet.first().map(|b| b >> 4) == Some(4) {
+        let header_len = ((packet[0] & 0x0f) as usize) * 4;
+        (packet.get(header_len..)?, packet.get(8).copied())
+    } else {
+        (packet, None)
+    };
+
+    let expected_type = if ipv6 {
+        ICMPV6_ECHO_REPLY
+    } else {
+        ICMP_ECHO_REPLY
+    };
+    if icmp.len() < ICMP_HEADER_LEN + TOKEN_LEN || icmp[0] != expected_type {
+        return None;
+    }
+    if &icmp[ICMP_HEADER_LEN..ICMP_HEADER_LEN + TOKEN_LEN] != token {
+        return None;
+    }
+
+    Some(ParsedReply {
+        sequence: u16::from_be_bytes([icmp[6], icmp[7]]),
+        ttl: header_ttl,
+    })
+}
+
+// Matches a time-exceeded, unreachable or packet-too-big error that quotes one
+// of our echo requests.
+fn parse_icmp_error(
+    packet: &[u8],
+    ipv6: bool,
+    identifier: u16,
+    token: &[u8; TOKEN_LEN],
+) -> Option<IcmpError> {
+    let icmp = if !ipv6 && packet.first().map(|b| b >> 4) == Some(4) {
+        packet.get(((packet[0] & 0x0f) as usize) * 4..)?
+    } else {
+        packet
+    };
+
+    let error_types: &[u8] = if ipv6 {
+        &[
+            ICMPV6_TIME_EXCEEDED,
+            ICMPV6_DEST_UNREACHABLE,
+            ICMPV6_PACKET_TOO_BIG,
+        ]
+    } else {
+        &[ICMP_TIME_EXCEEDED, ICMP_DEST_UNREACHABLE]
+    };
+    let icmp_type = *icmp.first()?;
+    if !error_types.contains(&icmp_type) {
+        return None;
+    }
+    let code = *icmp.get(1)?;
+    let info = u32::from_be_bytes(icmp.get(4..8)?.try_into().ok()?);
+
+    let inner = icmp.get(ICMP_HEADER_LEN..)?;
+    let inner_header_len = if ipv6 {
+        if *inner.get(6)? as libc::c_int != libc::IPPROTO_ICMPV6 {
+            return None;
+        }
+        IPV6_HEADER_LEN
+    } else {
+        ((*inner.first()? & 0x0f) as usize) * 4
+    };
+    let quoted = inner.get(inner_header_len..)?;
+    let sequence = parse_quoted_request(quoted, ipv6, token, Some(identifier))?;
+    Some(IcmpError {
+        sequence,
+        icmp_type,
+        code,
+        info,
+    })
+}
+
+// Routers must quote at least the first 8 bytes of the request, which stops
+// short of our token. Short quotes are matched on the identifier instead.
+fn parse_quoted_request(
+    quoted: &[u8],
+    ipv6: bool,
+    token: &[u8; TOKEN_LEN],
+    identifier: Option<u16>,
+) -> Option<u16> {
+    let request_type = if ipv6 {
+        ICMPV6_ECHO_REQUEST
+    } else {
+        ICMP_ECHO_REQUEST
+    };
+    if quoted.len() < ICMP_HEADER_LEN || quoted[0] != request_type {
+        return None;
+    }
+    if let Some(quoted_token) = quoted.get(ICMP_HEADER_LEN..ICMP_HEADER_LEN + TOKEN_LEN) {
+        if quoted_token != token {
+            return None;
+        }
+    } else if let Some(identifier) = identifier {
+        if u16::from_be_bytes([quoted[4], quoted[5]]) != identifier {
+            return None;
+        }
+    }
+    Some(u16::from_be_bytes([quoted[6], quoted[7]]))
+}
+
+struct IcmpSocket {
+    fd: OwnedFd,
+}
+
+impl IcmpSocket {
+    fn open(addr: &SocketAddr) -> io::Result<Self> {
+        let (domain, protocol) = if addr.is_ipv6() {
+            (libc::AF_INET6, libc::IPPROTO_ICMPV6)
+        } else {
+            (libc::AF_INET, libc::IPPROTO_ICMP)
+        };
+
+        let fd = match open_socket(domain, libc::SOCK_DGRAM, protocol) {
+            Ok(fd) => fd,
+            Err(e) => {
+                log::debug!(
+                    "icmp: datagram ICMP socket unavailable ({}), trying raw socket",
+                    e
+                );
+                open_socket(domain, libc::SOCK_RAW, protocol)?
+            }
+        };
+
+        let (level, option) = if addr.is_ipv6() {
+            (libc::IPPROTO_IPV6, libc::IPV6_RECVHOPLIMIT)
+        } else {
+            (libc::IPPROTO_IP, libc::IP_RECVTTL)
+        };
+        let enable: libc::c_int = 1;
+        let ret = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                level,
+                option,
+                &enable as *const libc::c_int as *const libc::c_void,
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            log::debug!(
+                "icmp: failed to request TTLs: {}",
+                io::Error::last_os_error()
+            );
+        }
+
+        Ok(Self { fd })
+    }
+
+    fn set_option(
+        &self,
+        level: libc::c_int,
+        option: libc::c_int,
+        value: libc::c_int,
+    ) -> io::Result<()> {
+        let ret = unsafe {
+            libc::setsockopt(
+                self.fd.as_raw_fd(),
+                level,
+                option,
+                &value as *const libc::c_int as *const libc::c_void,
+                mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn set_ttl(&self, ttl: u8, ipv6: bool) -> io::Result<()> {
+        if ipv6 {
+            self.set_option(
+                libc::IPPROTO_IPV6,
+                libc::IPV6_UNICAST_HOPS,
+                ttl as libc::c_int,
+            )
+        } else {
+            self.set_option(libc::IPPROTO_IP, libc::IP_TTL, ttl as libc::c_int)
+        }
+    }
+
+    // Linux's probe mode sets DF and also ignores any path MTU the kernel has
+    // cached, so we measure the path rather than what was learned earlier.
+    #[cfg(target_os = "linux")]
+    fn set_dont_fragment(&self, ipv6: bool) -> io::Result<()> {
+        if ipv6 {
+            self.set_option(
+                libc::IPPROTO_IPV6,
+                libc::IPV6_MTU_DISCOVER,
+                libc::IPV6_PMTUDISC_PROBE,
+            )
+        } else {
+            self.set_option(
+                libc::IPPROTO_IP,
+                libc::IP_MTU_DISCOVER,
+                libc::IP_PMTUDISC_PROBE,
+            )
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn set_dont_fragment(&self, ipv6: bool) -> io::Result<()> {
+        if ipv6 {
+            self.set_option(libc::IPPROTO_IPV6, libc::IPV6_DONTFRAG, 1)
+        } else {
+            self.set_option(libc::IPPROTO_IP, libc::IP_DONTFRAG, 1)
+        }
+    }
+
+    // Datagram ICMP sockets on Linux only see ICMP errors through the error
+    // queue, and only once asked for them.
+    #[cfg(target_os = "linux")]
+    fn enable_error_queue(&self, ipv6: bool) {
+        let result = if ipv6 {
+            self.set_option(libc::IPPROTO_IPV6, libc::IPV6_RECVERR, 1)
+        } else {
+            self.set_option(libc::IPPROTO_IP, libc::IP_RECVERR, 1)
+        };
+        if let Err(e) = result {
+            log::debug!("icmp: failed to enable the error queue: {}", e);
+        }
+    }
+
+    fn send_to(&self, packet: &[u8], addr: &SocketAddr) -> io::Result<()> {
+        let (storage, len) = sockaddr_from(addr);
+        let sent = unsafe {
+            libc::sendto(
+                self.fd.as_raw_fd(),
+                packet.as_ptr() as *const libc::c_void,
+                packet.len(),
+                0,
+                &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+                len,
+            )
+        };
+        if sent < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    fn recv(&self, timeout: Duration) -> io::Result<Option<Incoming>> {
+        let mut pollfd = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout_ms = timeout.as_millis().min(i32::MAX as u128) as libc::c_int;
+        let ready = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+        if ready < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                return Ok(None);
+            }
+            return Err(err);
+        }
+        if ready == 0 {
+            return Ok(None);
+        }
+
+        #[cfg(target_os = "linux")]
+        if pollfd.revents & libc::POLLERR != 0 {
+            if let Some(error) = self.recv_error()? {
+                return Ok(Some(error));
+            }
+        }
+        if pollfd.revents & libc::POLLIN == 0 {
+            return Ok(None);
+        }
+
+        let mut buf = vec![0u8; RECV_BUFFER_SIZE];
+        let mut control = [0u8; CONTROL_BUFFER_SIZE];
+        let mut source: libc::sockaddr_storage = unsafe { mem::zeroed() };
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_name = &mut source as *mut libc::sockaddr_storage as *mut libc::c_void;
+        msg.msg_namelen = mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = control.len() as _;
+
+        let received = unsafe { libc::recvmsg(self.fd.as_raw_fd(), &mut msg, 0) };
+        if received < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        buf.truncate(received as usize);
+
+        let source = if msg.msg_namelen > 0 {
+            unsafe { ip_from_sockaddr(&source as *const libc::sockaddr_storage as *const u8) }
+        } else {
+            None
+        };
+        Ok(Some(Incoming::Packet {
+            data: buf,
+            ttl: ttl_from_control(&msg),
+            source,
+        }))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn recv_error(&self) -> io::Result<Option<Incoming>> {
+        let mut buf = vec![0u8; RECV_BUFFER_SIZE];
+        let mut control = [0u8; CONTROL_BUFFER_SIZE];
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as *mut libc::c_void,
+            iov_len: buf.len(),
+        };
+        let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as *mut libc::c_void;
+        msg.msg_controllen = control.len() as _;
+
+        let received = unsafe {
+            libc::recvmsg(
+                self.fd.as_raw_fd(),
+                &mut msg,
+                libc::MSG_ERRQUEUE | libc::MSG_DONTWAIT,
+            )
+        };
+        if received < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::WouldBlock {
+                return Ok(None);
+            }
+            return Err(err);
+        }
+        buf.truncate(received as usize);
+
+        let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        while !cmsg.is_null() {
+            let (level, ty) = unsafe { ((*cmsg).cmsg_level, (*cmsg).cmsg_type) };
+            if (level == libc::IPPROTO_IP && ty == libc::IP_RECVERR)
+                || (level == libc::IPPROTO_IPV6 && ty == libc::IPV6_RECVERR)
+            {
+                let data = unsafe { libc::CMSG_DATA(cmsg) };
+                let err = unsafe { (data as *const libc::sock_extended_err).read_unaligned() };
+                if err.ee_origin != libc::SO_EE_ORIGIN_ICMP
+                    && err.ee_origin != libc::SO_EE_ORIGIN_ICMP6
+                {
+                    return Ok(None);
+                }
+                // The offending router's address follows the error struct.
+                let offender = unsafe {
+                    ip_from_sockaddr(data.add(mem::size_of::<libc::sock_extended_err>()))
+                };
+                return Ok(Some(Incoming::QueuedError {
+                    data: buf,
+                    icmp_type: err.ee_type,
+                    code: err.ee_code,
+                    info: err.ee_info,
+                    offender,
+                }));
+            }
+            cmsg = unsafe { libc::CMSG_NXTHDR(&msg, cmsg) };
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn bind_to_interface(fd: RawFd, interface: &str, _ipv6: bool) -> io::Result<()> {
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            interface.as_ptr() as *const libc::c_void,
+            interface.len() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn bind_to_interface(fd: RawFd, interface: &str, ipv6: bool) -> io::Result<()> {
+    let name = std::ffi::CString::new(interface)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let index = unsafe { libc::if_nametoindex(name.as_ptr()) } as libc::c_int;
+    if index == 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let (level, option) = if ipv6 {
+        (libc::IPPROTO_IPV6, libc::IPV6_BOUND_IF)
+    } else {
+        (libc::IPPROTO_IP, libc::IP_BOUND_IF)
+    };
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            option,
+            &index as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub(crate) fn open_socket(
+    domain: libc::c_int,
+    ty: libc::c_int,
+    protocol: libc::c_int,
+) -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::socket(domain, ty, protocol) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn ttl_from_control(msg: &libc::msghdr) -> Option<u8> {
+    let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(msg) };
+    while !cmsg.is_null() {
+        let (level, ty, len) = unsafe { ((*cmsg).cmsg_level, (*cmsg).cmsg_type, (*cmsg).cmsg_len) };
+        let is_ttl = (level == libc::IPPROTO_IP && (ty == libc::IP_TTL || ty == libc::IP_RECVTTL))
+            || (level == libc::IPPROTO_IPV6 && ty == libc::IPV6_HOPLIMIT);
+        if is_ttl {
+            let int_len = unsafe { libc::CMSG_LEN(mem::size_of::<libc::c_int>() as u32) };
+            let data = unsafe { libc::CMSG_DATA(cmsg) };
+            // Linux reports an int, macOS a single byte.
+            let ttl = if len as u64 >= int_len as u64 {
+                unsafe { (data as *const libc::c_int).read_unaligned() as u8 }
+            } else {
+                unsafe { *data }
+            };
+            return Some(ttl);
+        }
+        cmsg = unsafe { libc::CMSG_NXTHDR(msg, cmsg) };
+    }
+    None
+}
+
+// Reads the address out of a sockaddr_in or sockaddr_in6, which need not be
+// aligned when it comes from a control message.
+unsafe fn ip_from_sockaddr(ptr: *const u8) -> Option<IpAddr> {
+    let family = (ptr as *const libc::sockaddr).read_unaligned().sa_family as libc::c_int;
+    match family {
+        libc::AF_INET => {
+            let sin = (ptr as *const libc::sockaddr_in).read_unaligned();
+            Some(IpAddr::V4(Ipv4Addr::from(
+                sin.sin_addr.s_addr.to_ne_bytes(),
+            )))
+        }
+        libc::AF_INET6 => {
+            let sin6 = (ptr as *const libc::sockaddr_in6).read_unaligned();
+            Some(IpAddr::V6(Ipv6Addr::from(sin6.sin6_addr.s6_addr)))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn sockaddr_from(addr: &SocketAddr) -> (libc::sockaddr_storage, libc::socklen_t) {
+    let mut storage: libc::sockaddr_storage = unsafe { mem::zeroed() };
+    let len = match addr {
+        SocketAddr::V4(v4) => {
+            let sin = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in) };
+            sin.sin_family = libc::AF_INET as libc::sa_family_t;
+            sin.sin_port = v4.port().to_be();
+            sin.sin_addr = libc::in_addr {
+                s_addr: u32::from_ne_bytes(v4.ip().octets()),
+            };
+            mem::size_of::<libc::sockaddr_in>()
+        }
+        SocketAddr::V6(v6) => {
+            let sin6 = unsafe { &mut *(&mut storage as *mut _ as *mut libc::sockaddr_in6) };
+            sin6.sin6_family = libc::AF_INET6 as libc::sa_family_t;
+            sin6.sin6_port = v6.port().to_be();
+            sin6.sin6_addr = libc::in6_addr {
+                s6_addr: v6.ip().octets(),
+            };
+            sin6.sin6_scope_id = v6.scope_id();
+            mem::size_of::<libc::sockaddr_in6>()
+        }
+    };
+    (storage, len as libc::socklen_t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: [u8; TOKEN_LEN] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+    fn as_reply(mut request: Vec<u8>, reply_type: u8) -> Vec<u8> {
+        request[0] = reply_type;
+        request
+    }
+
+    #[test]
+    fn test_build_echo_request() {
+        let packet = build_echo_request(false, 0x1234, 7, &TOKEN);
+        assert_eq!(packet.len(), ICMP_HEADER_LEN + PAYLOAD_LEN);
+        assert_eq!(packet[0], ICMP_ECHO_REQUEST);
+        assert_eq!(&packet[4..8], &[0x12, 0x34, 0x00, 0x07]);
+        assert_eq!(&packet[8..16], &TOKEN);
+        assert_eq!(internet_checksum(&packet), 0);
+
+        let packet = build_echo_request(true, 0x1234, 7, &TOKEN);
+        assert_eq!(packet[0], ICMPV6_ECHO_REQUEST);
+        assert_eq!(&packet[2..4], &[0, 0]);
+    }
+
+    #[test]
+    fn test_parse_echo_reply() {
+        let reply = as_reply(build_echo_request(false, 1, 3, &TOKEN), ICMP_ECHO_REPLY);
+        assert_eq!(
+            parse_echo_reply(&reply, false, &TOKEN),
+            Some(ParsedReply {
+                sequence: 3,
+                ttl: None
+            })
+        );
+
+        let mut with_ip_header = vec![
+            0x45, 0x00, 0x00, 0x54, 0x00, 0x00, 0x00, 0x00, 0x39, 0x01, 0x00, 0x00, 1, 1, 1, 1,
+            192, 168, 1, 20,
+        ];
+        with_ip_header.extend_from_slice(&reply);
+        assert_eq!(
+            parse_echo_reply(&with_ip_header, false, &TOKEN),
+            Some(ParsedReply {
+                sequence: 3,
+                ttl: Some(57)
+            })
+        );
+
+        let foreign = as_reply(
+            build_echo_request(false, 1, 3, &[9; TOKEN_LEN]),
+            ICMP_ECHO_REPLY,
+        );
+        assert_eq!(parse_echo_reply(&foreign, false, &TOKEN), None);
+
+        let request = build_echo_request(false, 1, 3, &TOKEN);
+        assert_eq!(parse_echo_reply(&request, false, &TOKEN), None);
+
+        let v6_reply = as_reply(build_echo_request(true, 1, 9, &TOKEN), ICMPV6_ECHO_REPLY);
+        assert_eq!(
+            parse_echo_reply(&v6_reply, true, &TOKEN).unwrap().sequence,
+            9
+        );
+    }
+
+    #[test]
+    fn test_parse_icmp_error() {
+        let request = build_echo_request(false, 0x1234, 42, &TOKEN);
+        let ip_header = |protocol: u8, src: [u8; 4]| {
+            let mut header = vec![
+                0x45, 0x00, 0x00, 0x54, 0x00, 0x00, 0x00, 0x00, 0x01, protocol, 0x00, 0x00,
+            ];
+            header.extend_from_slice(&src);
+            header.extend_from_slice(&[1, 1, 1, 1]);
+            header
+        };
+        let error = |icmp_type: u8, code: u8, info: u32, quoted: &[u8]| {
+            let mut packet = ip_header(1, [10, 0, 0, 1]);
+            packet.extend_from_slice(&[icmp_type, code, 0, 0]);
+            packet.extend_from_slice(&info.to_be_bytes());
+            packet.extend_from_slice(&ip_header(1, [192, 168, 1, 20]));
+            packet.extend_from_slice(quoted);
+            packet
+        };
+        let parse = |packet: &[u8], ipv6: bool, identifier: u16| {
+            parse_icmp_error(packet, ipv6, identifier, &TOKEN)
+                .map(|e| (e.sequence, e.icmp_type, e.info))
+        };
+
+        let time_exceeded = error(ICMP_TIME_EXCEEDED, 0, 0, &request);
+        assert_eq!(
+            parse(&time_exceeded, false, 0x1234),
+            Some((42, ICMP_TIME_EXCEEDED, 0))
+        );
+
+        let frag_needed = error(ICMP_DEST_UNREACHABLE, ICMP_FRAG_NEEDED, 1492, &request);
+        let parsed = parse_icmp_error(&frag_needed, false, 0x1234, &TOKEN).unwrap();
+        assert_eq!(parsed.info, 1492);
+        assert!(is_too_big(&parsed, false));
+        assert!(is_unreachable(parsed.icmp_type, false));
+
+        // Minimal quotes carry no token, so the identifier decides.
+        let short = error(ICMP_TIME_EXCEEDED, 0, 0, &request[..ICMP_HEADER_LEN]);
+        assert_eq!(
+            parse(&short, false, 0x1234),
+            Some((42, ICMP_TIME_EXCEEDED, 0))
+        );
+        assert_eq!(parse(&short, false, 0x4321), None);
+
+        let foreign = build_echo_request(false, 0x1234, 42, &[9; TOKEN_LEN]);
+        let foreign = error(ICMP_TIME_EXCEEDED, 0, 0, &foreign);
+        assert_eq!(parse(&foreign, false, 0x1234), None);
+
+        let reply = as_reply(request.clone(), ICMP_ECHO_REPLY);
+        assert_eq!(parse(&reply, false, 0x1234), None);
+
+        let v6_error = |icmp_type: u8, info: u32| {
+            let mut packet = vec![icmp_type, 0, 0, 0];
+            packet.extend_from_slice(&info.to_be_bytes());
+            let mut header = vec![0x60, 0, 0, 0, 0, 64, libc::IPPROTO_ICMPV6 as u8, 1];
+            header.resize(IPV6_HEADER_LEN, 0);
+            packet.extend_from_slice(&header);
+            packet.extend_from_slice(&build_echo_request(true, 0x1234, 7, &TOKEN));
+            packet
+        };
+        assert_eq!(
+            parse(&v6_error(ICMPV6_TIME_EXCEEDED, 0), true, 0x1234),
+            Some((7, ICMPV6_TIME_EXCEEDED, 0))
+        );
+        let too_big =
+            parse_icmp_error(&v6_error(ICMPV6_PACKET_TOO_BIG, 1280), true, 0x1234, &TOKEN).unwrap();
+        assert_eq!(too_big.info, 1280);
+        assert!(is_too_big(&too_big, true));
+    }
+
+    #[test]
+    #[ignore = "needs unprivileged ICMP sockets"]
+    fn test_probe_size_loopback() {
+        let probe = |size| probe_size("127.0.0.1", size, Duration::from_secs(1), None);
+        let fits = probe(1000).expect("ICMP sockets unavailable");
+        assert_eq!(fits, SizeProbe::Fits);
+
+        // No IPv4 packet can be this large, so the local stack refuses it.
+        assert_eq!(probe(70000).unwrap(), SizeProbe::TooBig(None));
+    }
+
+    #[test]
+    fn test_round_complete() {
+        let hop = |ttl: u8, terminal: bool| HopReply {
+            ttl,
+            address: IpAddr::V4(Ipv4Addr::new(10, 0, 0, ttl)),
+            rtt_ms: 1.0,
+            terminal,
+        };
+        assert!(!round_complete(&[hop(1, false), hop(2, false)]));
+        assert!(!round_complete(&[hop(1, false), hop(3, true)]));
+        assert!(round_complete(&[
+            hop(1, false),
+            hop(2, false),
+            hop(3, true)
+        ]));
+        assert!(round_complete(&[hop(1, true), hop(3, true)]));
+    }
+
+    #[test]
+    fn test_report_tracks_duplicates_and_reordering() {
+        let mut report = EchoReport {
+            sent: 4,
+            ..Default::default()
+        };
+        report.record(0, 10.0, Some(57));
+        report.record(2, 12.0, Some(57));
+        report.record(1, 30.0, Some(57));
+        report.record(2, 12.5, Some(57));
+
+        assert_eq!(report.received(), 3);
+        assert_eq!(report.duplicates(), 1);
+        assert_eq!(report.out_of_order(), 1);
+        assert!(report.replies[2].out_of_order);
+        assert!(report.replies[3].duplicate);
+        assert_eq!(report.rtts_ms(), vec![10.0, 12.0, 30.0]);
+    }
+
+    #[test]
+    #[ignore = "needs unprivileged ICMP sockets"]
+    fn test_echo_loopback() {
+        let report = echo(
+            "127.0.0.1",
+            3,
+            Duration::from_millis(10),
+            Duration::from_secs(1),
+            None,
+        )
+        .expect("ICMP sockets unavailable");
+
+        assert_eq!(report.sent, 3);
+        assert_eq!(report.received(), 3);
+        assert_eq!(report.duplicates(), 0);
+        assert!(report.replies.iter().all(|r| r.ttl.is_some()));
+        let mut sequences: Vec<u16> = report.replies.iter().map(|r| r.sequence).collect();
+        sequences.sort();
+        assert_eq!(sequences, vec![0, 1, 2]);
+    }
+
+    #[test]
+    #[ignore = "needs unprivileged ICMP sockets"]
+    fn test_trace_round_loopback() {
+        let hops = trace_round("127.0.0.1", 3, Duration::from_secs(1), None)
+            .expect("ICMP sockets unavailable");
+
+        let first = &hops[0];
+        assert_eq!(first.ttl, 1);
+        assert!(first.terminal);
+        assert_eq!(first.address, IpAddr::V4(Ipv4Addr::LOCALHOST));
+    }
+}
@@ -0,0 +1,426 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::runner::CommandRunner;
+
+static HARDWARE_PORT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"Hardware Port:\s*(.+)\nDevice:\s*(\S+)(?:\nEthernet Address:\s*(\S+))?").unwrap()
+});
+static IFCONFIG_HEADER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([A-Za-z0-9.]+):\s*flags=\w+<([^>]*)>").unwrap());
+static IFCONFIG_ETHER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s+ether\s+(\S+)").unwrap());
+static IFCONFIG_STATUS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s+status:\s*(\S+)").unwrap());
+
+const VIRTUAL_DEVTYPES: [&str; 7] = [
+    "bridge",
+    "vlan",
+    "bond",
+    "wireguard",
+    "tun",
+    "macvlan",
+    "vxlan",
+];
+const VIRTUAL_PREFIXES: [&str; 16] = [
+    "veth",
+    "docker",
+    "br-",
+    "virbr",
+    "vmnet",
+    "vboxnet",
+    "tun",
+    "tap",
+    "wg",
+    "tailscale",
+    "zt",
+    "lxc",
+    "cni",
+    "utun",
+    "awdl",
+    "llw",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterfaceKind {
+    Wireless,
+    Ethernet,
+    Virtual,
+    Loopback,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub kind: InterfaceKind,
+    pub up: bool,
+    pub mac_address: Option<String>,
+    pub service: Option<String>,
+}
+
+pub fn list_interfaces(runner: &dyn CommandRunner) -> Vec<NetworkInterface> {
+    let interfaces = if cfg!(target_os = "linux") {
+        list_interfaces_sysfs(runner)
+    } else {
+        list_interfaces_networksetup(runner)
+    };
+    log::debug!(
+        "list_interfaces: {:?}",
+        interfaces
+            .iter()
+            .map(|i| format!("{} ({:?}, up: {})", i.name, i.kind, i.up))
+            .collect::<Vec<_>>()
+    );
+    interfaces
+}
+
+pub fn select_wireless_interface<'a>(
+    interfaces: &'a [NetworkInterface],
+    preferred: Option<&str>,
+) -> Option<&'a NetworkInterface> {
+    if let Some(name) = preferred {
+        if let Some(interface) = interfaces.iter().find(|i| i.name == name) {
+            return Some(interface);
+        }
+        log::debug!(
+            "select_wireless_interface: preferred interface {} not found, picking automatically",
+            name
+        );
+    }
+
+    interfaces
+        .iter()
+        .filter(|i| i.kind == InterfaceKind::Wireless)
+        .min_by_key(|i| !i.up)
+}
+
+fn list_interfaces_sysfs(runner: &dyn CommandRunner) -> Vec<NetworkInterface> {
+    let proc_net_dev = match runner.read_file("/proc/net/dev") {
+        Ok(content) => content,
+        Err(e) => {
+            log::error!("list_interfaces: failed to read /proc/net/dev: {}", e);
+            return Vec::new();
+        }
+    };
+
+    parse_proc_net_dev(&proc_net_dev)
+        .into_iter()
+        .map(|name| {
+            let read = |attr: &str| {
+                runner
+                    .read_file(&format!("/sys/class/net/{}/{}", name, attr))
+                    .ok()
+            };
+            let uevent = read("uevent").unwrap_or_default();
+            let operstate = read("operstate").unwrap_or_default();
+            let mac_address = read("address")
+                .map(|a| a.trim().to_string())
+                .filter(|a| !a.is_empty() && a != "00:00:00:00:00:00");
+
+            NetworkInterface {
+                kind: classify_sysfs_interface(&name, &uevent),
+                // Loopback and tunnel devices report "unknown" while they are up.
+                up: matches!(operstate.trim(), "up" | "unknown"),
+                mac_address,
+                service: None,
+                name,
+            }
+        })
+        .collect()
+}
+
+fn parse_proc_net_dev(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .skip(2)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, _)| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+fn classify_sysfs_interface(name: &str, uevent: &str) -> InterfaceKind {
+    let devtype = uevent
+        .lines()
+        .find_map(|line| line.strip_prefix("DEVTYPE="))
+        .map(str::trim);
+
+    if name == "lo" {
+        InterfaceKind::Loopback
+    } else if devtype == Some("wlan") {
+        InterfaceKind::Wireless
+    } else if devtype.is_some_and(|t| VIRTUAL_DEVTYPES.contains(&t)) || is_virtual_name(name) {
+        InterfaceKind::Virtual
+    } else {
+        InterfaceKind::Ethernet
+    }
+}
+
+fn is_virtual_name(name: &str) -> bool {
+    VIRTUAL_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+}
+
+fn list_interfaces_networksetup(runner: &dyn CommandRunner) -> Vec<NetworkInterface> {
+    let ports = match runner.run("networksetup", &["-listallhardwareports"]) {
+        Ok(output) if output.success => output.stdout,
+        Ok(_) => {
+            log::error!("list_interfaces: networksetup -listallhardwareports failed");
+            String::new()
+        }
+        Err(e) => {
+            log::error!("list_interfaces: failed to run networksetup: {}", e);
+            String::new()
+        }
+    };
+
+    let ifconfig = match runner.run("ifconfig", &[]) {
+        Ok(output) if output.success => output.stdout,
+        Ok(_) => {
+            log::error!("list_interfaces: ifconfig failed");
+            String::new()
+        }
+        Err(e) => {
+            log::error!("list_interfaces: failed to run ifconfig: {}", e);
+            String::new()
+        }
+    };
+
+    merge_macos_interfaces(&parse_hardware_ports(&ports), &parse_ifconfig(&ifconfig))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct HardwarePort {
+    port: String,
+    device: String,
+    mac_address: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct IfconfigEntry {
+    name: String,
+    up: bool,
+    mac_address: Option<String>,
+}
+
+fn parse_hardware_ports(output: &str) -> Vec<HardwarePort> {
+    HARDWARE_PORT_RE
+        .captures_iter(output)
+        .map(|caps| HardwarePort {
+            port: caps[1].trim().to_string(),
+            device: caps[2].to_string(),
+            mac_address: caps
+                .get(3)
+                .map(|m| m.as_str().to_string())
+                .filter(|mac| mac != "N/A"),
+        })
+        .collect()
+}
+
+fn parse_ifconfig(output: &str) -> Vec<IfconfigEntry> {
+    let mut entries: Vec<IfconfigEntry> = Vec::new();
+
+    for line in output.lines() {
+        if let Some(caps) = IFCONFIG_HEADER_RE.captures(line) {
+            entries.push(IfconfigEntry {
+                name: caps[1].to_string(),
+                up: caps[2].split(',').any(|flag| flag == "UP"),
+                mac_address: None,
+            });
+            continue;
+        }
+
+        let Some(entry) = entries.last_mut() else {
+            continue;
+        };
+        if let Some(caps) = IFCONFIG_ETHER_RE.captures(line) {
+            entry.mac_address = Some(caps[1].to_string());
+        } else if let Some(caps) = IFCONFIG_STATUS_RE.captures(line) {
+            // Interfaces without a status line (loopback, tunnels) go by their UP flag alone.
+            entry.up = entry.up && &caps[1] == "active";
+        }
+    }
+
+    entries
+}
+
+fn merge_macos_interfaces(
+    ports: &[HardwarePort],
+    ifconfig: &[IfconfigEntry],
+) -> Vec<NetworkInterface> {
+    let mut interfaces: Vec<NetworkInterface> = ports
+        .iter()
+        .map(|port| {
+            let entry = ifconfig.iter().find(|e| e.name == port.device);
+            NetworkInterface {
+                name: port.device.clone(),
+                kind: classify_hardware_port(&port.port),
+                up: entry.is_some_and(|e| e.up),
+                mac_address: port
+                    .mac_address
+                    .clone()
+                    .or_else(|| entry.and_then(|e| e.mac_address.clone())),
+                service: Some(port.port.clone()),
+            }
+        })
+        .collect();
+
+    for entry in ifconfig {
+        if interfaces.iter().any(|i| i.name == entry.name) {
+            continue;
+        }
+        interfaces.push(NetworkInterface {
+            name: entry.name.clone(),
+            kind: if entry.name.starts_with("lo") {
+                InterfaceKind::Loopback
+            } else {
+                InterfaceKind::Virtual
+            },
+            up: entry.up,
+            mac_address: entry.mac_address.clone(),
+            service: None,
+        });
+    }
+
+    interfaces
+}
+
+fn classify_hardware_port(port: &str) -> InterfaceKind {
+    if port == "Wi-Fi" || port == "AirPort" {
+        InterfaceKind::Wireless
+    } else if port.contains("Bridge") || port.contains("VPN") {
+        InterfaceKind::Virtual
+    } else {
+        InterfaceKind::Ethernet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interface(name: &str, kind: InterfaceKind, up: bool) -> NetworkInterface {
+        NetworkInterface {
+            name: name.to_string(),
+            kind,
+            up,
+            mac_address: None,
+            service: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_sysfs_interfaces() {
+        let proc_net_dev = r#"Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:  918273    8123    0    0    0     0          0         0   918273    8123    0    0    0     0       0          0
+enp0s31f6:       0       0    0    0    0     0          0         0        0       0    0    0    0     0       0          0
+wlp2s0: 81726354   92817    0   12    0     0          0         0  9182736   51827    0    0    0     0       0          0
+docker0:       0       0    0    0    0     0          0         0        0       0    0    0    0     0       0          0
+"#;
+        assert_eq!(
+            parse_proc_net_dev(proc_net_dev),
+            vec!["lo", "enp0s31f6", "wlp2s0", "docker0"]
+        );
+
+        assert_eq!(
+            classify_sysfs_interface("wlp2s0", "DEVTYPE=wlan\nINTERFACE=wlp2s0\nIFINDEX=3\n"),
+            InterfaceKind::Wireless
+        );
+        assert_eq!(
+            classify_sysfs_interface("enp0s31f6", "INTERFACE=enp0s31f6\nIFINDEX=2\n"),
+            InterfaceKind::Ethernet
+        );
+        assert_eq!(
+            classify_sysfs_interface("docker0", "DEVTYPE=bridge\nINTERFACE=docker0\n"),
+            InterfaceKind::Virtual
+        );
+        assert_eq!(
+            classify_sysfs_interface("veth1a2b3c", "INTERFACE=veth1a2b3c\n"),
+            InterfaceKind::Virtual
+        );
+        assert_eq!(classify_sysfs_interface("lo", ""), InterfaceKind::Loopback);
+    }
+
+    #[test]
+    fn test_parse_macos_interfaces() {
+        let ports = r#"
+Hardware Port: Ethernet Adapter (en3)
+Device: en3
+Ethernet Address: 36:7d:da:11:22:33
+
+Hardware Port: Thunderbolt Bridge
+Device: bridge0
+Ethernet Address: N/A
+
+Hardware Port: Wi-Fi
+Device: en1
+Ethernet Address: 3c:22:fb:44:55:66
+
+VLAN Configurations
+===================
+"#;
+        let ifconfig = r#"lo0: flags=8049<UP,LOOPBACK,RUNNING,MULTICAST> mtu 16384
+	inet 127.0.0.1 netmask 0xff000000
+en1: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500
+	ether 3c:22:fb:44:55:66
+	inet 192.168.1.42 netmask 0xffffff00 broadcast 192.168.1.255
+	media: autoselect
+	status: active
+en3: flags=8863<UP,BROADCAST,SMART,RUNNING,SIMPLEX,MULTICAST> mtu 1500
+	ether 36:7d:da:11:22:33
+	media: autoselect (none)
+	status: inactive
+bridge0: flags=8822<BROADCAST,SMART,SIMPLEX,MULTICAST> mtu 1500
+	status: inactive
+utun0: flags=8051<UP,POINTOPOINT,RUNNING,MULTICAST> mtu 1380
+"#;
+
+        let interfaces =
+            merge_macos_interfaces(&parse_hardware_ports(ports), &parse_ifconfig(ifconfig));
+        let summary: Vec<(&str, InterfaceKind, bool)> = interfaces
+            .iter()
+            .map(|i| (i.name.as_str(), i.kind, i.up))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("en3", InterfaceKind::Ethernet, false),
+                ("bridge0", InterfaceKind::Virtual, false),
+                ("en1", InterfaceKind::Wireless, true),
+                ("lo0", InterfaceKind::Loopback, true),
+                ("utun0", InterfaceKind::Virtual, true),
+            ]
+        );
+        assert_eq!(interfaces[1].mac_address, None);
+        assert_eq!(interfaces[2].service, Some("Wi-Fi".to_string()));
+        assert_eq!(
+            interfaces[2].mac_address,
+            Some("3c:22:fb:44:55:66".to_string())
+        );
+    }
+
+    #[test]
+    fn test_select_wireless_interface() {
+        let interfaces = vec![
+            interface("enp0s31f6", InterfaceKind::Ethernet, true),
+            interface("wlx001122", InterfaceKind::Wireless, false),
+            interface("wlp2s0", InterfaceKind::Wireless, true),
+        ];
+
+        assert_eq!(
+            select_wireless_interface(&interfaces, None).map(|i| i.name.as_str()),
+            Some("wlp2s0")
+        );
+        assert_eq!(
+            select_wireless_interface(&interfaces, Some("wlx001122")).map(|i| i.name.as_str()),
+            Some("wlx001122")
+        );
+        assert_eq!(
+            select_wireless_interface(&interfaces, Some("wlan9")).map(|i| i.name.as_str()),
+            Some("wlp2s0")
+        );
+        assert_eq!(select_wireless_interface(&interfaces[..1], None), None);
+    }
+}
@@ -1,9 +1,12 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
 
-use crate::wifi::get_wifi_info;
+use crate::interfaces::NetworkInterface;
+use crate::pmtu::{mtu_suggestion, probe_path_mtus, PathMtu};
+use crate::probes::InternetTarget;
+use crate::runner::CommandRunner;
+use crate::wifi::{frequency_to_channel, get_wifi_info, run_iw};
 
 static OTHER_NETWORKS_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"Other Local Wi-Fi Networks:").unwrap());
@@ -13,12 +16,28 @@ static NETWORK_ENTRY_RE: Lazy<Regex> = Lazy::new(|| {
 static NETWORK_CHANNEL_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"Channel:\s*(\d+)(?:\s*\((\d+(?:\.\d+)?)\s*GHz)?").unwrap()
 });
+static IW_SCAN_BSS_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^BSS ([0-9a-fA-F:]{17})(.*)$").unwrap());
+static IW_SCAN_FREQ_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^\s*freq:\s*(\d+)").unwrap());
+static IW_SCAN_SIGNAL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*signal:\s*(-?[\d.]+) dBm").unwrap());
+static IW_SCAN_SSID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^\s*SSID:[ ]?(.*)$").unwrap());
+static IW_SCAN_VHT_WIDTH_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\*\s*channel width:\s*\d+\s*\((\d+) MHz\)").unwrap());
+static IW_SCAN_HT_OFFSET_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\*\s*secondary channel offset:\s*(above|below)").unwrap());
+
+pub(crate) const HIDDEN_SSID: &str = "(hidden)";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NearbyNetwork {
     pub ssid: String,
+    pub bssid: Option<String>,
     pub channel: u32,
     pub frequency_ghz: f64,
+    pub frequency_mhz: Option<u32>,
+    pub width_mhz: Option<u32>,
+    pub signal_dbm: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,12 +50,17 @@ pub struct InterferenceAnalysis {
     pub overlapping_count: u32,
     pub nearby_networks: Vec<NearbyNetwork>,
     pub interference_level: String,
+    pub path_mtu: Vec<PathMtu>,
     pub suggestions: Vec<String>,
 }
 
-pub fn analyze_interference() -> InterferenceAnalysis {
+pub fn analyze_interference(
+    runner: &dyn CommandRunner,
+    interface: Option<&NetworkInterface>,
+    targets: &[InternetTarget],
+) -> InterferenceAnalysis {
     log::debug!("analyze_interference: starting");
-    let wifi = get_wifi_info();
+    let wifi = get_wifi_info(runner, interface);
 
     let snr_db = match (wifi.signal_dbm, wifi.noise_dbm) {
         (Some(signal), Some(noise)) => Some(signal - noise),
@@ -52,7 +76,7 @@ pub fn analyze_interference() -> InterferenceAnalysis {
         current_frequency_ghz
     );
 
-    let nearby_networks = scan_nearby_networks();
+    let nearby_networks = scan_nearby_networks(runner, interface);
 
     let (same_channel_count, overlapping_count) = calculate_channel_congestion(
         current_channel,
@@ -71,15 +95,26 @@ pub fn analyze_interference() -> InterferenceAnalysis {
         overlapping_count,
     );
 
-    let suggestions = generate_suggestions(
+    let path_mtu = probe_path_mtus(runner, interface, targets);
+
+    let mut suggestions = generate_suggestions(
         snr_db,
-        &snr_quality,
         current_channel,
         current_frequency_ghz,
         same_channel_count,
         overlapping_count,
         &nearby_networks,
     );
+    // MTU problems stall connections in ways that look like poor Wi-Fi.
+    let mtu_suggestions: Vec<String> = path_mtu.iter().filter_map(mtu_suggestion).collect();
+    let mtu_ok = mtu_suggestions.is_empty();
+    suggestions.extend(mtu_suggestions);
+    if mtu_ok && snr_quality == "Excellent" && same_channel_count == 0 && overlapping_count <= 1 {
+        suggestions.push("Your Wi-Fi environment looks good!".to_string());
+    }
+    if suggestions.is_empty() {
+        suggestions.push("No major issues detected".to_string());
+    }
 
     log::debug!(
         "analyze_interference: complete - level: {}, suggestions: {}",
@@ -96,6 +131,7 @@ pub fn analyze_interference() -> InterferenceAnalysis {
         overlapping_count,
         nearby_networks,
         interference_level,
+        path_mtu,
         suggestions,
     }
 }
@@ -150,10 +186,27 @@ fn classify_snr(snr: Option<i32>) -> String {
     }
 }
 
-fn scan_nearby_networks() -> Vec<NearbyNetwork> {
-    let output = Command::new("system_profiler")
-        .args(["SPAirPortDataType"])
-        .output();
+fn scan_nearby_networks(
+    runner: &dyn CommandRunner,
+    interface: Option<&NetworkInterface>,
+) -> Vec<NearbyNetwork> {
+    let Some(interface) = interface else {
+        log::debug!("scan_nearby_networks: no wireless interface found");
+        return Vec::new();
+    };
+
+    #[cfg(target_os = "linux")]
+    if runner.native_probes() {
+        if let Some(networks) = crate::nl80211::scan_nearby_networks(&interface.name) {
+            return networks;
+        }
+    }
+
+    if cfg!(target_os = "linux") {
+        return scan_nearby_networks_iw(runner, &interface.name);
+    }
+
+    let output = runner.run("system_profiler", &["SPAirPortDataType"]);
 
     let output = match output {
         Ok(o) => o,
@@ -163,14 +216,95 @@ fn scan_nearby_networks() -> Vec<NearbyNetwork> {
         }
     };
 
-    if !output.status.success() {
+    if !output.success {
         log::error!("scan_nearby_networks: system_profiler failed");
         return Vec::new();
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let networks = parse_nearby_networks(&stdout);
-    log::debug!("scan_nearby_networks: found {} nearby networks", networks.len());
+    let networks = parse_nearby_networks(&output.stdout);
+    log::debug!(
+        "scan_nearby_networks: found {} nearby networks from {}",
+        networks.len(),
+        interface.name
+    );
+    networks
+}
+
+fn scan_nearby_networks_iw(runner: &dyn CommandRunner, iface: &str) -> Vec<NearbyNetwork> {
+    // A cached dump needs no privileges; a fresh scan only works as root.
+    let mut networks = run_iw(runner, &["dev", iface, "scan", "dump"])
+        .map(|output| parse_iw_scan(&output))
+        .unwrap_or_default();
+
+    if networks.is_empty() {
+        log::debug!("scan_nearby_networks: no cached scan results, triggering a scan");
+        if let Some(output) = run_iw(runner, &["dev", iface, "scan"]) {
+            networks = parse_iw_scan(&output);
+        }
+    }
+
+    log::debug!(
+        "scan_nearby_networks: found {} nearby networks from {}",
+        networks.len(),
+        iface
+    );
+    networks
+}
+
+fn parse_iw_scan(output: &str) -> Vec<NearbyNetwork> {
+    let headers: Vec<_> = IW_SCAN_BSS_RE.captures_iter(output).collect();
+    let mut networks = Vec::new();
+
+    for (i, caps) in headers.iter().enumerate() {
+        let header = caps.get(0).unwrap();
+        let end = headers
+            .get(i + 1)
+            .map_or(output.len(), |next| next.get(0).unwrap().start());
+        let block = &output[header.end()..end];
+
+        if caps[2].contains("-- associated") {
+            continue;
+        }
+
+        let Some(freq_mhz) = IW_SCAN_FREQ_RE
+            .captures(block)
+            .and_then(|c| c[1].parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Some((channel, frequency_ghz)) = frequency_to_channel(freq_mhz) else {
+            continue;
+        };
+
+        let ssid = IW_SCAN_SSID_RE
+            .captures(block)
+            .map(|c| c[1].trim().to_string())
+            .filter(|s| !s.is_empty() && !s.contains("\\x00"))
+            .unwrap_or_else(|| HIDDEN_SSID.to_string());
+        let signal_dbm = IW_SCAN_SIGNAL_RE
+            .captures(block)
+            .and_then(|c| c[1].parse::<f64>().ok())
+            .map(|dbm| dbm.round() as i32);
+
+        let width_mhz = if let Some(c) = IW_SCAN_VHT_WIDTH_RE.captures(block) {
+            c[1].parse().ok()
+        } else if IW_SCAN_HT_OFFSET_RE.is_match(block) {
+            Some(40)
+        } else {
+            Some(20)
+        };
+
+        networks.push(NearbyNetwork {
+            ssid,
+            bssid: Some(caps[1].to_lowercase()),
+            channel,
+            frequency_ghz,
+            frequency_mhz: Some(freq_mhz),
+            width_mhz,
+            signal_dbm,
+        });
+    }
+
     networks
 }
 
@@ -231,8 +365,12 @@ fn parse_nearby_networks(output: &str) -> Vec<NearbyNetwork> {
             if let (Some(ch), Some(freq)) = (channel, frequency) {
                 networks.push(NearbyNetwork {
                     ssid,
+                    bssid: None,
                     channel: ch,
                     frequency_ghz: freq,
+                    frequency_mhz: None,
+                    width_mhz: None,
+                    signal_dbm: None,
                 });
             }
         } else {
@@ -315,7 +453,6 @@ fn classify_interference(
 
 fn generate_suggestions(
     snr: Option<i32>,
-    snr_quality: &str,
     current_channel: Option<u32>,
     current_freq: Option<f64>,
     same_channel: u32,
@@ -358,14 +495,6 @@ fn generate_suggestions(
         }
     }
 
-    if snr_quality == "Excellent" && same_channel == 0 && overlapping <= 1 {
-        suggestions.push("Your Wi-Fi environment looks good!".to_string());
-    }
-
-    if suggestions.is_empty() {
-        suggestions.push("No major issues detected".to_string());
-    }
-
     suggestions
 }
 
@@ -401,6 +530,57 @@ mod tests {
         assert_eq!(classify_interference(Some(12), 3, 4), "Severe");
     }
 
+    #[test]
+    fn test_parse_iw_scan() {
+        let sample = r#"BSS 8c:3b:ad:12:34:56(on wlp2s0) -- associated
+	freq: 5180
+	signal: -52.00 dBm
+	SSID: HomeNet
+BSS A4:2B:B0:9E:11:02(on wlp2s0)
+	last seen: 1520.310s [boottime]
+	freq: 5180.0
+	signal: -78.00 dBm
+	SSID: Neighbor-5G
+	HT operation:
+		 * primary channel: 36
+		 * secondary channel offset: above
+	VHT operation:
+		 * channel width: 0 (20 or 40 MHz)
+BSS f0:9f:c2:77:80:13(on wlp2s0)
+	freq: 5220
+	signal: -81.50 dBm
+	SSID: \x00\x00\x00\x00
+	VHT operation:
+		 * channel width: 1 (80 MHz)
+BSS 38:10:d5:4c:aa:21(on wlp2s0)
+	freq: 2437
+	signal: -67.00 dBm
+	SSID: FRITZ!Box 7530
+	HT operation:
+		 * secondary channel offset: no secondary
+"#;
+
+        let networks = parse_iw_scan(sample);
+        assert_eq!(networks.len(), 3);
+
+        assert_eq!(networks[0].ssid, "Neighbor-5G");
+        assert_eq!(networks[0].bssid, Some("a4:2b:b0:9e:11:02".to_string()));
+        assert_eq!(networks[0].channel, 36);
+        assert_eq!(networks[0].frequency_mhz, Some(5180));
+        assert_eq!(networks[0].width_mhz, Some(40));
+        assert_eq!(networks[0].signal_dbm, Some(-78));
+
+        assert_eq!(networks[1].ssid, HIDDEN_SSID);
+        assert_eq!(networks[1].channel, 44);
+        assert_eq!(networks[1].width_mhz, Some(80));
+        assert_eq!(networks[1].signal_dbm, Some(-82));
+
+        assert_eq!(networks[2].ssid, "FRITZ!Box 7530");
+        assert_eq!(networks[2].channel, 6);
+        assert_eq!(networks[2].frequency_ghz, 2.4);
+        assert_eq!(networks[2].width_mhz, Some(20));
+    }
+
     #[test]
     fn test_parse_nearby_networks() {
         let sample = r#"
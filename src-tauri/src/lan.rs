@@ -0,0 +1,728 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::dhcp::list_addresses;
+use crate::interfaces::NetworkInterface;
+use crate::mdns::{self, MdnsHost, MDNS_ADDR};
+use crate::network::{get_router_ip, push_unique};
+use crate::runner::CommandRunner;
+use crate::ssdp::{self, SSDP_ADDR};
+
+// "? (192.168.1.1) at 50:c7:bf:6c:11:2 on en0 ifscope [ethernet]"
+static ARP_AN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\S+ \(([\d.]+)\) at ([0-9A-Fa-f:]+) on (\S+)").unwrap());
+
+const ARP_FLAG_COMPLETE: u32 = 0x2;
+const MAX_DATAGRAM: usize = 9000;
+// mDNS requires 255; SSDP asks for a small TTL so searches stay on the LAN.
+const MDNS_TTL: u32 = 255;
+const SSDP_TTL: u32 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoverySource {
+    Neighbor,
+    Mdns,
+    Ssdp,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LanDevice {
+    pub ip: String,
+    pub mac: Option<String>,
+    pub vendor: Option<String>,
+    // A locally administered MAC, as phones and laptops pick per network,
+    // which says nothing about who made the device.
+    pub private_mac: bool,
+    pub hostname: Option<String>,
+    pub model: Option<String>,
+    pub services: Vec<String>,
+    pub sources: Vec<DiscoverySource>,
+    pub gateway: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanInventory {
+    pub interface: Option<String>,
+    pub devices: Vec<LanDevice>,
+    // False when only the neighbor table was read, as in replays.
+    pub browsed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiscoveryConfig {
+    pub mdns_addr: SocketAddr,
+    pub ssdp_addr: SocketAddr,
+    // How long to collect answers; SSDP devices take up to a second to reply.
+    pub timeout: Duration,
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            mdns_addr: MDNS_ADDR,
+            ssdp_addr: SSDP_ADDR,
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Neighbor {
+    ip: Ipv4Addr,
+    mac: String,
+    interface: String,
+}
+
+// Devices this machine has talked to recently, plus everything that answers
+// an mDNS browse or SSDP search. Devices that stay quiet and have not been
+// contacted will not show up.
+pub fn discover(
+    runner: &dyn CommandRunner,
+    interface: Option<&NetworkInterface>,
+    config: &DiscoveryConfig,
+) -> LanInventory {
+    let name = interface.map(|i| i.name.as_str());
+    let neighbors = read_neighbors(runner, name);
+    let own: Vec<Ipv4Addr> = list_addresses(runner)
+        .into_iter()
+        .filter(|a| name.is_none_or(|name| a.interface == name))
+        .filter_map(|a| a.address.parse().ok())
+        .filter(|ip: &Ipv4Addr| !ip.is_loopback())
+        .collect();
+    let gateway = get_router_ip(runner, interface).and_then(|ip| ip.parse().ok());
+
+    let browsed = runner.native_probes();
+    let (mdns_hosts, ssdp_types) = if browsed {
+        browse(own.first().copied(), config)
+    } else {
+        log::debug!("lan::discover: browsing needs live sockets, reading the neighbor table only");
+        Default::default()
+    };
+
+    let devices = merge(&neighbors, mdns_hosts, ssdp_types, &own, gateway);
+    log::debug!(
+        "lan::discover: {} devices ({} in the neighbor table)",
+        devices.len(),
+        neighbors.len()
+    );
+    LanInventory {
+        interface: name.map(str::to_string),
+        devices,
+        browsed,
+    }
+}
+
+fn read_neighbors(runner: &dyn CommandRunner, interface: Option<&str>) -> Vec<Neighbor> {
+    let neighbors = if cfg!(target_os = "linux") {
+        match runner.read_file("/proc/net/arp") {
+            Ok(content) => parse_proc_arp(&content),
+            Err(e) => {
+                log::error!("lan::read_neighbors: failed to read /proc/net/arp: {}", e);
+                Vec::new()
+            }
+        }
+    } else {
+        match runner.run("arp", &["-an"]) {
+            Ok(output) if output.success => parse_arp_an(&output.stdout),
+            Ok(_) => {
+                log::error!("lan::read_neighbors: arp -an failed");
+                Vec::new()
+            }
+            Err(e) => {
+                log::error!("lan::read_neighbors: failed to run arp: {}", e);
+                Vec::new()
+            }
+        }
+    };
+    neighbors
+        .into_iter()
+        .filter(|n| interface.is_none_or(|name| n.interface == name))
+        .collect()
+}
+
+// Entries still being resolved have flags 0x0 and an all-zero address.
+fn parse_proc_arp(content: &str) -> Vec<Neighbor> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [ip, _, flags, mac, _, interface] = fields[..] else {
+                return None;
+            };
+            let flags = u32::from_str_radix(flags.trim_start_matches("0x"), 16).ok()?;
+            if flags & ARP_FLAG_COMPLETE == 0 {
+                return None;
+            }
+            Some(Neighbor {
+                ip: ip.parse().ok()?,
+                mac: normalize_mac(mac)?,
+                interface: interface.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_arp_an(output: &str) -> Vec<Neighbor> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let caps = ARP_AN_RE.captures(line)?;
+            Some(Neighbor {
+                ip: caps[1].parse().ok()?,
+                mac: normalize_mac(&caps[2])?,
+                interface: caps[3].to_string(),
+            })
+        })
+        .collect()
+}
+
+// Pads macOS's "0:11:32:a:b:c" style and drops addresses no single device
+// owns: all zeros, broadcast and multicast.
+fn normalize_mac(mac: &str) -> Option<String> {
+    let octets = mac
+        .split(':')
+        .map(|octet| u8::from_str_radix(octet, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    if octets.len() != 6 || octets.iter().all(|&o| o == 0) || octets[0] & 0x01 != 0 {
+        return None;
+    }
+    Some(
+        octets
+            .iter()
+            .map(|o| format!("{:02x}", o))
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+fn oui(mac: &str) -> Option<u32> {
+    let hex: String = mac.split(':').take(3).collect();
+    u32::from_str_radix(&hex, 16).ok()
+}
+
+fn is_private_mac(mac: &str) -> bool {
+    oui(mac).is_some_and(|oui| oui & 0x02_0000 != 0)
+}
+
+fn lookup_vendor(mac: &str) -> Option<&'static str> {
+    let oui = oui(mac)?;
+    OUI_VENDORS
+        .binary_search_by_key(&oui, |&(prefix, _)| prefix)
+        .ok()
+        .map(|i| OUI_VENDORS[i].1)
+}
+
+// Runs the mDNS browse and SSDP search side by side from `local`, or from
+// whichever address the routing table picks.
+fn browse(
+    local: Option<Ipv4Addr>,
+    config: &DiscoveryConfig,
+) -> (HashMap<Ipv4Addr, MdnsHost>, HashMap<Ipv4Addr, Vec<String>>) {
+    thread::scope(|scope| {
+        let mdns_hosts = scope.spawn(|| {
+            let mut hosts: HashMap<Ipv4Addr, MdnsHost> = HashMap::new();
+            let result = mdns::build_query().and_then(|query| {
+                listen(
+                    local,
+                    config.mdns_addr,
+                    MDNS_TTL,
+                    &query,
+                    config.timeout,
+                    |data, source| {
+                        if let Some(host) = mdns::parse_response(data, source) {
+                            let known = hosts.entry(source).or_default();
+                            known.hostname = known.hostname.take().or(host.hostname);
+                            known.model = known.model.take().or(host.model);
+                            for service in host.services {
+                                push_unique(&mut known.services, service);
+                            }
+                        }
+                    },
+                )
+            });
+            if let Err(e) = result {
+                log::debug!("lan::browse: mDNS browse failed: {}", e);
+            }
+            hosts
+        });
+
+        let mut ssdp_types: HashMap<Ipv4Addr, Vec<String>> = HashMap::new();
+        let search = ssdp::build_search(config.ssdp_addr);
+        let result = listen(
+            local,
+            config.ssdp_addr,
+            SSDP_TTL,
+            &search,
+            config.timeout,
+            |data, source| {
+                if let Some(device_type) = ssdp::parse_response(data) {
+                    let types = ssdp_types.entry(source).or_default();
+                    if let Some(device_type) = device_type {
+                        push_unique(types, device_type);
+                    }
+                }
+            },
+        );
+        if let Err(e) = result {
+            log::debug!("lan::browse: SSDP search failed: {}", e);
+        }
+
+        let mdns_hosts = mdns_hosts.join().unwrap_or_default();
+        log::debug!(
+            "lan::browse: {} mDNS responders, {} SSDP devices",
+            mdns_hosts.len(),
+            ssdp_types.len()
+        );
+        (mdns_hosts, ssdp_types)
+    })
+}
+
+// Sends `request` to a multicast group and hands every IPv4 datagram that
+// comes back before `timeout` to `handle`.
+fn listen(
+    local: Option<Ipv4Addr>,
+    target: SocketAddr,
+    ttl: u32,
+    request: &[u8],
+    timeout: Duration,
+    mut handle: impl FnMut(&[u8], Ipv4Addr),
+) -> io::Result<()> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+    socket.set_multicast_ttl_v4(ttl)?;
+    if let Some(local) = local {
+        set_multicast_interface(&socket, local)?;
+    }
+    socket.send_to(request, target)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = vec![0u8; MAX_DATAGRAM];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(());
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        match socket.recv_from(&mut buf) {
+            Ok((len, SocketAddr::V4(peer))) => handle(&buf[..len], *peer.ip()),
+            Ok(_) => {}
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_multicast_interface(socket: &UdpSocket, local: Ipv4Addr) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let addr = libc::in_addr {
+        s_addr: u32::from(local).to_be(),
+    };
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_IP,
+            libc::IP_MULTICAST_IF,
+            &addr as *const libc::in_addr as *const libc::c_void,
+            std::mem::size_of::<libc::in_addr>() as libc::socklen_t,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_multicast_interface(_socket: &UdpSocket, _local: Ipv4Addr) -> io::Result<()> {
+    Ok(())
+}
+
+// One device per address, in address order, leaving out this machine, which
+// answers its own mDNS browse.
+fn merge(
+    neighbors: &[Neighbor],
+    mdns_hosts: HashMap<Ipv4Addr, MdnsHost>,
+    ssdp_types: HashMap<Ipv4Addr, Vec<String>>,
+    own: &[Ipv4Addr],
+    gateway: Option<Ipv4Addr>,
+) -> Vec<LanDevice> {
+    let mut devices: BTreeMap<Ipv4Addr, LanDevice> = BTreeMap::new();
+
+    for neighbor in neighbors {
+        let device = device_entry(&mut devices, neighbor.ip, gateway);
+        device.vendor = lookup_vendor(&neighbor.mac).map(str::to_string);
+        device.private_mac = is_private_mac(&neighbor.mac);
+        device.mac = Some(neighbor.mac.clone());
+        device.sources.push(DiscoverySource::Neighbor);
+    }
+    for (ip, host) in mdns_hosts {
+        let device = device_entry(&mut devices, ip, gateway);
+        device.hostname = host.hostname;
+        device.model = host.model;
+        for service in host.services {
+            push_unique(&mut device.services, service);
+        }
+        device.sources.push(DiscoverySource::Mdns);
+    }
+    for (ip, types) in ssdp_types {
+        let device = device_entry(&mut devices, ip, gateway);
+        for device_type in types {
+            push_unique(&mut device.services, device_type);
+        }
+        device.sources.push(DiscoverySource::Ssdp);
+    }
+
+    devices.retain(|ip, _| !own.contains(ip));
+    devices.into_values().collect()
+}
+
+fn device_entry(
+    devices: &mut BTreeMap<Ipv4Addr, LanDevice>,
+    ip: Ipv4Addr,
+    gateway: Option<Ipv4Addr>,
+) -> &mut LanDevice {
+    devices.entry(ip).or_insert_with(|| LanDevice {
+        ip: ip.to_string(),
+        gateway: gateway == Some(ip),
+        ..Default::default()
+    })
+}
+
+// Makers of devices commonly found on home networks, keyed by the first three
+// octets of the MAC and sorted for binary search. Far from the full registry;
+// anything else is left without a vendor rather than guessed.
+const OUI_VENDORS: [(u32, &str); 65] = [
+    (0x000393, "Apple"),
+    (0x0009bf, "Nintendo"),
+    (0x000e58, "Sonos"),
+    (0x001132, "Synology"),
+    (0x001788, "Philips Hue"),
+    (0x001b63, "Apple"),
+    (0x001f32, "Nintendo"),
+    (0x002722, "Ubiquiti"),
+    (0x0418d6, "Ubiquiti"),
+    (0x080581, "Roku"),
+    (0x0c47c9, "Amazon"),
+    (0x14cc20, "TP-Link"),
+    (0x20e52a, "Netgear"),
+    (0x240ac4, "Espressif"),
+    (0x246f28, "Espressif"),
+    (0x24a43c, "Ubiquiti"),
+    (0x28cdc1, "Raspberry Pi"),
+    (0x28cfe9, "Apple"),
+    (0x2ccf67, "Raspberry Pi"),
+    (0x30aea4, "Espressif"),
+    (0x3c0754, "Apple"),
+    (0x3c5ab4, "Google"),
+    (0x3c71bf, "Espressif"),
+    (0x44650d, "Amazon"),
+    (0x48a6b8, "Sonos"),
+    (0x50c7bf, "TP-Link"),
+    (0x542a1b, "Sonos"),
+    (0x546009, "Google"),
+    (0x5caafd, "Sonos"),
+    (0x6837e9, "Amazon"),
+    (0x7483c2, "Ubiquiti"),
+    (0x74c246, "Amazon"),
+    (0x7828ca, "Sonos"),
+    (0x788a20, "Ubiquiti"),
+    (0x7cbb8a, "Nintendo"),
+    (0x84d6d0, "Amazon"),
+    (0x84f3eb, "Espressif"),
+    (0x949f3e, "Sonos"),
+    (0x98b6e9, "Nintendo"),
+    (0x98dac4, "TP-Link"),
+    (0x9c3dcf, "Netgear"),
+    (0xa040a0, "Netgear"),
+    (0xa45e60, "Apple"),
+    (0xa4cf12, "Espressif"),
+    (0xacbc32, "Apple"),
+    (0xb0a737, "Roku"),
+    (0xb4fbe4, "Ubiquiti"),
+    (0xb827eb, "Raspberry Pi"),
+    (0xb8e937, "Sonos"),
+    (0xc04a00, "TP-Link"),
+    (0xcc6da0, "Roku"),
+    (0xd83add, "Raspberry Pi"),
+    (0xdc3a5e, "Roku"),
+    (0xdca632, "Raspberry Pi"),
+    (0xe45f01, "Raspberry Pi"),
+    (0xec086b, "TP-Link"),
+    (0xecfabc, "Espressif"),
+    (0xf01898, "Apple"),
+    (0xf0272d, "Amazon"),
+    (0xf09fc2, "Ubiquiti"),
+    (0xf4f26d, "TP-Link"),
+    (0xf4f5d8, "Google"),
+    (0xf4f5e8, "Google"),
+    (0xfc65de, "Amazon"),
+    (0xfcecda, "Ubiquiti"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neighbor(ip: &str, mac: &str) -> Neighbor {
+        Neighbor {
+            ip: ip.parse().unwrap(),
+            mac: mac.to_string(),
+            interface: "wlp2s0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_proc_arp() {
+        let content = "\
+IP address       HW type     Flags       HW address            Mask     Device
+192.168.1.1      0x1         0x2         50:c7:bf:6c:11:02     *        wlp2s0
+192.168.1.90     0x1         0x0         00:00:00:00:00:00     *        wlp2s0
+192.168.254.1    0x1         0x6         00:11:32:AA:BB:CC     *        enp0s31f6
+";
+        let neighbors = parse_proc_arp(content);
+        assert_eq!(neighbors.len(), 2);
+        assert_eq!(neighbors[0], neighbor("192.168.1.1", "50:c7:bf:6c:11:02"));
+        assert_eq!(neighbors[1].mac, "00:11:32:aa:bb:cc");
+        assert_eq!(neighbors[1].interface, "enp0s31f6");
+    }
+
+    #[test]
+    fn test_parse_arp_an() {
+        let output = "\
+? (192.168.1.1) at 50:c7:bf:6c:11:2 on en0 ifscope [ethernet]
+? (192.168.1.40) at b8:27:eb:4f:20:9a on en0 ifscope [ethernet]
+? (192.168.1.90) at (incomplete) on en0 ifscope [ethernet]
+? (192.168.1.255) at ff:ff:ff:ff:ff:ff on en0 ifscope [ethernet]
+mdns.mcast.net (224.0.0.251) at 1:0:5e:0:0:fb on en0 ifscope permanent [ethernet]
+";
+        let neighbors = parse_arp_an(output);
+        let ips: Vec<String> = neighbors.iter().map(|n| n.ip.to_string()).collect();
+        assert_eq!(ips, vec!["192.168.1.1", "192.168.1.40"]);
+        assert_eq!(neighbors[0].mac, "50:c7:bf:6c:11:02");
+        assert_eq!(neighbors[0].interface, "en0");
+    }
+
+    #[test]
+    fn test_vendor() {
+        assert!(OUI_VENDORS.windows(2).all(|w| w[0].0 < w[1].0));
+        assert_eq!(lookup_vendor("b8:27:eb:4f:20:9a"), Some("Raspberry Pi"));
+        assert_eq!(lookup_vendor("00:03:93:01:02:03"), Some("Apple"));
+        assert_eq!(lookup_vendor("12:34:56:78:9a:bc"), None);
+        assert!(is_private_mac("9a:1c:3e:55:0b:21"));
+        assert!(!is_private_mac("b8:27:eb:4f:20:9a"));
+    }
+
+    #[test]
+    fn test_merge() {
+        let neighbors = vec![
+            neighbor("192.168.1.52", "48:a6:b8:01:7e:c3"),
+            neighbor("192.168.1.1", "50:c7:bf:6c:11:02"),
+        ];
+        let mdns_hosts = HashMap::from([
+            (
+                "192.168.1.52".parse().unwrap(),
+                MdnsHost {
+                    hostname: Some("Sonos-48A6B8017EC3".to_string()),
+                    model: None,
+                    services: vec!["spotify-connect".to_string()],
+                },
+            ),
+            ("192.168.1.23".parse().unwrap(), MdnsHost::default()),
+        ]);
+        let ssdp_types = HashMap::from([
+            (
+                "192.168.1.52".parse().unwrap(),
+                vec!["ZonePlayer".to_string()],
+            ),
+            ("192.168.1.60".parse().unwrap(), vec!["dial".to_string()]),
+        ]);
+
+        let devices = merge(
+            &neighbors,
+            mdns_hosts,
+            ssdp_types,
+            &["192.168.1.23".parse().unwrap()],
+            "192.168.1.1".parse().ok(),
+        );
+        let ips: Vec<&str> = devices.iter().map(|d| d.ip.as_str()).collect();
+        assert_eq!(ips, vec!["192.168.1.1", "192.168.1.52", "192.168.1.60"]);
+
+        assert!(devices[0].gateway);
+        assert_eq!(devices[0].vendor.as_deref(), Some("TP-Link"));
+        assert_eq!(devices[0].sources, vec![DiscoverySource::Neighbor]);
+
+        let speaker = &devices[1];
+        assert!(!speaker.gateway);
+        assert_eq!(speaker.vendor.as_deref(), Some("Sonos"));
+        assert_eq!(speaker.hostname.as_deref(), Some("Sonos-48A6B8017EC3"));
+        assert_eq!(speaker.services, vec!["spotify-connect", "ZonePlayer"]);
+        assert_eq!(
+            speaker.sources,
+            vec![
+                DiscoverySource::Neighbor,
+                DiscoverySource::Mdns,
+                DiscoverySource::Ssdp
+            ]
+        );
+
+        assert_eq!(devices[2].mac, None);
+        assert_eq!(devices[2].sources, vec![DiscoverySource::Ssdp]);
+    }
+
+    #[cfg(target_os = "linux")]
+    mod namespace {
+        use super::*;
+        use crate::dns::encode_name;
+        use std::process::Command;
+
+        const INSIDE_ENV: &str = "YFI_LAN_NAMESPACE";
+        // A private network whose loopback carries multicast, so responders
+        // can listen on the real groups and ports.
+        const SETUP: &str = "ip link set lo up multicast on && ip route add 224.0.0.0/4 dev lo";
+
+        fn record(packet: &mut Vec<u8>, name: &str, record_type: u16, rdata: &[u8]) {
+            encode_name(packet, name).unwrap();
+            packet.extend_from_slice(&record_type.to_be_bytes());
+            packet.extend_from_slice(&[0, 1, 0, 0, 0, 10]);
+            packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            packet.extend_from_slice(rdata);
+        }
+
+        // A printer answering every query from 127.0.0.2.
+        fn spawn_mdns_responder() {
+            let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, MDNS_ADDR.port())).unwrap();
+            socket
+                .join_multicast_v4(&Ipv4Addr::new(224, 0, 0, 251), &Ipv4Addr::LOCALHOST)
+                .unwrap();
+            let reply = UdpSocket::bind("127.0.0.2:0").unwrap();
+
+            let mut response = vec![0, 0, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 3];
+            let mut instance = Vec::new();
+            encode_name(&mut instance, "Office._ipp._tcp.local").unwrap();
+            record(&mut response, "_ipp._tcp.local", 12, &instance);
+            let mut srv = vec![0, 0, 0, 0, 2, 119];
+            encode_name(&mut srv, "office-printer.local").unwrap();
+            record(&mut response, "Office._ipp._tcp.local", 33, &srv);
+            record(
+                &mut response,
+                "Office._ipp._tcp.local",
+                16,
+                b"\x15ty=Brother HL-L2350DW",
+            );
+            record(&mut response, "office-printer.local", 1, &[127, 0, 0, 2]);
+
+            thread::spawn(move || {
+                let mut buf = [0u8; 1500];
+                while let Ok((_, peer)) = socket.recv_from(&mut buf) {
+                    let _ = reply.send_to(&response, peer);
+                }
+            });
+        }
+
+        // A media renderer answering searches from 127.0.0.3.
+        fn spawn_ssdp_responder() {
+            let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, SSDP_ADDR.port())).unwrap();
+            socket
+                .join_multicast_v4(&Ipv4Addr::new(239, 255, 255, 250), &Ipv4Addr::LOCALHOST)
+                .unwrap();
+            let reply = UdpSocket::bind("127.0.0.3:0").unwrap();
+
+            thread::spawn(move || {
+                let mut buf = [0u8; 1500];
+                while let Ok((len, peer)) = socket.recv_from(&mut buf) {
+                    if !buf[..len].starts_with(b"M-SEARCH") {
+                        continue;
+                    }
+                    for st in [
+                        "upnp:rootdevice",
+                        "urn:schemas-upnp-org:device:MediaRenderer:1",
+                    ] {
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\nLOCATION: http://127.0.0.3:8008/desc.xml\r\nST: {}\r\n\r\n",
+                            st
+                        );
+                        let _ = reply.send_to(response.as_bytes(), peer);
+                    }
+                }
+            });
+        }
+
+        #[test]
+        #[ignore = "needs unprivileged user namespaces"]
+        fn test_browse_in_network_namespace() {
+            if std::env::var_os(INSIDE_ENV).is_none() {
+                let available = Command::new("unshare")
+                    .args(["-rn", "sh", "-c", SETUP])
+                    .status()
+                    .is_ok_and(|status| status.success());
+                assert!(available, "cannot create a network namespace");
+
+                // Run just this test again inside the namespace.
+                let (_, module) = module_path!().split_once("::").unwrap();
+                let status = Command::new("unshare")
+                    .args(["-rn", "sh", "-c"])
+                    .arg(format!("{} && exec \"$0\" \"$@\"", SETUP))
+                    .arg(std::env::current_exe().unwrap())
+                    .args([
+                        &format!("{}::test_browse_in_network_namespace", module),
+                        "--exact",
+                        "--ignored",
+                        "--nocapture",
+                    ])
+                    .env(INSIDE_ENV, "1")
+                    .status()
+                    .unwrap();
+                assert!(status.success());
+                return;
+            }
+
+            spawn_mdns_responder();
+            spawn_ssdp_responder();
+            let config = DiscoveryConfig {
+                timeout: Duration::from_millis(500),
+                ..Default::default()
+            };
+            let (mdns_hosts, ssdp_types) = browse(Some(Ipv4Addr::LOCALHOST), &config);
+
+            let neighbors = vec![Neighbor {
+                ip: "127.0.0.2".parse().unwrap(),
+                mac: "b8:27:eb:4f:20:9a".to_string(),
+                interface: "lo".to_string(),
+            }];
+            let devices = merge(&neighbors, mdns_hosts, ssdp_types, &[], None);
+            assert_eq!(devices.len(), 2);
+
+            let printer = &devices[0];
+            assert_eq!(printer.ip, "127.0.0.2");
+            assert_eq!(printer.vendor.as_deref(), Some("Raspberry Pi"));
+            assert_eq!(printer.hostname.as_deref(), Some("office-printer"));
+            assert_eq!(printer.model.as_deref(), Some("Brother HL-L2350DW"));
+            assert_eq!(printer.services, vec!["ipp"]);
+            assert_eq!(
+                printer.sources,
+                vec![DiscoverySource::Neighbor, DiscoverySource::Mdns]
+            );
+
+            let renderer = &devices[1];
+            assert_eq!(renderer.ip, "127.0.0.3");
+            assert_eq!(renderer.services, vec!["MediaRenderer"]);
+            assert_eq!(renderer.sources, vec![DiscoverySource::Ssdp]);
+        }
+    }
+}
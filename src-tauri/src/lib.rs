@@ -1,7 +1,33 @@
+mod bufferbloat;
+mod cache;
 mod commands;
+mod dhcp;
+mod dns;
+mod dnsbench;
+mod dualstack;
+mod http;
+#[cfg(unix)]
+mod icmp;
+mod interfaces;
 mod interference;
+mod lan;
+mod mdns;
 mod network;
+#[cfg(target_os = "linux")]
+mod networkmanager;
+#[cfg(any(target_os = "linux", test))]
+mod nl80211;
+mod pmtu;
+mod portal;
+mod probes;
+mod runner;
+mod speedtest;
+mod ssdp;
+#[cfg(unix)]
+mod tcp;
+mod traceroute;
 mod wifi;
+#[cfg(target_os = "macos")]
 #[allow(deprecated)]
 use cocoa::appkit::{NSApp, NSApplication, NSApplicationActivationPolicy};
 use tauri::{
@@ -140,6 +166,7 @@ pub fn run() {
         .setup(|app| {
             log::info!("Yfi app starting up");
 
+            #[cfg(target_os = "macos")]
             #[allow(deprecated)]
             unsafe {
                 let app_instance = NSApp();
@@ -178,7 +205,7 @@ pub fn run() {
                 app.exit(0);
             }
         })
-        .invoke_handler(tauri::generate_handler![hide_window, commands::get_network_metrics, commands::check_interference])
+        .invoke_handler(tauri::generate_handler![hide_window, commands::get_network_metrics, commands::check_interference, commands::benchmark_dns, commands::list_network_interfaces, commands::measure_http_timing, commands::measure_bufferbloat, commands::run_speed_test, commands::trace_route, commands::discover_lan])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,374 @@
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+use crate::dns::encode_name;
+use crate::network::push_unique;
+
+pub const MDNS_ADDR: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(224, 0, 0, 251), 5353));
+
+const HEADER_LEN: usize = 12;
+const FLAG_QR: u16 = 0x8000;
+const CLASS_IN: u16 = 1;
+// Asks for the answer to come back unicast (RFC 6762 section 5.4).
+const CLASS_UNICAST_RESPONSE: u16 = 0x8000;
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_TXT: u16 = 16;
+const TYPE_SRV: u16 = 33;
+const MAX_POINTERS: usize = 16;
+
+const SERVICES_NAME: &str = "_services._dns-sd._udp.local";
+// Browsing a type by name makes responders add the instance's host name and
+// address, which enumerating `SERVICES_NAME` alone does not.
+const BROWSE_TYPES: [&str; 11] = [
+    "_airplay._tcp.local",
+    "_raop._tcp.local",
+    "_googlecast._tcp.local",
+    "_spotify-connect._tcp.local",
+    "_ipp._tcp.local",
+    "_printer._tcp.local",
+    "_hap._tcp.local",
+    "_smb._tcp.local",
+    "_http._tcp.local",
+    "_workstation._tcp.local",
+    "_device-info._tcp.local",
+];
+// TXT keys that carry a model name: Cast, printers, and Apple devices.
+const MODEL_KEYS: [&str; 3] = ["md", "ty", "model"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum RecordData {
+    Ptr(String),
+    Srv(String),
+    Txt(Vec<String>),
+    A(Ipv4Addr),
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Record {
+    name: String,
+    data: RecordData,
+}
+
+// What one responder said about itself.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MdnsHost {
+    pub hostname: Option<String>,
+    pub model: Option<String>,
+    pub services: Vec<String>,
+}
+
+// A single query enumerating service types and browsing the common ones. The
+// ID is zero and the source port is not 5353, so responders treat it as a
+// legacy unicast query and answer the sender directly.
+pub fn build_query() -> io::Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(HEADER_LEN + 32 * (BROWSE_TYPES.len() + 1));
+    packet.extend_from_slice(&[0; 4]);
+    packet.extend_from_slice(&(BROWSE_TYPES.len() as u16 + 1).to_be_bytes());
+    packet.extend_from_slice(&[0; 6]);
+    for name in std::iter::once(SERVICES_NAME).chain(BROWSE_TYPES) {
+        encode_name(&mut packet, name)?;
+        packet.extend_from_slice(&TYPE_PTR.to_be_bytes());
+        packet.extend_from_slice(&(CLASS_IN | CLASS_UNICAST_RESPONSE).to_be_bytes());
+    }
+    Ok(packet)
+}
+
+// Everything in a response is credited to its sender, which is how responders
+// behave outside of sleep proxies.
+pub fn parse_response(packet: &[u8], source: Ipv4Addr) -> Option<MdnsHost> {
+    let records = parse_records(packet)?;
+    let mut host = MdnsHost::default();
+
+    for record in &records {
+        match &record.data {
+            RecordData::A(ip) if *ip == source => {
+                host.hostname
+                    .get_or_insert_with(|| strip_local(&record.name));
+            }
+            RecordData::Ptr(target) => {
+                let service_type = if record.name.eq_ignore_ascii_case(SERVICES_NAME) {
+                    target
+                } else {
+                    &record.name
+                };
+                if let Some(label) = service_label(service_type) {
+                    push_unique(&mut host.services, label);
+                }
+            }
+            RecordData::Srv(target) => {
+                if let Some(label) = service_label(&record.name) {
+                    push_unique(&mut host.services, label);
+                }
+                // Only a fallback, as the target may be another host.
+                if !records
+                    .iter()
+                    .any(|r| matches!(r.data, RecordData::A(ip) if ip == source))
+                {
+                    host.hostname.get_or_insert_with(|| strip_local(target));
+                }
+            }
+            RecordData::Txt(entries) if host.model.is_none() => {
+                host.model = MODEL_KEYS.iter().find_map(|key| {
+                    entries.iter().find_map(|entry| {
+                        let (k, v) = entry.split_once('=')?;
+                        (k.eq_ignore_ascii_case(key) && !v.is_empty()).then(|| v.to_string())
+                    })
+                });
+            }
+            _ => {}
+        }
+    }
+
+    if host == MdnsHost::default() {
+        return None;
+    }
+    Some(host)
+}
+
+fn parse_records(packet: &[u8]) -> Option<Vec<Record>> {
+    let read_u16 = |offset: usize| -> Option<u16> {
+        Some(u16::from_be_bytes([
+            *packet.get(offset)?,
+            *packet.get(offset + 1)?,
+        ]))
+    };
+    if packet.len() < HEADER_LEN || read_u16(2)? & FLAG_QR == 0 {
+        return None;
+    }
+
+    let mut offset = HEADER_LEN;
+    for _ in 0..read_u16(4)? {
+        offset = read_name(packet, offset)?.1 + 4;
+    }
+
+    let count = read_u16(6)? as usize + read_u16(8)? as usize + read_u16(10)? as usize;
+    let mut records = Vec::with_capacity(count);
+    for _ in 0..count {
+        let (name, end) = read_name(packet, offset)?;
+        let record_type = read_u16(end)?;
+        let start = end + 10;
+        let len = read_u16(end + 8)? as usize;
+        let rdata = packet.get(start..start + len)?;
+        let data = match record_type {
+            TYPE_PTR => RecordData::Ptr(read_name(packet, start)?.0),
+            TYPE_SRV => RecordData::Srv(read_name(packet, start + 6)?.0),
+            TYPE_TXT => RecordData::Txt(parse_txt(rdata)),
+            TYPE_A if len == 4 => {
+                RecordData::A(Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]))
+            }
+            _ => RecordData::Other,
+        };
+        records.push(Record { name, data });
+        offset = start + len;
+    }
+    Some(records)
+}
+
+// The dotted name at `offset` and where the data after it starts, following
+// compression pointers (RFC 1035 section 4.1.4).
+fn read_name(packet: &[u8], mut offset: usize) -> Option<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut pointers = 0;
+
+    loop {
+        let len = *packet.get(offset)? as usize;
+        if len == 0 {
+            offset += 1;
+            break;
+        }
+        if len & 0xc0 == 0xc0 {
+            pointers += 1;
+            if pointers > MAX_POINTERS {
+                return None;
+            }
+            end.get_or_insert(offset + 2);
+            offset = ((len & 0x3f) << 8) | *packet.get(offset + 1)? as usize;
+            continue;
+        }
+        if len & 0xc0 != 0 {
+            return None;
+        }
+        let label = packet.get(offset + 1..offset + 1 + len)?;
+        labels.push(String::from_utf8_lossy(label).into_owned());
+        offset += 1 + len;
+    }
+
+    Some((labels.join("."), end.unwrap_or(offset)))
+}
+
+fn parse_txt(rdata: &[u8]) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut rest = rdata;
+    while let Some((&len, tail)) = rest.split_first() {
+        let Some(entry) = tail.get(..len as usize) else {
+            break;
+        };
+        entries.push(String::from_utf8_lossy(entry).into_owned());
+        rest = &tail[len as usize..];
+    }
+    entries
+}
+
+// "_googlecast._tcp.local" or an instance of it becomes "googlecast".
+fn service_label(name: &str) -> Option<String> {
+    let mut labels = name.trim_end_matches('.').rsplit('.');
+    if !labels.next()?.eq_ignore_ascii_case("local") {
+        return None;
+    }
+    let protocol = labels.next()?;
+    let service = labels.next()?.strip_prefix('_')?;
+    if !matches!(protocol, "_tcp" | "_udp") || service == "device-info" || service == "dns-sd" {
+        return None;
+    }
+    Some(service.to_ascii_lowercase())
+}
+
+fn strip_local(name: &str) -> String {
+    let name = name.trim_end_matches('.');
+    name.strip_suffix(".local").unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(packet: &mut Vec<u8>, name: &[u8], record_type: u16, rdata: &[u8]) {
+        packet.extend_from_slice(name);
+        packet.extend_from_slice(&record_type.to_be_bytes());
+        packet.extend_from_slice(&CLASS_IN.to_be_bytes());
+        packet.extend_from_slice(&120u32.to_be_bytes());
+        packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        packet.extend_from_slice(rdata);
+    }
+
+    fn encoded(name: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_name(&mut out, name).unwrap();
+        out
+    }
+
+    // A Cast device answering a browse for its type, with the instance name
+    // and host name compressed against earlier records.
+    fn cast_response() -> Vec<u8> {
+        let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 3];
+        let type_offset = packet.len() as u8;
+        let mut rdata = vec![11];
+        rdata.extend_from_slice(b"Living Room");
+        rdata.extend_from_slice(&[0xc0, type_offset]);
+        record(
+            &mut packet,
+            &encoded("_googlecast._tcp.local"),
+            TYPE_PTR,
+            &rdata,
+        );
+        let instance = (packet.len() - rdata.len()) as u8;
+
+        record(
+            &mut packet,
+            &[0xc0, instance],
+            TYPE_TXT,
+            b"\x0dmd=Chromecast\x0efn=Living Room",
+        );
+        let mut srv = vec![0, 0, 0, 0, 0x1f, 0x49];
+        srv.extend_from_slice(&encoded("chromecast-1a2b.local"));
+        record(&mut packet, &[0xc0, instance], TYPE_SRV, &srv);
+
+        record(
+            &mut packet,
+            &encoded("chromecast-1a2b.local"),
+            TYPE_A,
+            &[192, 168, 1, 60],
+        );
+        packet
+    }
+
+    #[test]
+    fn test_build_query() {
+        let packet = build_query().unwrap();
+        assert_eq!(&packet[..4], &[0, 0, 0, 0]);
+        assert_eq!(u16::from_be_bytes([packet[4], packet[5]]), 12);
+        let (name, end) = read_name(&packet, HEADER_LEN).unwrap();
+        assert_eq!(name, SERVICES_NAME);
+        assert_eq!(&packet[end..end + 4], &[0, 12, 0x80, 1]);
+    }
+
+    #[test]
+    fn test_read_name() {
+        let mut packet = vec![0; HEADER_LEN];
+        packet.extend_from_slice(&encoded("_ipp._tcp.local"));
+        packet.extend_from_slice(&[7]);
+        packet.extend_from_slice(b"printer");
+        packet.extend_from_slice(&[0xc0, HEADER_LEN as u8]);
+
+        assert_eq!(
+            read_name(&packet, HEADER_LEN),
+            Some(("_ipp._tcp.local".to_string(), HEADER_LEN + 17))
+        );
+        assert_eq!(
+            read_name(&packet, HEADER_LEN + 17),
+            Some(("printer._ipp._tcp.local".to_string(), packet.len()))
+        );
+
+        // A pointer to itself never ends.
+        let looping = [0xc0, 0];
+        assert_eq!(read_name(&looping, 0), None);
+        assert_eq!(read_name(&[5, b'a'], 0), None);
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let source = Ipv4Addr::new(192, 168, 1, 60);
+        let host = parse_response(&cast_response(), source).unwrap();
+        assert_eq!(host.hostname.as_deref(), Some("chromecast-1a2b"));
+        assert_eq!(host.model.as_deref(), Some("Chromecast"));
+        assert_eq!(host.services, vec!["googlecast"]);
+
+        // Queries from other browsers are not answers.
+        let mut query = cast_response();
+        query[2] = 0;
+        assert_eq!(parse_response(&query, source), None);
+        assert_eq!(parse_response(&cast_response()[..40], source), None);
+    }
+
+    #[test]
+    fn test_parse_service_enumeration() {
+        let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 2, 0, 0, 0, 0];
+        let services = encoded(SERVICES_NAME);
+        record(
+            &mut packet,
+            &services,
+            TYPE_PTR,
+            &encoded("_ipp._tcp.local"),
+        );
+        record(
+            &mut packet,
+            &services,
+            TYPE_PTR,
+            &encoded("_device-info._tcp.local"),
+        );
+
+        let host = parse_response(&packet, Ipv4Addr::new(192, 168, 1, 70)).unwrap();
+        assert_eq!(host.services, vec!["ipp"]);
+        assert_eq!(host.hostname, None);
+    }
+
+    #[test]
+    fn test_service_label() {
+        assert_eq!(
+            service_label("_googlecast._tcp.local"),
+            Some("googlecast".to_string())
+        );
+        assert_eq!(
+            service_label("Kitchen._Spotify-Connect._tcp.local."),
+            Some("spotify-connect".to_string())
+        );
+        assert_eq!(service_label(SERVICES_NAME), None);
+        assert_eq!(service_label("_device-info._tcp.local"), None);
+        assert_eq!(service_label("printer.local"), None);
+        assert_eq!(service_label("_ipp._tcp.example.com"), None);
+    }
+}
@@ -1,23 +1,75 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::time::Duration;
 
-static ROUTER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"Router:\s*([\d.]+)").unwrap());
+use crate::dns::{self, DnsTransport, RecordType};
+#[cfg(unix)]
+use crate::icmp;
+use crate::interfaces::NetworkInterface;
+use crate::runner::CommandRunner;
+#[cfg(unix)]
+use crate::tcp;
+
+static ROUTER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^Router:\s*(\S+)").unwrap());
+static IPV6_ROUTER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^IPv6 Router:\s*(\S+)").unwrap());
 static PACKET_LOSS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"([\d.]+)% packet loss").unwrap());
+static PING_COUNTS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(\d+) packets transmitted, (\d+) (?:packets )?received(?:, \+(\d+) duplicates)?")
+        .unwrap()
+});
+static PING_REPLY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"icmp_seq=\d+ .*time[=<]([\d.]+) ms(.*)$").unwrap());
 static PING_STATS_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"round-trip min/avg/max/stddev = ([\d.]+)/([\d.]+)/([\d.]+)/([\d.]+)").unwrap()
+    Regex::new(
+        r"(?:round-trip|rtt) min/avg/max/(?:stddev|mdev) = ([\d.]+)/([\d.]+)/([\d.]+)/([\d.]+)",
+    )
+    .unwrap()
 });
 static DNS_SERVER_RE: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"nameserver\[\d+\]\s*:\s*([\d.]+)").unwrap());
+    Lazy::new(|| Regex::new(r"nameserver\[\d+\]\s*:\s*(\S+)").unwrap());
 static QUERY_TIME_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"Query time:\s*(\d+)\s*msec").unwrap());
+static SCUTIL_SEARCH_DOMAIN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"search domain\[\d+\]\s*:\s*(\S+)").unwrap());
+static SCUTIL_IF_INDEX_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"if_index\s*:\s*\d+\s*\(([^)]+)\)").unwrap());
+static RESOLVECTL_LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^Link\s+\d+\s+\(([^)]+)\)").unwrap());
+static RESOLVECTL_FIELD_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*([A-Za-z][A-Za-z ]*):\s*(.*)$").unwrap());
+
+const RTF_GATEWAY: u32 = 0x2;
+const RESOLVED_STUB_ADDRESSES: [&str; 2] = ["127.0.0.53", "127.0.0.54"];
+const ECHO_INTERVAL: Duration = Duration::from_millis(200);
+const ECHO_TIMEOUT: Duration = Duration::from_secs(2);
+const DNS_LOOKUP_NAME: &str = "google.com";
+const DNS_LOOKUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gateway {
+    pub interface: String,
+    pub address: String,
+    pub metric: u32,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PingResult {
     pub latency_ms: Option<f64>,
     pub jitter_ms: Option<f64>,
+    pub stddev_ms: Option<f64>,
     pub packet_loss_percent: Option<f64>,
+    pub min_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    pub median_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub samples_ms: Vec<f64>,
+    pub sent: Option<u32>,
+    pub received: Option<u32>,
+    pub duplicates: Option<u32>,
 }
 
 impl Default for PingResult {
@@ -25,47 +77,345 @@ impl Default for PingResult {
         Self {
             latency_ms: None,
             jitter_ms: None,
+            stddev_ms: None,
             packet_loss_percent: None,
+            min_ms: None,
+            max_ms: None,
+            median_ms: None,
+            p95_ms: None,
+            p99_ms: None,
+            samples_ms: Vec::new(),
+            sent: None,
+            received: None,
+            duplicates: None,
         }
     }
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DnsResolver {
+    pub interface: Option<String>,
+    pub servers: Vec<String>,
+    pub search_domains: Vec<String>,
+    pub transport: DnsTransport,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DnsInfo {
     pub servers: Vec<String>,
+    pub search_domains: Vec<String>,
+    pub resolvers: Vec<DnsResolver>,
     pub lookup_latency_ms: Option<f64>,
+    pub lookup_rcode: Option<String>,
+    pub lookup_answer_count: Option<u16>,
+    pub lookup_transport: Option<DnsTransport>,
+    // Split of lookup_latency_ms; setup is None when no connection was opened.
+    pub lookup_connect_ms: Option<f64>,
+    pub lookup_query_ms: Option<f64>,
 }
 
 impl Default for DnsInfo {
     fn default() -> Self {
         Self {
             servers: Vec::new(),
+            search_domains: Vec::new(),
+            resolvers: Vec::new(),
             lookup_latency_ms: None,
+            lookup_rcode: None,
+            lookup_answer_count: None,
+            lookup_transport: None,
+            lookup_connect_ms: None,
+            lookup_query_ms: None,
         }
     }
 }
 
-pub fn get_router_ip() -> Option<String> {
-    let output = Command::new("networksetup")
-        .args(["-getinfo", "Wi-Fi"])
-        .output()
-        .ok()?;
+pub fn get_router_ip(
+    runner: &dyn CommandRunner,
+    interface: Option<&NetworkInterface>,
+) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    if runner.native_probes() {
+        if let Some(gateway) = interface
+            .and_then(|i| crate::networkmanager::get_device_state(&i.name))
+            .and_then(|state| state.gateway)
+        {
+            log::debug!(
+                "get_router_ip: NetworkManager reports router at {}",
+                gateway
+            );
+            return Some(gateway);
+        }
+    }
+
+    if cfg!(target_os = "linux") {
+        let gateways = get_default_gateways(runner);
+        let router_ip = select_gateway(&gateways, |iface| {
+            interface.is_some_and(|interface| interface.name == iface)
+        })
+        .map(|gateway| gateway.address.clone());
+        log::debug!("get_router_ip: found router at {:?}", router_ip);
+        return router_ip;
+    }
+
+    let Some(service) = interface.and_then(|i| i.service.as_deref()) else {
+        log::debug!("get_router_ip: no network service for the selected interface");
+        return None;
+    };
+    let output = runner.run("networksetup", &["-getinfo", service]).ok()?;
 
-    if !output.status.success() {
+    if !output.success {
         log::debug!("get_router_ip: networksetup command failed");
         return None;
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let router_ip = ROUTER_RE.captures(&stdout).map(|caps| caps[1].to_string());
+    let router_ip = parse_router(&output.stdout, &ROUTER_RE);
     log::debug!("get_router_ip: found router at {:?}", router_ip);
     router_ip
 }
 
-pub fn ping_host(host: &str, count: u32) -> PingResult {
-    let output = Command::new("ping")
-        .args(["-c", &count.to_string(), "-t", "2", host])
-        .output();
+// The IPv6 default gateway, which `get_router_ip` only returns when there is
+// no IPv4 one. Link-local addresses carry the interface as their scope.
+pub fn get_ipv6_router_ip(
+    runner: &dyn CommandRunner,
+    interface: Option<&NetworkInterface>,
+) -> Option<String> {
+    let name = interface.map(|i| i.name.as_str());
+
+    #[cfg(target_os = "linux")]
+    if runner.native_probes() {
+        if let Some(gateway) = interface
+            .and_then(|i| crate::networkmanager::get_device_state(&i.name))
+            .and_then(|state| state.ipv6_gateway)
+        {
+            log::debug!(
+                "get_ipv6_router_ip: NetworkManager reports router at {}",
+                gateway
+            );
+            return Some(scope_link_local(&gateway, name));
+        }
+    }
+
+    if cfg!(target_os = "linux") {
+        let gateways: Vec<Gateway> = get_default_gateways(runner)
+            .into_iter()
+            .filter(|gateway| is_ipv6_address(&gateway.address))
+            .collect();
+        let router_ip = select_gateway(&gateways, |iface| name == Some(iface))
+            .map(|gateway| gateway.address.clone());
+        log::debug!("get_ipv6_router_ip: found router at {:?}", router_ip);
+        return router_ip;
+    }
+
+    let service = interface.and_then(|i| i.service.as_deref())?;
+    let output = runner.run("networksetup", &["-getinfo", service]).ok()?;
+    if !output.success {
+        log::debug!("get_ipv6_router_ip: networksetup command failed");
+        return None;
+    }
+
+    let router_ip =
+        parse_router(&output.stdout, &IPV6_ROUTER_RE).map(|ip| scope_link_local(&ip, name));
+    log::debug!("get_ipv6_router_ip: found router at {:?}", router_ip);
+    router_ip
+}
+
+// networksetup prints "none" rather than leaving the field out.
+fn parse_router(output: &str, re: &Regex) -> Option<String> {
+    re.captures(output)
+        .map(|caps| caps[1].to_string())
+        .filter(|ip| parse_ip(ip).is_some())
+}
+
+// Parses an address that may carry a `%scope` suffix.
+pub fn parse_ip(address: &str) -> Option<IpAddr> {
+    address.split('%').next()?.parse().ok()
+}
+
+pub fn is_ipv6_address(address: &str) -> bool {
+    parse_ip(address).is_some_and(|ip| ip.is_ipv6())
+}
+
+// Appends `item` unless it is already listed, keeping first-seen order.
+pub(crate) fn push_unique(items: &mut Vec<String>, item: String) {
+    if !items.contains(&item) {
+        items.push(item);
+    }
+}
+
+fn scope_link_local(address: &str, interface: Option<&str>) -> String {
+    match (parse_ip(address), interface) {
+        (Some(IpAddr::V6(ip)), Some(interface))
+            if ip.is_unicast_link_local() && !address.contains('%') =>
+        {
+            format!("{}%{}", address, interface)
+        }
+        _ => address.to_string(),
+    }
+}
+
+pub fn get_default_gateways(runner: &dyn CommandRunner) -> Vec<Gateway> {
+    let mut gateways = Vec::new();
+
+    match runner.read_file("/proc/net/route") {
+        Ok(content) => gateways.extend(parse_proc_net_route(&content)),
+        Err(e) => log::debug!(
+            "get_default_gateways: failed to read /proc/net/route: {}",
+            e
+        ),
+    }
+
+    match runner.read_file("/proc/net/ipv6_route") {
+        Ok(content) => gateways.extend(parse_proc_net_ipv6_route(&content)),
+        Err(e) => log::debug!(
+            "get_default_gateways: failed to read /proc/net/ipv6_route: {}",
+            e
+        ),
+    }
+
+    log::debug!("get_default_gateways: {:?}", gateways);
+    gateways
+}
+
+fn select_gateway(gateways: &[Gateway], is_preferred: impl Fn(&str) -> bool) -> Option<&Gateway> {
+    let is_ipv4 = |gateway: &Gateway| gateway.address.parse::<Ipv4Addr>().is_ok();
+
+    gateways.iter().min_by_key(|gateway| {
+        (
+            !is_preferred(&gateway.interface),
+            !is_ipv4(gateway),
+            gateway.metric,
+        )
+    })
+}
+
+fn parse_proc_net_route(content: &str) -> Vec<Gateway> {
+    let mut gateways = Vec::new();
+
+    for line in content.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 8 {
+            continue;
+        }
+
+        let (Ok(destination), Ok(gateway), Ok(flags), Ok(metric), Ok(mask)) = (
+            u32::from_str_radix(fields[1], 16),
+            u32::from_str_radix(fields[2], 16),
+            u32::from_str_radix(fields[3], 16),
+            fields[6].parse::<u32>(),
+            u32::from_str_radix(fields[7], 16),
+        ) else {
+            continue;
+        };
+
+        if destination != 0 || mask != 0 || flags & RTF_GATEWAY == 0 {
+            continue;
+        }
+
+        gateways.push(Gateway {
+            interface: fields[0].to_string(),
+            address: Ipv4Addr::from(gateway.to_ne_bytes()).to_string(),
+            metric,
+        });
+    }
+
+    gateways
+}
+
+fn parse_proc_net_ipv6_route(content: &str) -> Vec<Gateway> {
+    let mut gateways = Vec::new();
+
+    for line in content.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 10 {
+            continue;
+        }
+
+        let (Some(destination), Ok(prefix_len), Some(gateway), Ok(metric), Ok(flags)) = (
+            parse_ipv6_hex(fields[0]),
+            u8::from_str_radix(fields[1], 16),
+            parse_ipv6_hex(fields[4]),
+            u32::from_str_radix(fields[5], 16),
+            u32::from_str_radix(fields[8], 16),
+        ) else {
+            continue;
+        };
+
+        if !destination.is_unspecified()
+            || prefix_len != 0
+            || gateway.is_unspecified()
+            || flags & RTF_GATEWAY == 0
+        {
+            continue;
+        }
+
+        let interface = fields[9].to_string();
+        let address = if gateway.is_unicast_link_local() {
+            format!("{}%{}", gateway, interface)
+        } else {
+            gateway.to_string()
+        };
+
+        gateways.push(Gateway {
+            interface,
+            address,
+            metric,
+        });
+    }
+
+    gateways
+}
+
+fn parse_ipv6_hex(hex: &str) -> Option<Ipv6Addr> {
+    if hex.len() != 32 {
+        return None;
+    }
+    u128::from_str_radix(hex, 16).ok().map(Ipv6Addr::from)
+}
+
+pub fn ping_host(
+    runner: &dyn CommandRunner,
+    host: &str,
+    count: u32,
+    interface: Option<&NetworkInterface>,
+) -> PingResult {
+    let interface = interface.map(|i| i.name.as_str());
+
+    #[cfg(unix)]
+    if runner.native_probes() {
+        match icmp::echo(host, count, ECHO_INTERVAL, ECHO_TIMEOUT, interface) {
+            Ok(report) => {
+                let result = summarize_echo_report(&report);
+                log_ping_result(host, &result);
+                return result;
+            }
+            Err(e) => log::debug!(
+                "ping_host: in-process echo to {} failed, falling back to ping: {}",
+                host,
+                e
+            ),
+        }
+    }
+
+    // macOS ping is IPv4 only, and its ping6 has no per-reply timeout.
+    let ipv6 = !cfg!(target_os = "linux") && is_ipv6_address(host);
+    let (program, timeout_flag, interface_flag) = if cfg!(target_os = "linux") {
+        ("ping", Some("-W"), "-I")
+    } else if ipv6 {
+        ("ping6", None, "-I")
+    } else {
+        ("ping", Some("-t"), "-b")
+    };
+    let count = count.to_string();
+    let mut args = vec!["-c", count.as_str()];
+    if let Some(timeout_flag) = timeout_flag {
+        args.extend([timeout_flag, "2"]);
+    }
+    if let Some(interface) = interface {
+        args.extend([interface_flag, interface]);
+    }
+    args.push(host);
+    let output = runner.run(program, &args);
 
     let output = match output {
         Ok(o) => o,
@@ -75,18 +425,143 @@ pub fn ping_host(host: &str, count: u32) -> PingResult {
         }
     };
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let result = parse_ping_output(&stdout);
+    let result = parse_ping_output(&output.stdout);
+    log_ping_result(host, &result);
+    result
+}
+
+// Times TCP handshakes instead of ICMP echoes, for networks that filter ping.
+// The result carries the same statistics as `ping_host`.
+pub fn tcp_ping(
+    host: &str,
+    port: u16,
+    count: u32,
+    interface: Option<&NetworkInterface>,
+) -> PingResult {
+    #[cfg(unix)]
+    {
+        let interface = interface.map(|i| i.name.as_str());
+        match tcp::connect_times(host, port, count, ECHO_INTERVAL, ECHO_TIMEOUT, interface) {
+            Ok(report) => {
+                let result = summarize_connect_report(&report);
+                log_ping_result(host, &result);
+                result
+            }
+            Err(e) => {
+                log::error!("tcp_ping: failed to probe {}:{}: {}", host, port, e);
+                PingResult::default()
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (count, interface);
+        log::debug!(
+            "tcp_ping: not supported on this platform, skipping {}:{}",
+            host,
+            port
+        );
+        PingResult::default()
+    }
+}
+
+fn log_ping_result(host: &str, result: &PingResult) {
     log::debug!(
-        "ping_host: {} - latency: {:?}ms, jitter: {:?}ms, loss: {:?}%",
+        "ping_host: {} - latency: {:?}ms, p95: {:?}ms, jitter: {:?}ms, loss: {:?}%, dup: {:?}",
         host,
         result.latency_ms,
+        result.p95_ms,
         result.jitter_ms,
-        result.packet_loss_percent
+        result.packet_loss_percent,
+        result.duplicates
     );
+}
+
+#[cfg(unix)]
+fn summarize_echo_report(report: &icmp::EchoReport) -> PingResult {
+    let mut result = PingResult {
+        sent: Some(report.sent),
+        received: Some(report.received()),
+        duplicates: Some(report.duplicates()),
+        ..Default::default()
+    };
+
+    if report.sent > 0 {
+        let lost = report.sent.saturating_sub(report.received());
+        result.packet_loss_percent = Some(lost as f64 / report.sent as f64 * 100.0);
+    }
+
+    apply_samples(&mut result, report.rtts_ms());
     result
 }
 
+#[cfg(unix)]
+fn summarize_connect_report(report: &tcp::ConnectReport) -> PingResult {
+    summarize_samples(report.sent, report.rtts_ms.clone())
+}
+
+// Statistics for probes that either got exactly one answer or were lost.
+pub fn summarize_samples(sent: u32, samples: Vec<f64>) -> PingResult {
+    let received = samples.len() as u32;
+    let mut result = PingResult {
+        sent: Some(sent),
+        received: Some(received),
+        duplicates: Some(0),
+        ..Default::default()
+    };
+
+    if sent > 0 {
+        let lost = sent.saturating_sub(received);
+        result.packet_loss_percent = Some(lost as f64 / sent as f64 * 100.0);
+    }
+
+    apply_samples(&mut result, samples);
+    result
+}
+
+// Fills the sample-derived statistics. Summary values the ping engine reported
+// itself are kept, except jitter and stddev which always come from the samples.
+fn apply_samples(result: &mut PingResult, samples: Vec<f64>) {
+    if !samples.is_empty() {
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance =
+            samples.iter().map(|rtt| (rtt - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+
+        let mut sorted = samples.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+
+        result.latency_ms = result.latency_ms.or(Some(mean));
+        result.jitter_ms = interarrival_jitter(&samples);
+        result.stddev_ms = Some(variance.sqrt());
+        result.min_ms = result.min_ms.or(sorted.first().copied());
+        result.max_ms = result.max_ms.or(sorted.last().copied());
+        result.median_ms = percentile(&sorted, 50.0);
+        result.p95_ms = percentile(&sorted, 95.0);
+        result.p99_ms = percentile(&sorted, 99.0);
+    }
+    result.samples_ms = samples;
+}
+
+// Mean absolute difference between consecutive RTTs, the RFC 3550 notion of
+// jitter used by VoIP tools and most speed tests.
+fn interarrival_jitter(samples: &[f64]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let total: f64 = samples.windows(2).map(|w| (w[1] - w[0]).abs()).sum();
+    Some(total / (samples.len() - 1) as f64)
+}
+
+// Linear interpolation between the closest ranks of an ascending sample set.
+fn percentile(sorted: &[f64], pct: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = pct / 100.0 * last as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
 fn parse_ping_output(output: &str) -> PingResult {
     let mut result = PingResult::default();
 
@@ -94,25 +569,132 @@ fn parse_ping_output(output: &str) -> PingResult {
         result.packet_loss_percent = caps[1].parse().ok();
     }
 
+    if let Some(caps) = PING_COUNTS_RE.captures(output) {
+        result.sent = caps[1].parse().ok();
+        result.received = caps[2].parse().ok();
+        result.duplicates = caps.get(3).map_or(Some(0), |m| m.as_str().parse().ok());
+    }
+
     if let Some(caps) = PING_STATS_RE.captures(output) {
+        result.min_ms = caps[1].parse().ok();
         result.latency_ms = caps[2].parse().ok();
-        result.jitter_ms = caps[4].parse().ok();
+        result.max_ms = caps[3].parse().ok();
+        result.stddev_ms = caps[4].parse().ok();
     }
 
+    let samples = output
+        .lines()
+        .filter_map(|line| PING_REPLY_RE.captures(line))
+        .filter(|caps| !caps[2].contains("DUP!"))
+        .filter_map(|caps| caps[1].parse().ok())
+        .collect();
+    apply_samples(&mut result, samples);
+
     result
 }
 
-pub fn get_dns_info() -> DnsInfo {
+pub fn get_dns_info(runner: &dyn CommandRunner, interface: Option<&NetworkInterface>) -> DnsInfo {
+    let mut info = if cfg!(target_os = "linux") {
+        get_dns_config_linux(runner)
+    } else {
+        get_dns_config_scutil(runner)
+    };
+
+    #[cfg(target_os = "linux")]
+    if runner.native_probes() {
+        if let Some(state) =
+            interface.and_then(|i| crate::networkmanager::get_device_state(&i.name))
+        {
+            merge_resolver(&mut info, state.dns);
+        }
+    }
+
+    if let Some(server) = lookup_server(&info, interface.map(|i| i.name.as_str())) {
+        if runner.native_probes() {
+            query_dns_lookup(&mut info, &server);
+        } else {
+            measure_dns_lookup(runner, &mut info, &server);
+        }
+        log::debug!(
+            "get_dns_info: servers: {:?}, search domains: {:?}, lookup latency: {:?}ms over {:?}, rcode: {:?}",
+            info.servers,
+            info.search_domains,
+            info.lookup_latency_ms,
+            info.lookup_transport,
+            info.lookup_rcode
+        );
+    } else {
+        log::debug!("get_dns_info: no DNS servers found");
+    }
+
+    info
+}
+
+// The server to time a lookup against, as `dns::parse_server` takes it.
+fn lookup_server(info: &DnsInfo, interface: Option<&str>) -> Option<String> {
+    let server = info
+        .resolvers
+        .iter()
+        .find(|resolver| interface.is_some() && resolver.interface.as_deref() == interface)
+        .and_then(|resolver| resolver.servers.first())
+        .or_else(|| info.servers.first())?;
+
+    // systemd-resolved may use DoT without knowing the server's name.
+    let tls = info
+        .resolvers
+        .iter()
+        .any(|r| r.transport == DnsTransport::Tls && r.servers.contains(server));
+    if tls && !server.contains('#') {
+        Some(format!("tls://{}", server))
+    } else {
+        Some(server.clone())
+    }
+}
+
+// Folds a per-interface resolver (e.g. from NetworkManager) into the system view,
+// replacing whatever was known for that interface.
+fn merge_resolver(info: &mut DnsInfo, resolver: DnsResolver) {
+    if resolver.servers.is_empty() {
+        return;
+    }
+
+    for server in &resolver.servers {
+        if !info.servers.contains(server) {
+            info.servers.push(server.clone());
+        }
+    }
+    for domain in &resolver.search_domains {
+        if !info.search_domains.contains(domain) {
+            info.search_domains.push(domain.clone());
+        }
+    }
+
+    match info
+        .resolvers
+        .iter_mut()
+        .find(|r| r.interface.is_some() && r.interface == resolver.interface)
+    {
+        // NetworkManager does not know whether systemd-resolved uses DoT.
+        Some(existing) => {
+            *existing = DnsResolver {
+                transport: existing.transport,
+                ..resolver
+            }
+        }
+        None => info.resolvers.push(resolver),
+    }
+}
+
+fn get_dns_config_scutil(runner: &dyn CommandRunner) -> DnsInfo {
     let mut info = DnsInfo::default();
 
-    let output = Command::new("scutil")
-        .args(["--dns"])
-        .output();
+    let output = runner.run("scutil", &["--dns"]);
 
     if let Ok(output) = output {
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            info.servers = parse_dns_servers(&stdout);
+        if output.success {
+            info.servers = parse_dns_servers(&output.stdout);
+            info.resolvers = parse_scutil_resolvers(&output.stdout);
+            info.search_domains = collect_search_domains(&info.resolvers);
         } else {
             log::debug!("get_dns_info: scutil command failed");
         }
@@ -120,18 +702,206 @@ pub fn get_dns_info() -> DnsInfo {
         log::debug!("get_dns_info: failed to run scutil");
     }
 
-    if !info.servers.is_empty() {
-        info.lookup_latency_ms = measure_dns_lookup(&info.servers[0]);
-        log::debug!(
-            "get_dns_info: servers: {:?}, lookup latency: {:?}ms",
-            info.servers,
-            info.lookup_latency_ms
-        );
-    } else {
-        log::debug!("get_dns_info: no DNS servers found");
+    info
+}
+
+fn get_dns_config_linux(runner: &dyn CommandRunner) -> DnsInfo {
+    let content = match runner.read_file("/etc/resolv.conf") {
+        Ok(c) => c,
+        Err(e) => {
+            log::debug!("get_dns_info: failed to read /etc/resolv.conf: {}", e);
+            return DnsInfo::default();
+        }
+    };
+
+    let info = parse_resolv_conf(&content);
+    if !info
+        .servers
+        .iter()
+        .any(|server| RESOLVED_STUB_ADDRESSES.contains(&server.as_str()))
+    {
+        return info;
     }
 
-    info
+    log::debug!("get_dns_info: resolv.conf points at the systemd-resolved stub");
+    let output = match runner.run("resolvectl", &["status"]) {
+        Ok(o) if o.success => o,
+        Ok(_) => {
+            log::debug!("get_dns_info: resolvectl failed");
+            return info;
+        }
+        Err(e) => {
+            log::debug!("get_dns_info: failed to run resolvectl: {}", e);
+            return info;
+        }
+    };
+
+    let resolvers = parse_resolvectl_status(&output.stdout);
+    if resolvers.iter().all(|r| r.servers.is_empty()) {
+        return info;
+    }
+
+    let mut search_domains = collect_search_domains(&resolvers);
+    for domain in info.search_domains {
+        if !search_domains.contains(&domain) {
+            search_domains.push(domain);
+        }
+    }
+
+    DnsInfo {
+        servers: collect_servers(&resolvers),
+        search_domains,
+        resolvers,
+        ..Default::default()
+    }
+}
+
+fn parse_resolv_conf(content: &str) -> DnsInfo {
+    let mut resolver = DnsResolver::default();
+
+    for line in content.lines() {
+        let line = line.split(['#', ';']).next().unwrap_or("").trim();
+        let mut fields = line.split_whitespace();
+        match fields.next() {
+            Some("nameserver") => {
+                if let Some(server) = fields.next() {
+                    if !resolver.servers.iter().any(|s| s == server) {
+                        resolver.servers.push(server.to_string());
+                    }
+                }
+            }
+            Some("search") | Some("domain") => {
+                resolver.search_domains = fields.map(|d| d.to_string()).collect();
+            }
+            _ => {}
+        }
+    }
+
+    DnsInfo {
+        servers: resolver.servers.clone(),
+        search_domains: resolver.search_domains.clone(),
+        resolvers: vec![resolver],
+        ..Default::default()
+    }
+}
+
+fn parse_resolvectl_status(output: &str) -> Vec<DnsResolver> {
+    let mut resolvers = Vec::new();
+    let mut current: Option<DnsResolver> = None;
+    let mut in_server_list = false;
+
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            in_server_list = false;
+            continue;
+        }
+
+        if !line.starts_with(char::is_whitespace) && !RESOLVECTL_FIELD_RE.is_match(line) {
+            resolvers.extend(current.take());
+            in_server_list = false;
+            if line.trim() == "Global" {
+                current = Some(DnsResolver::default());
+            } else if let Some(caps) = RESOLVECTL_LINK_RE.captures(line) {
+                current = Some(DnsResolver {
+                    interface: Some(caps[1].to_string()),
+                    ..Default::default()
+                });
+            }
+            continue;
+        }
+
+        let Some(resolver) = current.as_mut() else {
+            continue;
+        };
+
+        let values = match RESOLVECTL_FIELD_RE.captures(line) {
+            Some(caps) => {
+                in_server_list = &caps[1] == "DNS Servers";
+                match &caps[1] {
+                    "DNS Servers" => caps[2].to_string(),
+                    "Protocols" => {
+                        if caps[2].split_whitespace().any(|p| p == "+DNSOverTLS") {
+                            resolver.transport = DnsTransport::Tls;
+                        }
+                        continue;
+                    }
+                    "DNS Domain" => {
+                        resolver.search_domains.extend(
+                            caps[2]
+                                .split_whitespace()
+                                .filter(|d| !d.starts_with('~'))
+                                .map(|d| d.to_string()),
+                        );
+                        continue;
+                    }
+                    _ => continue,
+                }
+            }
+            None if in_server_list => line.trim().to_string(),
+            None => continue,
+        };
+
+        // The `#name` suffix is the certificate name, which only DoT uses.
+        for server in values.split_whitespace() {
+            let server = match resolver.transport {
+                DnsTransport::Tls => server.to_string(),
+                _ => server.split('#').next().unwrap_or(server).to_string(),
+            };
+            if !resolver.servers.contains(&server) {
+                resolver.servers.push(server);
+            }
+        }
+    }
+
+    resolvers.extend(current);
+    resolvers.retain(|r| !r.servers.is_empty() || !r.search_domains.is_empty());
+    resolvers
+}
+
+fn parse_scutil_resolvers(output: &str) -> Vec<DnsResolver> {
+    let mut resolvers: Vec<DnsResolver> = Vec::new();
+
+    for block in output.split("resolver #").skip(1) {
+        let resolver = DnsResolver {
+            interface: SCUTIL_IF_INDEX_RE
+                .captures(block)
+                .map(|caps| caps[1].to_string()),
+            servers: parse_dns_servers(block),
+            search_domains: SCUTIL_SEARCH_DOMAIN_RE
+                .captures_iter(block)
+                .map(|caps| caps[1].to_string())
+                .collect(),
+            // scutil does not say how queries travel.
+            ..Default::default()
+        };
+
+        if resolver.servers.is_empty() || resolvers.contains(&resolver) {
+            continue;
+        }
+        resolvers.push(resolver);
+    }
+
+    resolvers
+}
+
+fn collect_servers(resolvers: &[DnsResolver]) -> Vec<String> {
+    let mut servers: Vec<String> = Vec::new();
+    for server in resolvers.iter().flat_map(|r| &r.servers) {
+        if !servers.contains(server) {
+            servers.push(server.clone());
+        }
+    }
+    servers
+}
+
+fn collect_search_domains(resolvers: &[DnsResolver]) -> Vec<String> {
+    let mut domains: Vec<String> = Vec::new();
+    for domain in resolvers.iter().flat_map(|r| &r.search_domains) {
+        if !domains.contains(domain) {
+            domains.push(domain.clone());
+        }
+    }
+    domains
 }
 
 fn parse_dns_servers(output: &str) -> Vec<String> {
@@ -139,7 +909,7 @@ fn parse_dns_servers(output: &str) -> Vec<String> {
 
     for caps in DNS_SERVER_RE.captures_iter(output) {
         let server = caps[1].to_string();
-        if !servers.contains(&server) {
+        if parse_ip(&server).is_some() && !servers.contains(&server) {
             servers.push(server);
         }
     }
@@ -147,28 +917,55 @@ fn parse_dns_servers(output: &str) -> Vec<String> {
     servers
 }
 
-fn measure_dns_lookup(dns_server: &str) -> Option<f64> {
-    let server_arg = format!("@{}", dns_server);
-    let output = Command::new("dig")
-        .args([
-            &server_arg,
-            "google.com",
-            "+noall",
-            "+stats",
-            "+tries=1",
-            "+time=2",
-        ])
-        .output()
-        .ok()?;
-
-    if !output.status.success() {
-        return None;
+fn query_dns_lookup(info: &mut DnsInfo, dns_server: &str) {
+    let response = dns::parse_server(dns_server).and_then(|server| {
+        info.lookup_transport = Some(server.transport());
+        dns::lookup(&server, DNS_LOOKUP_NAME, RecordType::A, DNS_LOOKUP_TIMEOUT)
+    });
+
+    match response {
+        Ok(response) => {
+            info.lookup_latency_ms = Some(response.latency_ms);
+            info.lookup_rcode = Some(response.rcode_name());
+            info.lookup_answer_count = Some(response.answer_count);
+            info.lookup_transport = Some(response.transport);
+            info.lookup_connect_ms = response.connect_ms;
+            info.lookup_query_ms = Some(response.query_ms);
+        }
+        Err(e) => log::debug!("query_dns_lookup: query to {} failed: {}", dns_server, e),
     }
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    QUERY_TIME_RE
-        .captures(&stdout)
-        .and_then(|caps| caps[1].parse().ok())
+fn measure_dns_lookup(runner: &dyn CommandRunner, info: &mut DnsInfo, dns_server: &str) {
+    let (server, transport) = match dns_server.strip_prefix("tls://") {
+        Some(server) => (server, DnsTransport::Tls),
+        None => match dns_server.split_once('#') {
+            Some((server, _)) => (server, DnsTransport::Tls),
+            None => (dns_server, DnsTransport::Udp),
+        },
+    };
+    info.lookup_transport = Some(transport);
+
+    let server_arg = format!("@{}", server);
+    let mut args = vec![
+        server_arg.as_str(),
+        DNS_LOOKUP_NAME,
+        "+noall",
+        "+stats",
+        "+tries=1",
+        "+time=2",
+    ];
+    if transport == DnsTransport::Tls {
+        args.push("+tls");
+    }
+
+    let output = match runner.run("dig", &args) {
+        Ok(output) if output.success => output,
+        _ => return,
+    };
+    info.lookup_latency_ms = QUERY_TIME_RE
+        .captures(&output.stdout)
+        .and_then(|caps| caps[1].parse().ok());
 }
 
 #[cfg(test)]
@@ -190,8 +987,199 @@ round-trip min/avg/max/stddev = 11.234/12.715/14.567/1.234 ms
 
         let result = parse_ping_output(sample);
         assert!((result.latency_ms.unwrap() - 12.715).abs() < 0.001);
-        assert!((result.jitter_ms.unwrap() - 1.234).abs() < 0.001);
+        assert!((result.jitter_ms.unwrap() - 2.7775).abs() < 0.001);
+        assert!((result.stddev_ms.unwrap() - 1.386).abs() < 0.001);
         assert!((result.packet_loss_percent.unwrap() - 0.0).abs() < 0.001);
+        assert_eq!(result.samples_ms, vec![12.345, 14.567, 11.234]);
+        assert_eq!(result.min_ms, Some(11.234));
+        assert_eq!(result.max_ms, Some(14.567));
+        assert_eq!(result.median_ms, Some(12.345));
+        assert!((result.p95_ms.unwrap() - 14.345).abs() < 0.001);
+        assert_eq!(result.sent, Some(3));
+        assert_eq!(result.received, Some(3));
+        assert_eq!(result.duplicates, Some(0));
+    }
+
+    #[test]
+    fn test_parse_linux_ping_output() {
+        let sample = r#"
+PING 192.168.1.1 (192.168.1.1) 56(84) bytes of data.
+64 bytes from 192.168.1.1: icmp_seq=1 ttl=64 time=2.31 ms
+64 bytes from 192.168.1.1: icmp_seq=2 ttl=64 time=1.91 ms
+
+--- 192.168.1.1 ping statistics ---
+3 packets transmitted, 2 received, 33.3333% packet loss, time 2003ms
+rtt min/avg/max/mdev = 1.912/2.111/2.311/0.199 ms
+"#;
+
+        let result = parse_ping_output(sample);
+        assert!((result.latency_ms.unwrap() - 2.111).abs() < 0.001);
+        assert!((result.jitter_ms.unwrap() - 0.4).abs() < 0.001);
+        assert!((result.stddev_ms.unwrap() - 0.2).abs() < 0.001);
+        assert!((result.packet_loss_percent.unwrap() - 33.3333).abs() < 0.001);
+        assert_eq!(result.samples_ms, vec![2.31, 1.91]);
+        assert_eq!(result.min_ms, Some(1.912));
+        assert_eq!(result.sent, Some(3));
+        assert_eq!(result.received, Some(2));
+    }
+
+    #[test]
+    fn test_parse_ping_output_duplicates() {
+        let sample = r#"
+PING 192.168.1.1 (192.168.1.1) 56(84) bytes of data.
+64 bytes from 192.168.1.1: icmp_seq=1 ttl=64 time=2.00 ms
+64 bytes from 192.168.1.1: icmp_seq=1 ttl=64 time=9.00 ms (DUP!)
+64 bytes from 192.168.1.1: icmp_seq=2 ttl=64 time=4.00 ms
+
+--- 192.168.1.1 ping statistics ---
+2 packets transmitted, 2 received, +1 duplicates, 0% packet loss, time 1001ms
+"#;
+
+        let result = parse_ping_output(sample);
+        assert_eq!(result.samples_ms, vec![2.0, 4.0]);
+        assert_eq!(result.duplicates, Some(1));
+        assert_eq!(result.received, Some(2));
+        assert_eq!(result.latency_ms, Some(3.0));
+        assert_eq!(result.max_ms, Some(4.0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_summarize_connect_report() {
+        let result = summarize_connect_report(&tcp::ConnectReport {
+            sent: 4,
+            rtts_ms: vec![10.0, 14.0, 12.0],
+        });
+        assert_eq!(result.latency_ms, Some(12.0));
+        assert_eq!(result.jitter_ms, Some(3.0));
+        assert_eq!(result.packet_loss_percent, Some(25.0));
+        assert_eq!(result.received, Some(3));
+        assert_eq!(result.median_ms, Some(12.0));
+    }
+
+    #[test]
+    fn test_interarrival_jitter() {
+        // A steady path has no jitter however high the latency.
+        assert_eq!(interarrival_jitter(&[80.0, 80.0, 80.0, 80.0]), Some(0.0));
+        // Alternating RTTs: large jitter, same stddev as a slow ramp below.
+        assert_eq!(interarrival_jitter(&[10.0, 30.0, 10.0, 30.0]), Some(20.0));
+        // A gradual ramp changes by 20ms overall but only ~6.67ms per packet.
+        let jitter = interarrival_jitter(&[10.0, 10.0, 30.0, 30.0]).unwrap();
+        assert!((jitter - 6.667).abs() < 0.001);
+        assert_eq!(interarrival_jitter(&[12.0, 9.0]), Some(3.0));
+        assert_eq!(interarrival_jitter(&[12.0]), None);
+        assert_eq!(interarrival_jitter(&[]), None);
+    }
+
+    #[test]
+    fn test_percentile() {
+        let sorted: Vec<f64> = (1..=100).map(f64::from).collect();
+        assert_eq!(percentile(&sorted, 50.0), Some(50.5));
+        assert!((percentile(&sorted, 95.0).unwrap() - 95.05).abs() < 0.001);
+        assert!((percentile(&sorted, 99.0).unwrap() - 99.01).abs() < 0.001);
+        assert_eq!(percentile(&[7.0], 99.0), Some(7.0));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_summarize_echo_report() {
+        let reply = |sequence, rtt_ms, duplicate| icmp::EchoReply {
+            sequence,
+            rtt_ms,
+            ttl: Some(64),
+            duplicate,
+            out_of_order: false,
+        };
+        let report = icmp::EchoReport {
+            sent: 4,
+            replies: vec![
+                reply(0, 2.0, false),
+                reply(1, 4.0, false),
+                reply(1, 9.0, true),
+                reply(3, 6.0, false),
+            ],
+        };
+
+        let result = summarize_echo_report(&report);
+        assert!((result.latency_ms.unwrap() - 4.0).abs() < 0.001);
+        assert!((result.jitter_ms.unwrap() - 2.0).abs() < 0.001);
+        assert!((result.stddev_ms.unwrap() - 1.633).abs() < 0.001);
+        assert!((result.packet_loss_percent.unwrap() - 25.0).abs() < 0.001);
+        assert_eq!(result.samples_ms, vec![2.0, 4.0, 6.0]);
+        assert_eq!(result.median_ms, Some(4.0));
+        assert_eq!(result.min_ms, Some(2.0));
+        assert_eq!(result.max_ms, Some(6.0));
+        assert_eq!(result.sent, Some(4));
+        assert_eq!(result.received, Some(3));
+        assert_eq!(result.duplicates, Some(1));
+
+        let result = summarize_echo_report(&icmp::EchoReport {
+            sent: 3,
+            replies: Vec::new(),
+        });
+        assert_eq!(result.latency_ms, None);
+        assert_eq!(result.packet_loss_percent, Some(100.0));
+    }
+
+    #[test]
+    fn test_parse_proc_net_route() {
+        let sample =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+enp0s31f6\t00000000\t0100000A\t0003\t0\t0\t100\t00000000\t0\t0\t0
+wlp2s0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+wlp2s0\t0001A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+";
+
+        let gateways = parse_proc_net_route(sample);
+        assert_eq!(gateways.len(), 2);
+        assert_eq!(gateways[0].interface, "enp0s31f6");
+        assert_eq!(gateways[0].address, "10.0.0.1");
+        assert_eq!(gateways[1].interface, "wlp2s0");
+        assert_eq!(gateways[1].address, "192.168.1.1");
+        assert_eq!(gateways[1].metric, 600);
+    }
+
+    #[test]
+    fn test_parse_proc_net_ipv6_route() {
+        let sample = "\
+fe800000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000258 00000001 00000000 00000001 wlp2s0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000001a2b3cfffe4d5e6f 00000258 00000001 00000000 00000003 wlp2s0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200 lo
+";
+
+        let gateways = parse_proc_net_ipv6_route(sample);
+        assert_eq!(gateways.len(), 1);
+        assert_eq!(gateways[0].interface, "wlp2s0");
+        assert_eq!(gateways[0].address, "fe80::1a2b:3cff:fe4d:5e6f%wlp2s0");
+        assert_eq!(gateways[0].metric, 600);
+    }
+
+    #[test]
+    fn test_select_gateway() {
+        let gateways = vec![
+            Gateway {
+                interface: "enp0s31f6".to_string(),
+                address: "10.0.0.1".to_string(),
+                metric: 100,
+            },
+            Gateway {
+                interface: "wlp2s0".to_string(),
+                address: "fe80::1%wlp2s0".to_string(),
+                metric: 600,
+            },
+            Gateway {
+                interface: "wlp2s0".to_string(),
+                address: "192.168.1.1".to_string(),
+                metric: 600,
+            },
+        ];
+
+        let selected = select_gateway(&gateways, |iface| iface.starts_with("wl"));
+        assert_eq!(selected.unwrap().address, "192.168.1.1");
+
+        let selected = select_gateway(&gateways, |_| false);
+        assert_eq!(selected.unwrap().address, "10.0.0.1");
     }
 
     #[test]
@@ -207,12 +1195,270 @@ resolver #1
 
 resolver #2
   nameserver[0] : 192.168.1.1
+  nameserver[1] : 2001:4860:4860::8888
+  nameserver[2] : fe80::1%en0
   flags    : Request A records
 "#;
 
         let servers = parse_dns_servers(sample);
-        assert_eq!(servers.len(), 2);
+        assert_eq!(servers.len(), 4);
         assert_eq!(servers[0], "192.168.1.1");
         assert_eq!(servers[1], "8.8.8.8");
+        assert_eq!(servers[2], "2001:4860:4860::8888");
+        assert_eq!(servers[3], "fe80::1%en0");
+    }
+
+    #[test]
+    fn test_parse_router() {
+        let sample = "\
+DHCP Configuration
+IP address: 192.168.1.23
+Subnet mask: 255.255.255.0
+Router: 192.168.1.1
+Client ID:
+IPv6: Automatic
+IPv6 IP address: 2001:db8::23
+IPv6 Router: fe80::1
+Wi-Fi ID: a0:b1:c2:d3:e4:f5
+";
+        assert_eq!(
+            parse_router(sample, &ROUTER_RE),
+            Some("192.168.1.1".to_string())
+        );
+        assert_eq!(
+            parse_router(sample, &IPV6_ROUTER_RE),
+            Some("fe80::1".to_string())
+        );
+
+        let sample = "Router: none\nIPv6: Off\nIPv6 Router: none\n";
+        assert_eq!(parse_router(sample, &ROUTER_RE), None);
+        assert_eq!(parse_router(sample, &IPV6_ROUTER_RE), None);
+    }
+
+    #[test]
+    fn test_scope_link_local() {
+        assert_eq!(scope_link_local("fe80::1", Some("en0")), "fe80::1%en0");
+        assert_eq!(
+            scope_link_local("fe80::1%wlp2s0", Some("en0")),
+            "fe80::1%wlp2s0"
+        );
+        assert_eq!(scope_link_local("2001:db8::1", Some("en0")), "2001:db8::1");
+        assert_eq!(scope_link_local("192.168.1.1", Some("en0")), "192.168.1.1");
+        assert_eq!(scope_link_local("fe80::1", None), "fe80::1");
+        assert!(is_ipv6_address("fe80::1%en0"));
+        assert!(!is_ipv6_address("192.168.1.1"));
+    }
+
+    #[test]
+    fn test_parse_scutil_resolvers() {
+        let sample = r#"
+DNS configuration
+
+resolver #1
+  search domain[0] : lan
+  nameserver[0] : 192.168.1.1
+  if_index : 6 (en0)
+  flags    : Request A records
+
+resolver #2
+  domain   : local
+  options  : mdns
+  timeout  : 5
+
+DNS configuration (for scoped queries)
+
+resolver #1
+  search domain[0] : lan
+  nameserver[0] : 192.168.1.1
+  if_index : 6 (en0)
+  flags    : Scoped, Request A records
+"#;
+
+        let resolvers = parse_scutil_resolvers(sample);
+        assert_eq!(resolvers.len(), 1);
+        assert_eq!(resolvers[0].interface, Some("en0".to_string()));
+        assert_eq!(resolvers[0].servers, vec!["192.168.1.1"]);
+        assert_eq!(collect_search_domains(&resolvers), vec!["lan"]);
+    }
+
+    #[test]
+    fn test_parse_resolv_conf() {
+        let sample = r#"
+# This is /run/systemd/resolve/stub-resolv.conf managed by man:systemd-resolved(8).
+nameserver 127.0.0.53
+options edns0 trust-ad
+search corp.example.com lan
+"#;
+
+        let info = parse_resolv_conf(sample);
+        assert_eq!(info.servers, vec!["127.0.0.53"]);
+        assert_eq!(info.search_domains, vec!["corp.example.com", "lan"]);
+        assert_eq!(info.resolvers.len(), 1);
+        assert_eq!(info.resolvers[0].interface, None);
+    }
+
+    #[test]
+    fn test_parse_resolvectl_status() {
+        let sample = r#"Global
+           Protocols: +LLMNR +mDNS -DNSOverTLS DNSSEC=no/unsupported
+    resolv.conf mode: stub
+
+Link 2 (enp0s31f6)
+    Current Scopes: none
+         Protocols: -DefaultRoute +LLMNR -mDNS -DNSOverTLS DNSSEC=no/unsupported
+
+Link 3 (wlp2s0)
+    Current Scopes: DNS LLMNR/IPv4 LLMNR/IPv6
+         Protocols: +DefaultRoute +LLMNR -mDNS -DNSOverTLS DNSSEC=no/unsupported
+Current DNS Server: 192.168.1.1
+       DNS Servers: 192.168.1.1 fd00::1
+                    1.1.1.1#cloudflare-dns.com
+        DNS Domain: lan ~.
+
+Link 4 (tun0)
+    Current Scopes: DNS
+       DNS Servers: 10.8.0.1
+        DNS Domain: corp.example.com
+"#;
+
+        let resolvers = parse_resolvectl_status(sample);
+        assert_eq!(resolvers.len(), 2);
+        assert_eq!(resolvers[0].interface, Some("wlp2s0".to_string()));
+        assert_eq!(
+            resolvers[0].servers,
+            vec!["192.168.1.1", "fd00::1", "1.1.1.1"]
+        );
+        assert_eq!(resolvers[0].search_domains, vec!["lan"]);
+        assert_eq!(resolvers[1].interface, Some("tun0".to_string()));
+        assert_eq!(resolvers[1].servers, vec!["10.8.0.1"]);
+
+        assert_eq!(
+            collect_servers(&resolvers),
+            vec!["192.168.1.1", "fd00::1", "1.1.1.1", "10.8.0.1"]
+        );
+        assert_eq!(
+            collect_search_domains(&resolvers),
+            vec!["lan", "corp.example.com"]
+        );
+    }
+
+    #[test]
+    fn test_parse_resolvectl_dns_over_tls() {
+        let sample = r#"Global
+           Protocols: +LLMNR +mDNS +DNSOverTLS DNSSEC=no/unsupported
+    resolv.conf mode: stub
+
+Link 3 (wlp2s0)
+    Current Scopes: DNS
+         Protocols: +DefaultRoute +LLMNR -mDNS +DNSOverTLS DNSSEC=no/unsupported
+       DNS Servers: 1.1.1.1#cloudflare-dns.com 9.9.9.9
+"#;
+
+        let resolvers = parse_resolvectl_status(sample);
+        assert_eq!(resolvers.len(), 1);
+        assert_eq!(resolvers[0].transport, DnsTransport::Tls);
+        assert_eq!(
+            resolvers[0].servers,
+            vec!["1.1.1.1#cloudflare-dns.com", "9.9.9.9"]
+        );
+
+        let mut info = DnsInfo {
+            servers: collect_servers(&resolvers),
+            resolvers,
+            ..Default::default()
+        };
+        assert_eq!(
+            lookup_server(&info, Some("wlp2s0")),
+            Some("1.1.1.1#cloudflare-dns.com".to_string())
+        );
+        info.resolvers[0].servers.remove(0);
+        assert_eq!(
+            lookup_server(&info, Some("wlp2s0")),
+            Some("tls://9.9.9.9".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lookup_server() {
+        let info = DnsInfo {
+            servers: vec!["10.0.0.53".to_string(), "192.168.1.1".to_string()],
+            resolvers: vec![
+                DnsResolver {
+                    interface: Some("enp0s31f6".to_string()),
+                    servers: vec!["10.0.0.53".to_string()],
+                    ..Default::default()
+                },
+                DnsResolver {
+                    interface: Some("wlp2s0".to_string()),
+                    servers: vec!["192.168.1.1".to_string()],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            lookup_server(&info, Some("wlp2s0")),
+            Some("192.168.1.1".to_string())
+        );
+        assert_eq!(lookup_server(&info, None), Some("10.0.0.53".to_string()));
+        assert_eq!(
+            lookup_server(&info, Some("wlan9")),
+            Some("10.0.0.53".to_string())
+        );
+        assert_eq!(lookup_server(&DnsInfo::default(), None), None);
+    }
+
+    #[test]
+    fn test_merge_resolver() {
+        let mut info = parse_resolv_conf("nameserver 127.0.0.53\nsearch lan\n");
+
+        merge_resolver(
+            &mut info,
+            DnsResolver {
+                interface: Some("wlp2s0".to_string()),
+                servers: vec!["192.168.1.1".to_string()],
+                search_domains: vec!["home.arpa".to_string()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(info.servers, vec!["127.0.0.53", "192.168.1.1"]);
+        assert_eq!(info.search_domains, vec!["lan", "home.arpa"]);
+        assert_eq!(info.resolvers.len(), 2);
+        assert_eq!(
+            lookup_server(&info, Some("wlp2s0")),
+            Some("192.168.1.1".to_string())
+        );
+
+        merge_resolver(
+            &mut info,
+            DnsResolver {
+                interface: Some("wlp2s0".to_string()),
+                servers: vec!["1.1.1.1".to_string()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(info.resolvers.len(), 2);
+        assert_eq!(
+            lookup_server(&info, Some("wlp2s0")),
+            Some("1.1.1.1".to_string())
+        );
+
+        info.resolvers[1].transport = DnsTransport::Tls;
+        merge_resolver(
+            &mut info,
+            DnsResolver {
+                interface: Some("wlp2s0".to_string()),
+                servers: vec!["9.9.9.9".to_string()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            lookup_server(&info, Some("wlp2s0")),
+            Some("tls://9.9.9.9".to_string())
+        );
+
+        merge_resolver(&mut info, DnsResolver::default());
+        assert_eq!(info.resolvers.len(), 2);
     }
 }
@@ -0,0 +1,558 @@
+use std::collections::HashMap;
+use std::net::Ipv6Addr;
+use std::sync::OnceLock;
+
+use zbus::blocking::Connection;
+use zbus::proxy;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+use crate::network::{push_unique, DnsResolver};
+use crate::wifi::{format_channel, WifiInfo};
+
+const NM_DEVICE_TYPE_WIFI: u32 = 2;
+
+// Opened on first use and shared, as every metrics poll reads device state
+// several times. A failed connection is not retried.
+static SYSTEM_BUS: OnceLock<Option<Connection>> = OnceLock::new();
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+trait NetworkManager {
+    fn get_device_by_ip_iface(&self, iface: &str) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Device",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Device {
+    #[zbus(property)]
+    fn device_type(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn active_connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property, name = "Ip4Config")]
+    fn ip4_config(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property, name = "Ip6Config")]
+    fn ip6_config(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Wireless {
+    #[zbus(property)]
+    fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn bitrate(&self) -> zbus::Result<u32>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait AccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+
+    #[zbus(property)]
+    fn hw_address(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+
+    #[zbus(property)]
+    fn frequency(&self) -> zbus::Result<u32>;
+
+    // Only exposed by NetworkManager 1.46 and later.
+    #[zbus(property)]
+    fn bandwidth(&self) -> zbus::Result<u32>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait ActiveConnection {
+    #[zbus(property)]
+    fn id(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn uuid(&self) -> zbus::Result<String>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.IP4Config",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Ip4Config {
+    #[zbus(property)]
+    fn gateway(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn nameserver_data(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    #[zbus(property)]
+    fn searches(&self) -> zbus::Result<Vec<String>>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.IP6Config",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Ip6Config {
+    #[zbus(property)]
+    fn gateway(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn nameservers(&self) -> zbus::Result<Vec<Vec<u8>>>;
+
+    #[zbus(property)]
+    fn searches(&self) -> zbus::Result<Vec<String>>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AccessPointState {
+    pub ssid: Option<String>,
+    pub bssid: Option<String>,
+    pub strength: u8,
+    pub frequency_mhz: u32,
+    pub width_mhz: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceState {
+    pub interface: String,
+    pub wireless: bool,
+    pub connection_id: Option<String>,
+    // Names the connection's DHCP lease files.
+    pub connection_uuid: Option<String>,
+    pub access_point: Option<AccessPointState>,
+    pub bitrate_mbps: Option<f64>,
+    // IPv4 when there is one, otherwise IPv6.
+    pub gateway: Option<String>,
+    pub ipv6_gateway: Option<String>,
+    pub dns: DnsResolver,
+}
+
+impl DeviceState {
+    pub fn wifi_info(&self) -> Option<WifiInfo> {
+        if !self.wireless {
+            return None;
+        }
+        let Some(ap) = &self.access_point else {
+            return Some(WifiInfo::default());
+        };
+
+        Some(WifiInfo {
+            connected: true,
+            ssid: ap.ssid.clone(),
+            frequency_band: None,
+            channel: format_channel(ap.frequency_mhz, ap.width_mhz.unwrap_or(20)),
+            link_rate_mbps: self.bitrate_mbps,
+            signal_dbm: Some(strength_to_dbm(ap.strength)),
+            noise_dbm: None,
+        })
+    }
+}
+
+fn system_bus() -> Option<&'static Connection> {
+    SYSTEM_BUS
+        .get_or_init(|| match Connection::system() {
+            Ok(c) => Some(c),
+            Err(e) => {
+                log::debug!(
+                    "networkmanager::system_bus: failed to connect to the system bus: {}",
+                    e
+                );
+                None
+            }
+        })
+        .as_ref()
+}
+
+pub fn get_device_state(ifname: &str) -> Option<DeviceState> {
+    let connection = system_bus()?;
+
+    match read_device_state(connection, ifname) {
+        Ok(state) => {
+            log::debug!("networkmanager::get_device_state: {:?}", state);
+            Some(state)
+        }
+        Err(e) => {
+            log::debug!(
+                "networkmanager::get_device_state: failed to query {}: {}",
+                ifname,
+                e
+            );
+            None
+        }
+    }
+}
+
+fn read_device_state(connection: &Connection, ifname: &str) -> zbus::Result<DeviceState> {
+    let manager = NetworkManagerProxyBlocking::new(connection)?;
+    let device_path = manager.get_device_by_ip_iface(ifname)?;
+    let device = DeviceProxyBlocking::builder(connection)
+        .path(device_path.clone())?
+        .build()?;
+
+    let mut state = DeviceState {
+        interface: ifname.to_string(),
+        wireless: device.device_type()? == NM_DEVICE_TYPE_WIFI,
+        dns: DnsResolver {
+            interface: Some(ifname.to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    if let Some(path) = non_root(device.active_connection()?) {
+        let active = ActiveConnectionProxyBlocking::builder(connection)
+            .path(path)?
+            .build()?;
+        state.connection_id = Some(active.id()?);
+        state.connection_uuid = Some(active.uuid()?).filter(|uuid| !uuid.is_empty());
+    }
+
+    if state.wireless {
+        let wireless = WirelessProxyBlocking::builder(connection)
+            .path(device_path)?
+            .build()?;
+        state.bitrate_mbps = match wireless.bitrate()? {
+            0 => None,
+            kbps => Some(kbps as f64 / 1000.0),
+        };
+        if let Some(path) = non_root(wireless.active_access_point()?) {
+            let ap = AccessPointProxyBlocking::builder(connection)
+                .path(path)?
+                .build()?;
+            let ssid = ap.ssid()?;
+            state.access_point = Some(AccessPointState {
+                ssid: (!ssid.is_empty()).then(|| String::from_utf8_lossy(&ssid).into_owned()),
+                bssid: Some(ap.hw_address()?).filter(|s| !s.is_empty()),
+                strength: ap.strength()?,
+                frequency_mhz: ap.frequency()?,
+                width_mhz: ap.bandwidth().ok().filter(|w| *w > 0),
+            });
+        }
+    }
+
+    if let Some(path) = non_root(device.ip4_config()?) {
+        let config = Ip4ConfigProxyBlocking::builder(connection)
+            .path(path)?
+            .build()?;
+        state.gateway = Some(config.gateway()?).filter(|g| !g.is_empty());
+        for entry in config.nameserver_data()? {
+            if let Some(address) = entry
+                .get("address")
+                .and_then(|v| String::try_from(v.clone()).ok())
+            {
+                push_unique(&mut state.dns.servers, address);
+            }
+        }
+        for domain in config.searches()? {
+            push_unique(&mut state.dns.search_domains, domain);
+        }
+    }
+
+    if let Some(path) = non_root(device.ip6_config()?) {
+        let config = Ip6ConfigProxyBlocking::builder(connection)
+            .path(path)?
+            .build()?;
+        state.ipv6_gateway = Some(config.gateway()?).filter(|g| !g.is_empty());
+        if state.gateway.is_none() {
+            state.gateway = state.ipv6_gateway.clone();
+        }
+        for bytes in config.nameservers()? {
+            if let Ok(octets) = <[u8; 16]>::try_from(bytes.as_slice()) {
+                push_unique(&mut state.dns.servers, Ipv6Addr::from(octets).to_string());
+            }
+        }
+        for domain in config.searches()? {
+            push_unique(&mut state.dns.search_domains, domain);
+        }
+    }
+
+    Ok(state)
+}
+
+// NetworkManager reports "/" for unset object path properties.
+fn non_root(path: OwnedObjectPath) -> Option<OwnedObjectPath> {
+    (path.as_str() != "/").then_some(path)
+}
+
+// NetworkManager derives Strength linearly from -100..-40 dBm, so invert that
+// mapping to get an approximate signal level.
+fn strength_to_dbm(strength: u8) -> i32 {
+    -100 + (strength.min(100) as i32 * 60) / 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use zbus::interface;
+    use zbus::zvariant::{ObjectPath, Value};
+
+    const DEVICE_PATH: &str = "/org/freedesktop/NetworkManager/Devices/3";
+    const ACTIVE_PATH: &str = "/org/freedesktop/NetworkManager/ActiveConnection/1";
+    const AP_PATH: &str = "/org/freedesktop/NetworkManager/AccessPoint/7";
+    const IP4_PATH: &str = "/org/freedesktop/NetworkManager/IP4Config/5";
+    const IP6_PATH: &str = "/org/freedesktop/NetworkManager/IP6Config/5";
+
+    struct PrivateBus(Child);
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    fn start_private_bus() -> Option<(PrivateBus, String)> {
+        let mut child = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address=1"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(child.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+        Some((PrivateBus(child), address.trim().to_string()))
+    }
+
+    struct MockManager;
+
+    #[interface(name = "org.freedesktop.NetworkManager")]
+    impl MockManager {
+        fn get_device_by_ip_iface(&self, iface: &str) -> zbus::fdo::Result<OwnedObjectPath> {
+            if iface == "wlp2s0" {
+                Ok(ObjectPath::try_from(DEVICE_PATH).unwrap().into())
+            } else {
+                Err(zbus::fdo::Error::Failed("No device found".into()))
+            }
+        }
+    }
+
+    struct MockDevice;
+
+    #[interface(name = "org.freedesktop.NetworkManager.Device")]
+    impl MockDevice {
+        #[zbus(property)]
+        fn device_type(&self) -> u32 {
+            NM_DEVICE_TYPE_WIFI
+        }
+
+        #[zbus(property)]
+        fn active_connection(&self) -> OwnedObjectPath {
+            ObjectPath::try_from(ACTIVE_PATH).unwrap().into()
+        }
+
+        #[zbus(property, name = "Ip4Config")]
+        fn ip4_config(&self) -> OwnedObjectPath {
+            ObjectPath::try_from(IP4_PATH).unwrap().into()
+        }
+
+        #[zbus(property, name = "Ip6Config")]
+        fn ip6_config(&self) -> OwnedObjectPath {
+            ObjectPath::try_from(IP6_PATH).unwrap().into()
+        }
+    }
+
+    struct MockWireless;
+
+    #[interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+    impl MockWireless {
+        #[zbus(property)]
+        fn active_access_point(&self) -> OwnedObjectPath {
+            ObjectPath::try_from(AP_PATH).unwrap().into()
+        }
+
+        #[zbus(property)]
+        fn bitrate(&self) -> u32 {
+            866_700
+        }
+    }
+
+    struct MockAccessPoint;
+
+    #[interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+    impl MockAccessPoint {
+        #[zbus(property)]
+        fn ssid(&self) -> Vec<u8> {
+            b"HomeNet".to_vec()
+        }
+
+        #[zbus(property)]
+        fn hw_address(&self) -> String {
+            "AA:BB:CC:DD:EE:01".to_string()
+        }
+
+        #[zbus(property)]
+        fn strength(&self) -> u8 {
+            70
+        }
+
+        #[zbus(property)]
+        fn frequency(&self) -> u32 {
+            5180
+        }
+
+        #[zbus(property)]
+        fn bandwidth(&self) -> u32 {
+            80
+        }
+    }
+
+    struct MockActiveConnection;
+
+    #[interface(name = "org.freedesktop.NetworkManager.Connection.Active")]
+    impl MockActiveConnection {
+        #[zbus(property)]
+        fn id(&self) -> String {
+            "HomeNet".to_string()
+        }
+
+        #[zbus(property)]
+        fn uuid(&self) -> String {
+            "5f0b2e0c-3d6a-4c1e-9a57-2b8f1d4e6c90".to_string()
+        }
+    }
+
+    struct MockIp4Config;
+
+    #[interface(name = "org.freedesktop.NetworkManager.IP4Config")]
+    impl MockIp4Config {
+        #[zbus(property)]
+        fn gateway(&self) -> String {
+            "192.168.1.1".to_string()
+        }
+
+        #[zbus(property)]
+        fn nameserver_data(&self) -> Vec<HashMap<String, OwnedValue>> {
+            ["192.168.1.1", "1.1.1.1"]
+                .iter()
+                .map(|address| {
+                    let value = Value::from(*address).try_to_owned().unwrap();
+                    HashMap::from([("address".to_string(), value)])
+                })
+                .collect()
+        }
+
+        #[zbus(property)]
+        fn searches(&self) -> Vec<String> {
+            vec!["home.arpa".to_string()]
+        }
+    }
+
+    struct MockIp6Config;
+
+    #[interface(name = "org.freedesktop.NetworkManager.IP6Config")]
+    impl MockIp6Config {
+        #[zbus(property)]
+        fn gateway(&self) -> String {
+            "fe80::1".to_string()
+        }
+
+        #[zbus(property)]
+        fn nameservers(&self) -> Vec<Vec<u8>> {
+            vec!["2606:4700:4700::1111"
+                .parse::<Ipv6Addr>()
+                .unwrap()
+                .octets()
+                .to_vec()]
+        }
+
+        #[zbus(property)]
+        fn searches(&self) -> Vec<String> {
+            vec!["home.arpa".to_string()]
+        }
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn test_read_device_state_from_mock_service() {
+        let (_bus, address) = start_private_bus().expect("dbus-daemon unavailable");
+
+        let _service = zbus::blocking::connection::Builder::address(address.as_str())
+            .unwrap()
+            .name("org.freedesktop.NetworkManager")
+            .unwrap()
+            .serve_at("/org/freedesktop/NetworkManager", MockManager)
+            .unwrap()
+            .serve_at(DEVICE_PATH, MockDevice)
+            .unwrap()
+            .serve_at(DEVICE_PATH, MockWireless)
+            .unwrap()
+            .serve_at(AP_PATH, MockAccessPoint)
+            .unwrap()
+            .serve_at(ACTIVE_PATH, MockActiveConnection)
+            .unwrap()
+            .serve_at(IP4_PATH, MockIp4Config)
+            .unwrap()
+            .serve_at(IP6_PATH, MockIp6Config)
+            .unwrap()
+            .build()
+            .unwrap();
+        let client = zbus::blocking::connection::Builder::address(address.as_str())
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let state = read_device_state(&client, "wlp2s0").unwrap();
+        assert!(state.wireless);
+        assert_eq!(state.connection_id.as_deref(), Some("HomeNet"));
+        assert_eq!(
+            state.connection_uuid.as_deref(),
+            Some("5f0b2e0c-3d6a-4c1e-9a57-2b8f1d4e6c90")
+        );
+        assert_eq!(
+            state.access_point,
+            Some(AccessPointState {
+                ssid: Some("HomeNet".to_string()),
+                bssid: Some("AA:BB:CC:DD:EE:01".to_string()),
+                strength: 70,
+                frequency_mhz: 5180,
+                width_mhz: Some(80),
+            })
+        );
+        assert_eq!(state.bitrate_mbps, Some(866.7));
+        assert_eq!(state.gateway.as_deref(), Some("192.168.1.1"));
+        assert_eq!(state.ipv6_gateway.as_deref(), Some("fe80::1"));
+        assert_eq!(state.dns.interface.as_deref(), Some("wlp2s0"));
+        assert_eq!(
+            state.dns.servers,
+            vec!["192.168.1.1", "1.1.1.1", "2606:4700:4700::1111"]
+        );
+        assert_eq!(state.dns.search_domains, vec!["home.arpa"]);
+
+        let info = state.wifi_info().unwrap();
+        assert!(info.connected);
+        assert_eq!(info.ssid.as_deref(), Some("HomeNet"));
+        assert_eq!(info.channel.as_deref(), Some("ch 36, 5 GHz, 80 MHz"));
+        assert_eq!(info.link_rate_mbps, Some(866.7));
+        assert_eq!(info.signal_dbm, Some(-58));
+        assert_eq!(info.noise_dbm, None);
+
+        assert!(read_device_state(&client, "eth9").is_err());
+    }
+
+    #[test]
+    fn test_strength_to_dbm() {
+        assert_eq!(strength_to_dbm(0), -100);
+        assert_eq!(strength_to_dbm(50), -70);
+        assert_eq!(strength_to_dbm(100), -40);
+        assert_eq!(strength_to_dbm(255), -40);
+    }
+}
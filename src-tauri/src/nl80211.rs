@@ -0,0 +1,846 @@
+use crate::interference::{NearbyNetwork, HIDDEN_SSID};
+use crate::wifi::{format_channel, frequency_to_channel, WifiInfo};
+
+const NLMSG_HDRLEN: usize = 16;
+const GENL_HDRLEN: usize = 4;
+const NLA_HDRLEN: usize = 4;
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_ACK: u16 = 0x4;
+const NLM_F_DUMP: u16 = 0x300;
+
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_CMD_GET_STATION: u8 = 17;
+const NL80211_CMD_GET_SCAN: u8 = 32;
+const NL80211_CMD_GET_SURVEY: u8 = 50;
+
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_IFNAME: u16 = 4;
+const NL80211_ATTR_IFTYPE: u16 = 5;
+const NL80211_ATTR_STA_INFO: u16 = 21;
+const NL80211_ATTR_WIPHY_FREQ: u16 = 38;
+const NL80211_ATTR_BSS: u16 = 47;
+const NL80211_ATTR_SSID: u16 = 52;
+const NL80211_ATTR_SURVEY_INFO: u16 = 84;
+
+const NL80211_IFTYPE_STATION: u32 = 2;
+
+const NL80211_STA_INFO_SIGNAL: u16 = 7;
+const NL80211_STA_INFO_TX_BITRATE: u16 = 8;
+const NL80211_STA_INFO_SIGNAL_AVG: u16 = 13;
+
+const NL80211_RATE_INFO_BITRATE: u16 = 1;
+const NL80211_RATE_INFO_MCS: u16 = 2;
+const NL80211_RATE_INFO_40_MHZ_WIDTH: u16 = 3;
+const NL80211_RATE_INFO_BITRATE32: u16 = 5;
+const NL80211_RATE_INFO_VHT_MCS: u16 = 6;
+const NL80211_RATE_INFO_80_MHZ_WIDTH: u16 = 8;
+const NL80211_RATE_INFO_80P80_MHZ_WIDTH: u16 = 9;
+const NL80211_RATE_INFO_160_MHZ_WIDTH: u16 = 10;
+const NL80211_RATE_INFO_HE_MCS: u16 = 13;
+const NL80211_RATE_INFO_320_MHZ_WIDTH: u16 = 18;
+const NL80211_RATE_INFO_EHT_MCS: u16 = 19;
+
+const NL80211_BSS_BSSID: u16 = 1;
+const NL80211_BSS_FREQUENCY: u16 = 2;
+const NL80211_BSS_INFORMATION_ELEMENTS: u16 = 6;
+const NL80211_BSS_SIGNAL_MBM: u16 = 7;
+const NL80211_BSS_STATUS: u16 = 9;
+const NL80211_BSS_STATUS_ASSOCIATED: u32 = 1;
+
+const NL80211_SURVEY_INFO_FREQUENCY: u16 = 1;
+const NL80211_SURVEY_INFO_NOISE: u16 = 2;
+const NL80211_SURVEY_INFO_IN_USE: u16 = 3;
+
+const WLAN_EID_SSID: u8 = 0;
+const WLAN_EID_HT_OPERATION: u8 = 61;
+const WLAN_EID_VHT_OPERATION: u8 = 192;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Interface {
+    ifindex: u32,
+    name: String,
+    iftype: Option<u32>,
+    ssid: Option<String>,
+    freq_mhz: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Station {
+    signal_dbm: Option<i32>,
+    tx_bitrate_mbps: Option<f64>,
+    phy_generation: Option<&'static str>,
+    width_mhz: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Bss {
+    bssid: Option<String>,
+    ssid: Option<String>,
+    freq_mhz: u32,
+    width_mhz: u32,
+    signal_dbm: Option<i32>,
+    associated: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Survey {
+    freq_mhz: u32,
+    noise_dbm: Option<i32>,
+    in_use: bool,
+}
+
+struct NlMessage<'a> {
+    msg_type: u16,
+    payload: &'a [u8],
+}
+
+type Attrs<'a> = Vec<(u16, &'a [u8])>;
+
+#[cfg(target_os = "linux")]
+pub fn get_wifi_info(ifname: &str) -> Option<WifiInfo> {
+    let mut socket = match socket::GenlSocket::open() {
+        Ok(s) => s,
+        Err(e) => {
+            log::debug!(
+                "nl80211::get_wifi_info: failed to open netlink socket: {}",
+                e
+            );
+            return None;
+        }
+    };
+    let family = resolve_family(&mut socket)?;
+    let iface = find_station_interface(&mut socket, family, ifname)?;
+
+    let ifindex = iface.ifindex.to_ne_bytes();
+    let station = dump(&mut socket, family, NL80211_CMD_GET_STATION, &ifindex)
+        .iter()
+        .find_map(|payload| decode_station(payload));
+    let bss: Vec<Bss> = dump(&mut socket, family, NL80211_CMD_GET_SCAN, &ifindex)
+        .iter()
+        .filter_map(|payload| decode_bss(payload))
+        .collect();
+    let surveys: Vec<Survey> = dump(&mut socket, family, NL80211_CMD_GET_SURVEY, &ifindex)
+        .iter()
+        .filter_map(|payload| decode_survey(payload))
+        .collect();
+
+    let info = build_wifi_info(&iface, station.as_ref(), &bss, &surveys);
+    log::debug!(
+        "nl80211::get_wifi_info: {} signal: {:?}dBm, noise: {:?}dBm, channel: {:?}, rate: {:?}Mbps",
+        iface.name,
+        info.signal_dbm,
+        info.noise_dbm,
+        info.channel,
+        info.link_rate_mbps
+    );
+    Some(info)
+}
+
+#[cfg(target_os = "linux")]
+pub fn scan_nearby_networks(ifname: &str) -> Option<Vec<NearbyNetwork>> {
+    let mut socket = match socket::GenlSocket::open() {
+        Ok(s) => s,
+        Err(e) => {
+            log::debug!(
+                "nl80211::scan_nearby_networks: failed to open netlink socket: {}",
+                e
+            );
+            return None;
+        }
+    };
+    let family = resolve_family(&mut socket)?;
+    let iface = find_station_interface(&mut socket, family, ifname)?;
+
+    let bss: Vec<Bss> = dump(
+        &mut socket,
+        family,
+        NL80211_CMD_GET_SCAN,
+        &iface.ifindex.to_ne_bytes(),
+    )
+    .iter()
+    .filter_map(|payload| decode_bss(payload))
+    .collect();
+
+    let networks = nearby_networks(&bss);
+    log::debug!(
+        "nl80211::scan_nearby_networks: {} cached scan results on {}",
+        networks.len(),
+        iface.name
+    );
+    Some(networks)
+}
+
+#[cfg(target_os = "linux")]
+fn resolve_family(socket: &mut socket::GenlSocket) -> Option<u16> {
+    let request = build_request(
+        GENL_ID_CTRL,
+        NLM_F_REQUEST | NLM_F_ACK,
+        CTRL_CMD_GETFAMILY,
+        &[(CTRL_ATTR_FAMILY_NAME, b"nl80211\0")],
+    );
+    match socket.transact(&request) {
+        Ok(payloads) => payloads
+            .iter()
+            .find_map(|payload| decode_family_id(payload)),
+        Err(e) => {
+            log::debug!("nl80211: failed to resolve family id: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn find_station_interface(
+    socket: &mut socket::GenlSocket,
+    family: u16,
+    ifname: &str,
+) -> Option<Interface> {
+    let request = build_request(
+        family,
+        NLM_F_REQUEST | NLM_F_ACK | NLM_F_DUMP,
+        NL80211_CMD_GET_INTERFACE,
+        &[],
+    );
+    let payloads = match socket.transact(&request) {
+        Ok(p) => p,
+        Err(e) => {
+            log::debug!("nl80211: interface dump failed: {}", e);
+            return None;
+        }
+    };
+
+    let interfaces: Vec<Interface> = payloads
+        .iter()
+        .filter_map(|payload| decode_interface(payload))
+        .collect();
+    select_station_interface(interfaces, ifname)
+}
+
+#[cfg(target_os = "linux")]
+fn dump(socket: &mut socket::GenlSocket, family: u16, cmd: u8, ifindex: &[u8]) -> Vec<Vec<u8>> {
+    let request = build_request(
+        family,
+        NLM_F_REQUEST | NLM_F_ACK | NLM_F_DUMP,
+        cmd,
+        &[(NL80211_ATTR_IFINDEX, ifindex)],
+    );
+    socket.transact(&request).unwrap_or_else(|e| {
+        log::debug!("nl80211: dump of command {} failed: {}", cmd, e);
+        Vec::new()
+    })
+}
+
+fn select_station_interface(interfaces: Vec<Interface>, ifname: &str) -> Option<Interface> {
+    interfaces
+        .into_iter()
+        .find(|i| i.name == ifname && i.iftype.is_none_or(|t| t == NL80211_IFTYPE_STATION))
+}
+
+fn build_wifi_info(
+    iface: &Interface,
+    station: Option<&Station>,
+    bss: &[Bss],
+    surveys: &[Survey],
+) -> WifiInfo {
+    let associated = bss.iter().find(|b| b.associated);
+    if station.is_none() && associated.is_none() {
+        return WifiInfo::default();
+    }
+
+    let freq_mhz = iface.freq_mhz.or(associated.map(|b| b.freq_mhz));
+    let width_mhz = station.map(|s| s.width_mhz).unwrap_or(20);
+
+    WifiInfo {
+        connected: true,
+        ssid: iface
+            .ssid
+            .clone()
+            .or_else(|| associated.and_then(|b| b.ssid.clone())),
+        frequency_band: station
+            .and_then(|s| s.phy_generation)
+            .map(|g| g.to_string()),
+        channel: freq_mhz.and_then(|f| format_channel(f, width_mhz)),
+        link_rate_mbps: station.and_then(|s| s.tx_bitrate_mbps),
+        signal_dbm: station
+            .and_then(|s| s.signal_dbm)
+            .or(associated.and_then(|b| b.signal_dbm)),
+        noise_dbm: surveys
+            .iter()
+            .find(|s| s.in_use || Some(s.freq_mhz) == freq_mhz)
+            .and_then(|s| s.noise_dbm),
+    }
+}
+
+fn nearby_networks(bss: &[Bss]) -> Vec<NearbyNetwork> {
+    bss.iter()
+        .filter(|b| !b.associated)
+        .filter_map(|b| {
+            let (channel, frequency_ghz) = frequency_to_channel(b.freq_mhz)?;
+            Some(NearbyNetwork {
+                ssid: b
+                    .ssid
+                    .clone()
+                    .filter(|s| !s.is_empty())
+                    .unwrap_or_else(|| HIDDEN_SSID.to_string()),
+                bssid: b.bssid.clone(),
+                channel,
+                frequency_ghz,
+                frequency_mhz: Some(b.freq_mhz),
+                width_mhz: Some(b.width_mhz),
+                signal_dbm: b.signal_dbm,
+            })
+        })
+        .collect()
+}
+
+fn build_request(family: u16, flags: u16, cmd: u8, attrs: &[(u16, &[u8])]) -> Vec<u8> {
+    let mut buf = vec![0u8; NLMSG_HDRLEN];
+    buf.extend_from_slice(&[cmd, 1, 0, 0]);
+    for (attr_type, data) in attrs {
+        push_attr(&mut buf, *attr_type, data);
+    }
+
+    let len = buf.len() as u32;
+    buf[0..4].copy_from_slice(&len.to_ne_bytes());
+    buf[4..6].copy_from_slice(&family.to_ne_bytes());
+    buf[6..8].copy_from_slice(&flags.to_ne_bytes());
+    buf
+}
+
+fn push_attr(buf: &mut Vec<u8>, attr_type: u16, data: &[u8]) {
+    let len = (NLA_HDRLEN + data.len()) as u16;
+    buf.extend_from_slice(&len.to_ne_bytes());
+    buf.extend_from_slice(&attr_type.to_ne_bytes());
+    buf.extend_from_slice(data);
+    buf.resize(align(buf.len()), 0);
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn parse_messages(buf: &[u8]) -> Vec<NlMessage<'_>> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+
+    while offset + NLMSG_HDRLEN <= buf.len() {
+        let len = read_u32(&buf[offset..]).unwrap_or(0) as usize;
+        if len < NLMSG_HDRLEN || offset + len > buf.len() {
+            break;
+        }
+        messages.push(NlMessage {
+            msg_type: read_u16(&buf[offset + 4..]).unwrap_or(0),
+            payload: &buf[offset + NLMSG_HDRLEN..offset + len],
+        });
+        offset += align(len);
+    }
+
+    messages
+}
+
+fn parse_attrs(buf: &[u8]) -> Attrs<'_> {
+    let mut attrs = Vec::new();
+    let mut offset = 0;
+
+    while offset + NLA_HDRLEN <= buf.len() {
+        let len = read_u16(&buf[offset..]).unwrap_or(0) as usize;
+        if len < NLA_HDRLEN || offset + len > buf.len() {
+            break;
+        }
+        let attr_type = read_u16(&buf[offset + 2..]).unwrap_or(0) & NLA_TYPE_MASK;
+        attrs.push((attr_type, &buf[offset + NLA_HDRLEN..offset + len]));
+        offset += align(len);
+    }
+
+    attrs
+}
+
+fn genl_attrs(payload: &[u8]) -> Attrs<'_> {
+    payload
+        .get(GENL_HDRLEN..)
+        .map(parse_attrs)
+        .unwrap_or_default()
+}
+
+fn find_attr<'a>(attrs: &Attrs<'a>, attr_type: u16) -> Option<&'a [u8]> {
+    attrs
+        .iter()
+        .find(|(t, _)| *t == attr_type)
+        .map(|(_, data)| *data)
+}
+
+fn has_attr(attrs: &Attrs<'_>, attr_type: u16) -> bool {
+    attrs.iter().any(|(t, _)| *t == attr_type)
+}
+
+fn read_u16(data: &[u8]) -> Option<u16> {
+    Some(u16::from_ne_bytes(data.get(..2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8]) -> Option<u32> {
+    Some(u32::from_ne_bytes(data.get(..4)?.try_into().ok()?))
+}
+
+fn read_i32(data: &[u8]) -> Option<i32> {
+    Some(i32::from_ne_bytes(data.get(..4)?.try_into().ok()?))
+}
+
+fn read_i8(data: &[u8]) -> Option<i32> {
+    data.first().map(|b| *b as i8 as i32)
+}
+
+fn decode_error(payload: &[u8]) -> Option<i32> {
+    read_i32(payload)
+}
+
+fn decode_family_id(payload: &[u8]) -> Option<u16> {
+    let attrs = genl_attrs(payload);
+    find_attr(&attrs, CTRL_ATTR_FAMILY_ID).and_then(read_u16)
+}
+
+fn decode_interface(payload: &[u8]) -> Option<Interface> {
+    let attrs = genl_attrs(payload);
+    Some(Interface {
+        ifindex: find_attr(&attrs, NL80211_ATTR_IFINDEX).and_then(read_u32)?,
+        name: find_attr(&attrs, NL80211_ATTR_IFNAME)
+            .map(|name| decode_string(name.split(|b| *b == 0).next().unwrap_or(name)))
+            .unwrap_or_default(),
+        iftype: find_attr(&attrs, NL80211_ATTR_IFTYPE).and_then(read_u32),
+        ssid: find_attr(&attrs, NL80211_ATTR_SSID).map(decode_string),
+        freq_mhz: find_attr(&attrs, NL80211_ATTR_WIPHY_FREQ).and_then(read_u32),
+    })
+}
+
+fn decode_station(payload: &[u8]) -> Option<Station> {
+    let attrs = genl_attrs(payload);
+    let sta_info = parse_attrs(find_attr(&attrs, NL80211_ATTR_STA_INFO)?);
+
+    let mut station = Station {
+        signal_dbm: find_attr(&sta_info, NL80211_STA_INFO_SIGNAL)
+            .or(find_attr(&sta_info, NL80211_STA_INFO_SIGNAL_AVG))
+            .and_then(read_i8),
+        tx_bitrate_mbps: None,
+        phy_generation: None,
+        width_mhz: 20,
+    };
+
+    if let Some(rate) = find_attr(&sta_info, NL80211_STA_INFO_TX_BITRATE) {
+        let rate = parse_attrs(rate);
+        station.tx_bitrate_mbps = find_attr(&rate, NL80211_RATE_INFO_BITRATE32)
+            .and_then(read_u32)
+            .or(find_attr(&rate, NL80211_RATE_INFO_BITRATE)
+                .and_then(read_u16)
+                .map(u32::from))
+            .map(|rate| rate as f64 / 10.0);
+        station.phy_generation = phy_generation(&rate);
+        station.width_mhz = channel_width(&rate);
+    }
+
+    Some(station)
+}
+
+fn phy_generation(rate: &Attrs<'_>) -> Option<&'static str> {
+    if has_attr(rate, NL80211_RATE_INFO_EHT_MCS) {
+        Some("Wi-Fi 7")
+    } else if has_attr(rate, NL80211_RATE_INFO_HE_MCS) {
+        Some("Wi-Fi 6")
+    } else if has_attr(rate, NL80211_RATE_INFO_VHT_MCS) {
+        Some("Wi-Fi 5")
+    } else if has_attr(rate, NL80211_RATE_INFO_MCS) {
+        Some("Wi-Fi 4")
+    } else {
+        None
+    }
+}
+
+fn channel_width(rate: &Attrs<'_>) -> u32 {
+    if has_attr(rate, NL80211_RATE_INFO_320_MHZ_WIDTH) {
+        320
+    } else if has_attr(rate, NL80211_RATE_INFO_160_MHZ_WIDTH)
+        || has_attr(rate, NL80211_RATE_INFO_80P80_MHZ_WIDTH)
+    {
+        160
+    } else if has_attr(rate, NL80211_RATE_INFO_80_MHZ_WIDTH) {
+        80
+    } else if has_attr(rate, NL80211_RATE_INFO_40_MHZ_WIDTH) {
+        40
+    } else {
+        20
+    }
+}
+
+fn decode_bss(payload: &[u8]) -> Option<Bss> {
+    let attrs = genl_attrs(payload);
+    let bss = parse_attrs(find_attr(&attrs, NL80211_ATTR_BSS)?);
+
+    let ies = find_attr(&bss, NL80211_BSS_INFORMATION_ELEMENTS).unwrap_or_default();
+
+    Some(Bss {
+        bssid: find_attr(&bss, NL80211_BSS_BSSID)
+            .filter(|mac| mac.len() == 6)
+            .map(format_mac),
+        ssid: find_ie(ies, WLAN_EID_SSID).map(decode_string),
+        freq_mhz: find_attr(&bss, NL80211_BSS_FREQUENCY).and_then(read_u32)?,
+        width_mhz: width_from_ies(ies),
+        signal_dbm: find_attr(&bss, NL80211_BSS_SIGNAL_MBM)
+            .and_then(read_i32)
+            .map(|mbm| mbm / 100),
+        associated: find_attr(&bss, NL80211_BSS_STATUS).and_then(read_u32)
+            == Some(NL80211_BSS_STATUS_ASSOCIATED),
+    })
+}
+
+fn find_ie(ies: &[u8], wanted: u8) -> Option<&[u8]> {
+    let mut offset = 0;
+    while offset + 2 <= ies.len() {
+        let id = ies[offset];
+        let len = ies[offset + 1] as usize;
+        let data = ies.get(offset + 2..offset + 2 + len)?;
+        if id == wanted {
+            return Some(data);
+        }
+        offset += 2 + len;
+    }
+    None
+}
+
+fn width_from_ies(ies: &[u8]) -> u32 {
+    if let Some(&[width, seg0, seg1, ..]) = find_ie(ies, WLAN_EID_VHT_OPERATION) {
+        match width {
+            1 if seg1 != 0 && seg0.abs_diff(seg1) >= 8 => return 160,
+            1 => return 80,
+            2 | 3 => return 160,
+            _ => {}
+        }
+    }
+
+    match find_ie(ies, WLAN_EID_HT_OPERATION) {
+        Some(&[_, params, ..]) if params & 0x03 != 0 => 40,
+        _ => 20,
+    }
+}
+
+fn format_mac(mac: &[u8]) -> String {
+    mac.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn decode_survey(payload: &[u8]) -> Option<Survey> {
+    let attrs = genl_attrs(payload);
+    let survey = parse_attrs(find_attr(&attrs, NL80211_ATTR_SURVEY_INFO)?);
+
+    Some(Survey {
+        freq_mhz: find_attr(&survey, NL80211_SURVEY_INFO_FREQUENCY).and_then(read_u32)?,
+        noise_dbm: find_attr(&survey, NL80211_SURVEY_INFO_NOISE).and_then(read_i8),
+        in_use: has_attr(&survey, NL80211_SURVEY_INFO_IN_USE),
+    })
+}
+
+fn decode_string(data: &[u8]) -> String {
+    String::from_utf8_lossy(data).into_owned()
+}
+
+#[cfg(target_os = "linux")]
+mod socket {
+    use std::io;
+    use std::mem;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    use super::{decode_error, parse_messages, NLMSG_DONE, NLMSG_ERROR};
+
+    const RECV_BUFFER_SIZE: usize = 64 * 1024;
+    const RECV_TIMEOUT_SECS: libc::time_t = 2;
+
+    pub struct GenlSocket {
+        fd: OwnedFd,
+        seq: u32,
+    }
+
+    impl GenlSocket {
+        pub fn open() -> io::Result<Self> {
+            let fd = unsafe {
+                libc::socket(
+                    libc::AF_NETLINK,
+                    libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                    libc::NETLINK_GENERIC,
+                )
+            };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+            let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+            addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            let ret = unsafe {
+                libc::bind(
+                    fd.as_raw_fd(),
+                    &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                    mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+                )
+            };
+            if ret < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let timeout = libc::timeval {
+                tv_sec: RECV_TIMEOUT_SECS,
+                tv_usec: 0,
+            };
+            let ret = unsafe {
+                libc::setsockopt(
+                    fd.as_raw_fd(),
+                    libc::SOL_SOCKET,
+                    libc::SO_RCVTIMEO,
+                    &timeout as *const libc::timeval as *const libc::c_void,
+                    mem::size_of::<libc::timeval>() as libc::socklen_t,
+                )
+            };
+            if ret < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self { fd, seq: 0 })
+        }
+
+        pub fn transact(&mut self, request: &[u8]) -> io::Result<Vec<Vec<u8>>> {
+            self.seq = self.seq.wrapping_add(1);
+            let mut request = request.to_vec();
+            request[8..12].copy_from_slice(&self.seq.to_ne_bytes());
+
+            let sent = unsafe {
+                libc::send(
+                    self.fd.as_raw_fd(),
+                    request.as_ptr() as *const libc::c_void,
+                    request.len(),
+                    0,
+                )
+            };
+            if sent < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut payloads = Vec::new();
+            let mut buf = vec![0u8; RECV_BUFFER_SIZE];
+            loop {
+                let received = unsafe {
+                    libc::recv(
+                        self.fd.as_raw_fd(),
+                        buf.as_mut_ptr() as *mut libc::c_void,
+                        buf.len(),
+                        0,
+                    )
+                };
+                if received < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+
+                for message in parse_messages(&buf[..received as usize]) {
+                    match message.msg_type {
+                        NLMSG_DONE => return Ok(payloads),
+                        NLMSG_ERROR => {
+                            return match decode_error(message.payload) {
+                                Some(0) => Ok(payloads),
+                                Some(errno) => Err(io::Error::from_raw_os_error(-errno)),
+                                None => Err(io::Error::other("truncated netlink error")),
+                            };
+                        }
+                        _ => payloads.push(message.payload.to_vec()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attr(attr_type: u16, data: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        push_attr(&mut buf, attr_type, data);
+        buf
+    }
+
+    fn genl_message(msg_type: u16, cmd: u8, attrs: &[Vec<u8>]) -> Vec<u8> {
+        let mut payload = vec![cmd, 1, 0, 0];
+        for a in attrs {
+            payload.extend_from_slice(a);
+        }
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&((NLMSG_HDRLEN + payload.len()) as u32).to_ne_bytes());
+        buf.extend_from_slice(&msg_type.to_ne_bytes());
+        buf.extend_from_slice(&0x2u16.to_ne_bytes());
+        buf.extend_from_slice(&[0u8; 8]);
+        buf.extend_from_slice(&payload);
+        buf
+    }
+
+    #[test]
+    fn test_build_request() {
+        let request = build_request(
+            GENL_ID_CTRL,
+            NLM_F_REQUEST | NLM_F_ACK,
+            CTRL_CMD_GETFAMILY,
+            &[(CTRL_ATTR_FAMILY_NAME, b"nl80211\0")],
+        );
+        assert_eq!(
+            request,
+            vec![
+                0x20, 0x00, 0x00, 0x00, 0x10, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00, 0x03, 0x01, 0x00, 0x00, 0x0c, 0x00, 0x02, 0x00, b'n', b'l', b'8', b'0',
+                b'2', b'1', b'1', 0x00,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decode_family_id() {
+        let buf: &[u8] = &[
+            0x28, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x01, 0x02, 0x00, 0x00, 0x0c, 0x00, 0x02, 0x00, b'n', b'l', b'8', b'0',
+            b'2', b'1', b'1', 0x00, 0x06, 0x00, 0x01, 0x00, 0x1c, 0x00, 0x00, 0x00,
+        ];
+        let messages = parse_messages(buf);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].msg_type, GENL_ID_CTRL);
+        assert_eq!(decode_family_id(messages[0].payload), Some(0x1c));
+    }
+
+    #[test]
+    fn test_decode_error() {
+        let buf: &[u8] = &[
+            0x24, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xed, 0xff, 0xff, 0xff, 0x14, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x05, 0x03,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let messages = parse_messages(buf);
+        assert_eq!(messages[0].msg_type, NLMSG_ERROR);
+        assert_eq!(decode_error(messages[0].payload), Some(-19));
+    }
+
+    #[test]
+    fn test_decode_station_and_build_wifi_info() {
+        let iface = genl_message(
+            0x1c,
+            NL80211_CMD_GET_INTERFACE,
+            &[
+                attr(NL80211_ATTR_IFINDEX, &3u32.to_ne_bytes()),
+                attr(NL80211_ATTR_IFNAME, b"wlp2s0\0"),
+                attr(NL80211_ATTR_IFTYPE, &NL80211_IFTYPE_STATION.to_ne_bytes()),
+                attr(NL80211_ATTR_WIPHY_FREQ, &5745u32.to_ne_bytes()),
+                attr(NL80211_ATTR_SSID, b"MyNetwork"),
+            ],
+        );
+
+        let mut rate = Vec::new();
+        rate.extend(attr(NL80211_RATE_INFO_BITRATE32, &9607u32.to_ne_bytes()));
+        rate.extend(attr(NL80211_RATE_INFO_HE_MCS, &[9]));
+        rate.extend(attr(NL80211_RATE_INFO_80_MHZ_WIDTH, &[]));
+        let mut sta_info = Vec::new();
+        sta_info.extend(attr(NL80211_STA_INFO_SIGNAL, &[(-58i8) as u8]));
+        sta_info.extend(attr(NL80211_STA_INFO_TX_BITRATE, &rate));
+        let station = genl_message(
+            0x1c,
+            NL80211_CMD_GET_STATION,
+            &[attr(NL80211_ATTR_STA_INFO, &sta_info)],
+        );
+
+        let mut survey_info = Vec::new();
+        survey_info.extend(attr(NL80211_SURVEY_INFO_FREQUENCY, &5745u32.to_ne_bytes()));
+        survey_info.extend(attr(NL80211_SURVEY_INFO_NOISE, &[(-92i8) as u8]));
+        survey_info.extend(attr(NL80211_SURVEY_INFO_IN_USE, &[]));
+        let survey = genl_message(
+            0x1c,
+            NL80211_CMD_GET_SURVEY,
+            &[attr(NL80211_ATTR_SURVEY_INFO, &survey_info)],
+        );
+
+        let iface = decode_interface(parse_messages(&iface)[0].payload).unwrap();
+        let station = decode_station(parse_messages(&station)[0].payload).unwrap();
+        let survey = decode_survey(parse_messages(&survey)[0].payload).unwrap();
+
+        assert_eq!(iface.name, "wlp2s0");
+        assert_eq!(station.width_mhz, 80);
+        assert_eq!(station.phy_generation, Some("Wi-Fi 6"));
+
+        let info = build_wifi_info(&iface, Some(&station), &[], &[survey]);
+        assert!(info.connected);
+        assert_eq!(info.ssid, Some("MyNetwork".to_string()));
+        assert_eq!(info.frequency_band, Some("Wi-Fi 6".to_string()));
+        assert_eq!(info.channel, Some("ch 149, 5 GHz, 80 MHz".to_string()));
+        assert_eq!(info.signal_dbm, Some(-58));
+        assert_eq!(info.noise_dbm, Some(-92));
+        assert_eq!(info.link_rate_mbps, Some(960.7));
+    }
+
+    #[test]
+    fn test_decode_bss_and_nearby_networks() {
+        let bss_message = |ies: &[u8], freq: u32, signal_mbm: i32, status: Option<u32>| {
+            let mut bss = Vec::new();
+            bss.extend(attr(
+                NL80211_BSS_BSSID,
+                &[0x8c, 0x3b, 0xad, 0, 0, freq as u8],
+            ));
+            bss.extend(attr(NL80211_BSS_FREQUENCY, &freq.to_ne_bytes()));
+            bss.extend(attr(NL80211_BSS_INFORMATION_ELEMENTS, ies));
+            bss.extend(attr(NL80211_BSS_SIGNAL_MBM, &signal_mbm.to_ne_bytes()));
+            if let Some(status) = status {
+                bss.extend(attr(NL80211_BSS_STATUS, &status.to_ne_bytes()));
+            }
+            genl_message(0x1c, NL80211_CMD_GET_SCAN, &[attr(NL80211_ATTR_BSS, &bss)])
+        };
+
+        let mut buf = Vec::new();
+        buf.extend(bss_message(
+            &[0, 6, b'H', b'o', b'm', b'e', b'N', b't', 1, 1, 0x8c],
+            2437,
+            -4700,
+            Some(NL80211_BSS_STATUS_ASSOCIATED),
+        ));
+        buf.extend(bss_message(
+            &[
+                0, 9, b'N', b'e', b'i', b'g', b'h', b'b', b'o', b'r', b'1', 61, 3, 1, 0x05, 0,
+            ],
+            2412,
+            -7100,
+            None,
+        ));
+        buf.extend(bss_message(&[0, 0, 192, 3, 1, 42, 0], 5180, -8000, None));
+
+        let bss: Vec<Bss> = parse_messages(&buf)
+            .iter()
+            .filter_map(|m| decode_bss(m.payload))
+            .collect();
+        assert_eq!(bss.len(), 3);
+        assert!(bss[0].associated);
+        assert_eq!(bss[0].ssid, Some("HomeNt".to_string()));
+        assert_eq!(bss[1].signal_dbm, Some(-71));
+
+        let networks = nearby_networks(&bss);
+        assert_eq!(networks.len(), 2);
+        assert_eq!(networks[0].ssid, "Neighbor1");
+        assert_eq!(networks[0].channel, 1);
+        assert_eq!(networks[0].frequency_ghz, 2.4);
+        assert_eq!(networks[0].bssid, Some("8c:3b:ad:00:00:6c".to_string()));
+        assert_eq!(networks[0].signal_dbm, Some(-71));
+        assert_eq!(networks[0].width_mhz, Some(40));
+        assert_eq!(networks[1].ssid, HIDDEN_SSID);
+        assert_eq!(networks[1].channel, 36);
+        assert_eq!(networks[1].frequency_ghz, 5.0);
+        assert_eq!(networks[1].frequency_mhz, Some(5180));
+        assert_eq!(networks[1].width_mhz, Some(80));
+    }
+}
@@ -0,0 +1,283 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::io;
+#[cfg(unix)]
+use std::time::Duration;
+
+#[cfg(unix)]
+use crate::icmp::{self, SizeProbe};
+use crate::interfaces::NetworkInterface;
+use crate::network::get_router_ip;
+use crate::probes::InternetTarget;
+use crate::runner::CommandRunner;
+
+static IFCONFIG_MTU_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bmtu (\d+)").unwrap());
+
+#[cfg(unix)]
+const DEFAULT_MTU: u32 = 1500;
+// Every IPv4 link must carry 576-byte packets and every IPv6 link 1280.
+#[cfg(unix)]
+const MIN_IPV4_MTU: u32 = 576;
+#[cfg(unix)]
+const MIN_IPV6_MTU: u32 = 1280;
+#[cfg(unix)]
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+#[cfg(unix)]
+const PROBE_ATTEMPTS: u32 = 2;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PathMtu {
+    pub host: String,
+    pub interface_mtu: Option<u32>,
+    pub path_mtu: Option<u32>,
+    // The next-hop MTU a router sent back in a fragmentation-needed or
+    // packet-too-big error.
+    pub reported_mtu: Option<u32>,
+    // Oversized packets vanished without any error coming back.
+    pub black_hole: bool,
+    pub mismatch: bool,
+}
+
+#[cfg(unix)]
+#[derive(Debug, Default, PartialEq)]
+struct MtuSearch {
+    path_mtu: Option<u32>,
+    reported_mtu: Option<u32>,
+    black_hole: bool,
+}
+
+// Measures the path MTU to the router and to each internet target.
+pub fn probe_path_mtus(
+    runner: &dyn CommandRunner,
+    interface: Option<&NetworkInterface>,
+    targets: &[InternetTarget],
+) -> Vec<PathMtu> {
+    let mut hosts: Vec<String> = get_router_ip(runner, interface).into_iter().collect();
+    for target in targets {
+        if !hosts.contains(&target.host) {
+            hosts.push(target.host.clone());
+        }
+    }
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = hosts
+            .iter()
+            .map(|host| scope.spawn(move || discover_path_mtu(runner, host, interface)))
+            .collect();
+        handles.into_iter().filter_map(|h| h.join().ok()).collect()
+    })
+}
+
+pub fn discover_path_mtu(
+    runner: &dyn CommandRunner,
+    host: &str,
+    interface: Option<&NetworkInterface>,
+) -> PathMtu {
+    let interface_mtu = interface.and_then(|i| read_interface_mtu(runner, &i.name));
+    let mut result = PathMtu {
+        host: host.to_string(),
+        interface_mtu,
+        ..Default::default()
+    };
+
+    if !runner.native_probes() {
+        log::debug!(
+            "discover_path_mtu: size probes need live sockets, skipping {}",
+            host
+        );
+        return result;
+    }
+
+    #[cfg(unix)]
+    {
+        let min = if host.contains(':') {
+            MIN_IPV6_MTU
+        } else {
+            MIN_IPV4_MTU
+        };
+        let max = interface_mtu.unwrap_or(DEFAULT_MTU).max(min);
+        let name = interface.map(|i| i.name.as_str());
+        match search_mtu(min, max, |size| probe_size(host, size, name)) {
+            Ok(search) => {
+                result.path_mtu = search.path_mtu;
+                result.reported_mtu = search.reported_mtu;
+                result.black_hole = search.black_hole;
+            }
+            Err(e) => log::debug!("discover_path_mtu: probing {} failed: {}", host, e),
+        }
+    }
+
+    result.mismatch = matches!(
+        (result.path_mtu, interface_mtu),
+        (Some(path), Some(iface)) if path < iface
+    );
+    log::debug!(
+        "discover_path_mtu: {} - path: {:?}, interface: {:?}, reported: {:?}, black hole: {}",
+        host,
+        result.path_mtu,
+        result.interface_mtu,
+        result.reported_mtu,
+        result.black_hole
+    );
+    result
+}
+
+// A silent drop could just be loss, so it is retried before it counts.
+#[cfg(unix)]
+fn probe_size(host: &str, size: u32, interface: Option<&str>) -> io::Result<SizeProbe> {
+    let mut outcome = SizeProbe::NoReply;
+    for _ in 0..PROBE_ATTEMPTS {
+        outcome = icmp::probe_size(host, size, PROBE_TIMEOUT, interface)?;
+        if outcome != SizeProbe::NoReply {
+            break;
+        }
+    }
+    Ok(outcome)
+}
+
+// Binary search for the largest packet that gets a reply, between the size
+// every link must carry and the interface MTU. Finds nothing when even the
+// smallest probe goes unanswered.
+#[cfg(unix)]
+fn search_mtu(
+    min: u32,
+    max: u32,
+    mut probe: impl FnMut(u32) -> io::Result<SizeProbe>,
+) -> io::Result<MtuSearch> {
+    let mut search = MtuSearch::default();
+    if probe(min)? != SizeProbe::Fits {
+        return Ok(search);
+    }
+
+    let (mut fits, mut too_big) = (min, max + 1);
+    let mut next = max;
+    while too_big - fits > 1 {
+        match probe(next)? {
+            SizeProbe::Fits => {
+                fits = next;
+                // Anything above the reported MTU fails at that router.
+                if search.reported_mtu == Some(next) {
+                    break;
+                }
+            }
+            SizeProbe::TooBig(reported) => {
+                too_big = next;
+                search.reported_mtu = reported.or(search.reported_mtu);
+            }
+            SizeProbe::NoReply => {
+                too_big = next;
+                search.black_hole = true;
+            }
+        }
+        // A router's report is usually exact, so try it before bisecting.
+        next = match search.reported_mtu {
+            Some(mtu) if mtu > fits && mtu < too_big => mtu,
+            _ => fits + (too_big - fits) / 2,
+        };
+    }
+
+    search.path_mtu = Some(fits);
+    Ok(search)
+}
+
+fn read_interface_mtu(runner: &dyn CommandRunner, name: &str) -> Option<u32> {
+    if cfg!(target_os = "linux") {
+        let path = format!("/sys/class/net/{}/mtu", name);
+        runner.read_file(&path).ok()?.trim().parse().ok()
+    } else {
+        let output = runner.run("ifconfig", &[name]).ok()?;
+        IFCONFIG_MTU_RE.captures(&output.stdout)?[1].parse().ok()
+    }
+}
+
+pub fn mtu_suggestion(result: &PathMtu) -> Option<String> {
+    let (Some(path), Some(interface)) = (result.path_mtu, result.interface_mtu) else {
+        return None;
+    };
+    if !result.mismatch {
+        return None;
+    }
+
+    let mut suggestion = format!(
+        "Packets over {} bytes do not reach {} although your interface sends up to {}. \
+         This is typical of PPPoE or VPN links; setting the MTU to {} on your router or device avoids stalls",
+        path, result.host, interface, path
+    );
+    if result.black_hole {
+        suggestion.push_str(
+            ". No error comes back for larger packets, so connections hang instead of adapting",
+        );
+    }
+    Some(suggestion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn simulate(path_mtu: u32, too_big: impl Fn(u32) -> SizeProbe) -> (MtuSearch, Vec<u32>) {
+        let mut probed = Vec::new();
+        let search = search_mtu(MIN_IPV4_MTU, 1500, |size| {
+            probed.push(size);
+            Ok(if size <= path_mtu {
+                SizeProbe::Fits
+            } else {
+                too_big(size)
+            })
+        })
+        .unwrap();
+        (search, probed)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_search_mtu() {
+        let (search, probed) = simulate(1500, |_| SizeProbe::NoReply);
+        assert_eq!(search.path_mtu, Some(1500));
+        assert!(!search.black_hole);
+        assert_eq!(probed, vec![576, 1500]);
+
+        // PPPoE with a router reporting its MTU is found without bisecting.
+        let (search, probed) = simulate(1492, |_| SizeProbe::TooBig(Some(1492)));
+        assert_eq!(search.path_mtu, Some(1492));
+        assert_eq!(search.reported_mtu, Some(1492));
+        assert_eq!(probed, vec![576, 1500, 1492]);
+
+        let (search, _) = simulate(1420, |_| SizeProbe::NoReply);
+        assert_eq!(search.path_mtu, Some(1420));
+        assert_eq!(search.reported_mtu, None);
+        assert!(search.black_hole);
+
+        let (search, _) = simulate(1400, |_| SizeProbe::TooBig(None));
+        assert_eq!(search.path_mtu, Some(1400));
+        assert!(!search.black_hole);
+
+        let search = search_mtu(MIN_IPV4_MTU, 1500, |_| Ok(SizeProbe::NoReply)).unwrap();
+        assert_eq!(search, MtuSearch::default());
+    }
+
+    #[test]
+    fn test_mtu_suggestion() {
+        let mut result = PathMtu {
+            host: "1.1.1.1".to_string(),
+            interface_mtu: Some(1500),
+            path_mtu: Some(1500),
+            ..Default::default()
+        };
+        assert_eq!(mtu_suggestion(&result), None);
+
+        result.path_mtu = Some(1492);
+        result.mismatch = true;
+        let suggestion = mtu_suggestion(&result).unwrap();
+        assert!(suggestion.starts_with("Packets over 1492 bytes do not reach 1.1.1.1"));
+        assert!(!suggestion.contains("No error comes back"));
+
+        result.black_hole = true;
+        assert!(mtu_suggestion(&result)
+            .unwrap()
+            .contains("No error comes back"));
+    }
+}
@@ -0,0 +1,253 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rustls::RootCertStore;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use url::Url;
+
+use crate::http;
+use crate::runner::CommandRunner;
+
+// Answers every request with an empty 204, so anything else came from
+// something in between.
+pub const DEFAULT_CHECK_URL: &str = "http://connectivitycheck.gstatic.com/generate_204";
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+static META_REFRESH_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)<meta[^>]+http-equiv=["']?refresh["']?[^>]+url=([^"'>\s]+)"#).unwrap()
+});
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortalState {
+    Open,
+    Portal,
+    // The check URL could not be reached at all.
+    Blocked,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortalSignal {
+    Redirect,
+    ContentSubstitution,
+    // The check host resolved to a local address, as when the network
+    // answers every name with its own sign-in page.
+    DnsInterception,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CaptivePortal {
+    pub check_url: String,
+    pub state: PortalState,
+    pub signal: Option<PortalSignal>,
+    // Where to sign in, when the network gives it away.
+    pub portal_url: Option<String>,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+pub fn detect(runner: &dyn CommandRunner, url: &str) -> CaptivePortal {
+    if !runner.native_probes() {
+        log::debug!("portal::detect: the check needs live sockets, skipping");
+        return CaptivePortal {
+            check_url: url.to_string(),
+            ..Default::default()
+        };
+    }
+    detect_with_roots(url, CHECK_TIMEOUT, http::default_roots())
+}
+
+fn detect_with_roots(url: &str, timeout: Duration, roots: RootCertStore) -> CaptivePortal {
+    let mut result = CaptivePortal {
+        check_url: url.to_string(),
+        ..Default::default()
+    };
+
+    let addr = match http::resolve_url(url) {
+        Ok(addr) => addr,
+        Err(e) => {
+            log::debug!("portal::detect: cannot resolve {}: {}", url, e);
+            result.state = PortalState::Blocked;
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    let intercepted = is_local(&addr.ip());
+
+    let reply = match http::connect(url, timeout, roots).and_then(|client| client.get()) {
+        Ok(reply) => reply,
+        Err(e) if intercepted => {
+            log::debug!(
+                "portal::detect: {} resolved to local {} and failed: {}",
+                url,
+                addr,
+                e
+            );
+            result.state = PortalState::Portal;
+            result.signal = Some(PortalSignal::DnsInterception);
+            result.portal_url = Some(local_portal_url(&addr));
+            result.error = Some(e.to_string());
+            return result;
+        }
+        Err(e) => {
+            log::debug!("portal::detect: {} failed: {}", url, e);
+            result.state = PortalState::Blocked;
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    result.status = Some(reply.status);
+
+    if reply.status == 204 {
+        result.state = PortalState::Open;
+    } else if let Some(location) = reply
+        .location
+        .filter(|_| (300..400).contains(&reply.status))
+    {
+        result.state = PortalState::Portal;
+        result.signal = Some(PortalSignal::Redirect);
+        result.portal_url = Some(absolute_url(url, &location));
+    } else {
+        result.state = PortalState::Portal;
+        result.signal = Some(if intercepted {
+            PortalSignal::DnsInterception
+        } else {
+            PortalSignal::ContentSubstitution
+        });
+        let body = String::from_utf8_lossy(&reply.body);
+        result.portal_url = Some(
+            META_REFRESH_RE
+                .captures(&body)
+                .map(|caps| absolute_url(url, &caps[1]))
+                .unwrap_or_else(|| url.to_string()),
+        );
+    }
+
+    log::debug!(
+        "portal::detect: {} - {:?} ({:?}, status {}), portal at {:?}",
+        url,
+        result.state,
+        result.signal,
+        reply.status,
+        result.portal_url
+    );
+    result
+}
+
+// Addresses no public name should resolve to. Loopback is left out, since a
+// check URL on this machine is a deliberate choice.
+fn is_local(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_link_local(),
+        IpAddr::V6(ip) => ip.is_unique_local() || ip.is_unicast_link_local(),
+    }
+}
+
+fn local_portal_url(addr: &SocketAddr) -> String {
+    match addr.ip() {
+        IpAddr::V4(ip) => format!("http://{}/", ip),
+        IpAddr::V6(ip) => format!("http://[{}]/", ip),
+    }
+}
+
+fn absolute_url(base: &str, location: &str) -> String {
+    Url::parse(base)
+        .and_then(|base| base.join(location))
+        .map(String::from)
+        .unwrap_or_else(|_| location.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn serve_once(response: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf);
+            let _ = stream.write_all(response);
+        });
+        format!("http://127.0.0.1:{}/generate_204", port)
+    }
+
+    fn detect_local(url: &str) -> CaptivePortal {
+        detect_with_roots(url, Duration::from_secs(2), RootCertStore::empty())
+    }
+
+    #[test]
+    fn test_detect_open() {
+        let url = serve_once(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n");
+        let result = detect_local(&url);
+        assert_eq!(result.state, PortalState::Open);
+        assert_eq!(result.signal, None);
+        assert_eq!(result.status, Some(204));
+        assert_eq!(result.portal_url, None);
+    }
+
+    #[test]
+    fn test_detect_redirect() {
+        let url = serve_once(b"HTTP/1.1 302 Found\r\nLocation: /login?next=1\r\n\r\n");
+        let result = detect_local(&url);
+        assert_eq!(result.state, PortalState::Portal);
+        assert_eq!(result.signal, Some(PortalSignal::Redirect));
+        assert_eq!(result.status, Some(302));
+        assert_eq!(
+            result.portal_url,
+            Some(url.replace("/generate_204", "/login?next=1"))
+        );
+    }
+
+    #[test]
+    fn test_detect_content_substitution() {
+        let url = serve_once(
+            b"HTTP/1.1 200 OK\r\n\r\n<html><head><meta http-equiv=\"refresh\" content=\"0; url=http://10.0.0.1/portal\"></head></html>",
+        );
+        let result = detect_local(&url);
+        assert_eq!(result.state, PortalState::Portal);
+        assert_eq!(result.signal, Some(PortalSignal::ContentSubstitution));
+        assert_eq!(result.portal_url.as_deref(), Some("http://10.0.0.1/portal"));
+
+        let url = serve_once(b"HTTP/1.1 511 Network Authentication Required\r\n\r\nSign in");
+        let result = detect_local(&url);
+        assert_eq!(result.state, PortalState::Portal);
+        assert_eq!(result.portal_url, Some(url));
+    }
+
+    #[test]
+    fn test_detect_blocked() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://127.0.0.1:{}/generate_204",
+            listener.local_addr().unwrap().port()
+        );
+        drop(listener);
+        let result = detect_local(&url);
+        assert_eq!(result.state, PortalState::Blocked);
+        assert!(result.error.is_some());
+        assert_eq!(result.status, None);
+    }
+
+    #[test]
+    fn test_is_local() {
+        assert!(is_local(&"192.168.1.1".parse().unwrap()));
+        assert!(is_local(&"10.0.0.1".parse().unwrap()));
+        assert!(is_local(&"fd00::1".parse().unwrap()));
+        assert!(!is_local(&"127.0.0.1".parse().unwrap()));
+        assert!(!is_local(&"142.250.74.3".parse().unwrap()));
+        assert!(!is_local(&"2001:4860:4860::8888".parse().unwrap()));
+        assert_eq!(
+            local_portal_url(&"[fd00::1]:80".parse().unwrap()),
+            "http://[fd00::1]/"
+        );
+    }
+}
@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+
+use crate::interfaces::NetworkInterface;
+use crate::network::{ping_host, tcp_ping, PingResult};
+use crate::runner::CommandRunner;
+
+const PROBE_COUNT: u32 = 3;
+const DEFAULT_TCP_PORT: u16 = 443;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProbeProtocol {
+    Icmp,
+    Tcp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InternetTarget {
+    pub label: String,
+    pub host: String,
+    pub protocol: ProbeProtocol,
+    // Used for TCP probes, including the fallback when ICMP gets no replies.
+    #[serde(default)]
+    pub port: Option<u16>,
+    // Where to reach the same service over IPv6 when `host` is an IPv4
+    // address. Names are looked up in both families instead.
+    #[serde(default)]
+    pub ipv6_host: Option<String>,
+}
+
+impl InternetTarget {
+    fn icmp(label: &str, host: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            host: host.to_string(),
+            protocol: ProbeProtocol::Icmp,
+            port: None,
+            ipv6_host: None,
+        }
+    }
+
+    fn with_ipv6_host(mut self, host: &str) -> Self {
+        self.ipv6_host = Some(host.to_string());
+        self
+    }
+
+    fn tcp_port(&self) -> u16 {
+        self.port.unwrap_or(DEFAULT_TCP_PORT)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetResult {
+    pub target: InternetTarget,
+    // The protocol that produced `ping`, which differs from the target's when
+    // an ICMP probe fell back to TCP.
+    pub protocol: ProbeProtocol,
+    pub ping: PingResult,
+}
+
+impl TargetResult {
+    fn reachable(&self) -> bool {
+        self.ping.latency_ms.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InternetSummary {
+    pub total_targets: u32,
+    pub reachable_targets: u32,
+    pub best_target: Option<String>,
+    pub median_latency_ms: Option<f64>,
+    pub packet_loss_percent: Option<f64>,
+}
+
+pub fn default_internet_targets() -> Vec<InternetTarget> {
+    vec![
+        InternetTarget::icmp("Cloudflare", "1.1.1.1").with_ipv6_host("2606:4700:4700::1111"),
+        InternetTarget::icmp("Google", "8.8.8.8").with_ipv6_host("2001:4860:4860::8888"),
+        InternetTarget::icmp("Quad9", "9.9.9.9").with_ipv6_host("2620:fe::fe"),
+    ]
+}
+
+pub fn probe_target(
+    runner: &dyn CommandRunner,
+    target: &InternetTarget,
+    interface: Option<&NetworkInterface>,
+) -> TargetResult {
+    let mut protocol = target.protocol;
+    let ping = match target.protocol {
+        ProbeProtocol::Icmp => {
+            let ping = ping_host(runner, &target.host, PROBE_COUNT, interface);
+            // TCP probes open real sockets, so only the live runner may fall back.
+            if ping.latency_ms.is_none() && runner.native_probes() {
+                log::debug!(
+                    "probe_target: no ICMP replies from {}, falling back to TCP port {}",
+                    target.host,
+                    target.tcp_port()
+                );
+                protocol = ProbeProtocol::Tcp;
+                tcp_ping(&target.host, target.tcp_port(), PROBE_COUNT, interface)
+            } else {
+                ping
+            }
+        }
+        ProbeProtocol::Tcp if runner.native_probes() => {
+            tcp_ping(&target.host, target.tcp_port(), PROBE_COUNT, interface)
+        }
+        ProbeProtocol::Tcp => {
+            log::debug!(
+                "probe_target: TCP probes need live sockets, skipping {}",
+                target.host
+            );
+            PingResult::default()
+        }
+    };
+    TargetResult {
+        target: target.clone(),
+        protocol,
+        ping,
+    }
+}
+
+// The reachable target with the least loss, then the lowest latency. This is
+// what the single "internet" reading shows, so one blocked provider does not
+// make the whole connection look down.
+pub fn best_result(results: &[TargetResult]) -> Option<&TargetResult> {
+    results.iter().filter(|r| r.reachable()).min_by(|a, b| {
+        let key = |r: &TargetResult| {
+            (
+                r.ping.packet_loss_percent.unwrap_or(0.0),
+                r.ping.latency_ms.unwrap_or(f64::MAX),
+            )
+        };
+        let (a, b) = (key(a), key(b));
+        a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1))
+    })
+}
+
+pub fn summarize_targets(results: &[TargetResult]) -> InternetSummary {
+    let mut latencies: Vec<f64> = results.iter().filter_map(|r| r.ping.latency_ms).collect();
+    latencies.sort_by(|a, b| a.total_cmp(b));
+    let median_latency_ms = match latencies.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(latencies[n / 2]),
+        n => Some((latencies[n / 2 - 1] + latencies[n / 2]) / 2.0),
+    };
+
+    let (sent, received) = results
+        .iter()
+        .filter_map(|r| Some((r.ping.sent?, r.ping.received?)))
+        .fold((0, 0), |(s, r), (sent, received)| (s + sent, r + received));
+    let packet_loss_percent =
+        (sent > 0).then(|| sent.saturating_sub(received) as f64 / sent as f64 * 100.0);
+
+    InternetSummary {
+        total_targets: results.len() as u32,
+        reachable_targets: results.iter().filter(|r| r.reachable()).count() as u32,
+        best_target: best_result(results).map(|r| r.target.label.clone()),
+        median_latency_ms,
+        packet_loss_percent,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(label: &str, latency_ms: Option<f64>, loss: f64, sent: u32) -> TargetResult {
+        let received = ((1.0 - loss / 100.0) * sent as f64).round() as u32;
+        TargetResult {
+            target: InternetTarget::icmp(label, label),
+            protocol: ProbeProtocol::Icmp,
+            ping: PingResult {
+                latency_ms,
+                packet_loss_percent: Some(loss),
+                sent: Some(sent),
+                received: Some(received),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_best_result_prefers_lossless_then_fastest() {
+        let results = vec![
+            result("lossy", Some(5.0), 25.0, 4),
+            result("slow", Some(30.0), 0.0, 4),
+            result("fast", Some(12.0), 0.0, 4),
+            result("blocked", None, 100.0, 4),
+        ];
+        assert_eq!(best_result(&results).unwrap().target.label, "fast");
+
+        let results = vec![result("blocked", None, 100.0, 4)];
+        assert!(best_result(&results).is_none());
+    }
+
+    #[test]
+    fn test_summarize_targets() {
+        let results = vec![
+            result("a", Some(10.0), 0.0, 4),
+            result("b", Some(20.0), 25.0, 4),
+            result("c", Some(40.0), 0.0, 4),
+            result("d", None, 100.0, 4),
+        ];
+        let summary = summarize_targets(&results);
+        assert_eq!(summary.total_targets, 4);
+        assert_eq!(summary.reachable_targets, 3);
+        assert_eq!(summary.best_target.as_deref(), Some("a"));
+        assert_eq!(summary.median_latency_ms, Some(20.0));
+        assert_eq!(summary.packet_loss_percent, Some(31.25));
+
+        let summary = summarize_targets(&results[..2]);
+        assert_eq!(summary.median_latency_ms, Some(15.0));
+
+        assert_eq!(summarize_targets(&[]), InternetSummary::default());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_probe_target_tcp() {
+        use crate::runner::{CommandOutput, CommandRunner};
+        use std::io;
+        use std::net::TcpListener;
+
+        // A ping binary that never gets a reply, as on a network that filters ICMP.
+        struct IcmpFiltered {
+            native: bool,
+        }
+
+        impl CommandRunner for IcmpFiltered {
+            fn run(&self, _program: &str, _args: &[&str]) -> io::Result<CommandOutput> {
+                Ok(CommandOutput {
+                    success: false,
+                    stdout: "3 packets transmitted, 0 received, 100% packet loss".to_string(),
+                })
+            }
+
+            fn read_file(&self, path: &str) -> io::Result<String> {
+                Err(io::Error::new(io::ErrorKind::NotFound, path.to_string()))
+            }
+
+            fn native_probes(&self) -> bool {
+                self.native
+            }
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let target = InternetTarget {
+            protocol: ProbeProtocol::Tcp,
+            port: Some(port),
+            ..InternetTarget::icmp("Local", "127.0.0.1")
+        };
+        let result = probe_target(&IcmpFiltered { native: true }, &target, None);
+        assert_eq!(result.protocol, ProbeProtocol::Tcp);
+        assert_eq!(result.ping.sent, Some(PROBE_COUNT));
+        assert_eq!(result.ping.received, Some(PROBE_COUNT));
+        assert_eq!(result.ping.samples_ms.len(), PROBE_COUNT as usize);
+
+        // Replayed runs never open sockets, so they neither probe TCP nor fall back.
+        let result = probe_target(&IcmpFiltered { native: false }, &target, None);
+        assert_eq!(result.ping.latency_ms, None);
+
+        let target = InternetTarget {
+            port: Some(port),
+            ..InternetTarget::icmp("Local", "127.0.0.1")
+        };
+        let result = probe_target(&IcmpFiltered { native: false }, &target, None);
+        assert_eq!(result.protocol, ProbeProtocol::Icmp);
+        assert_eq!(result.ping.packet_loss_percent, Some(100.0));
+        assert_eq!(result.ping.latency_ms, None);
+    }
+}
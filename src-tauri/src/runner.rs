@@ -0,0 +1,220 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+const RECORD_DIR_ENV: &str = "YFI_RECORD_DIR";
+const REPLAY_DIR_ENV: &str = "YFI_REPLAY_DIR";
+
+pub type SharedRunner = Arc<dyn CommandRunner>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandOutput {
+    pub success: bool,
+    pub stdout: String,
+}
+
+pub trait CommandRunner: Send + Sync {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput>;
+
+    fn read_file(&self, path: &str) -> io::Result<String>;
+
+    // Native probes (netlink, raw sockets) bypass the runner entirely, so only
+    // the live runner enables them. Recording and replay stick to commands.
+    fn native_probes(&self) -> bool {
+        false
+    }
+}
+
+pub fn from_env() -> SharedRunner {
+    if let Some(dir) = std::env::var_os(REPLAY_DIR_ENV) {
+        log::info!("Replaying probe fixtures from {:?}", dir);
+        return Arc::new(ReplayRunner::new(dir));
+    }
+
+    if let Some(dir) = std::env::var_os(RECORD_DIR_ENV) {
+        log::info!("Recording probe fixtures to {:?}", dir);
+        return Arc::new(RecordingRunner::new(SystemRunner, dir));
+    }
+
+    Arc::new(SystemRunner)
+}
+
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = Command::new(program).args(args).output()?;
+        Ok(CommandOutput {
+            success: output.status.success(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        })
+    }
+
+    fn read_file(&self, path: &str) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn native_probes(&self) -> bool {
+        true
+    }
+}
+
+pub struct RecordingRunner<R> {
+    inner: R,
+    dir: PathBuf,
+}
+
+impl<R: CommandRunner> RecordingRunner<R> {
+    pub fn new(inner: R, dir: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            dir: dir.into(),
+        }
+    }
+
+    fn record(&self, key: &str, contents: &str, success: bool) {
+        if let Err(e) = write_fixture(&self.dir, key, contents, success) {
+            log::error!("RecordingRunner: failed to record fixture {}: {}", key, e);
+        }
+    }
+}
+
+impl<R: CommandRunner> CommandRunner for RecordingRunner<R> {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = self.inner.run(program, args)?;
+        self.record(&command_key(program, args), &output.stdout, output.success);
+        Ok(output)
+    }
+
+    fn read_file(&self, path: &str) -> io::Result<String> {
+        let contents = self.inner.read_file(path)?;
+        self.record(&file_key(path), &contents, true);
+        Ok(contents)
+    }
+}
+
+pub struct ReplayRunner {
+    dir: PathBuf,
+}
+
+impl ReplayRunner {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn replay(&self, key: &str) -> io::Result<CommandOutput> {
+        let path = self.dir.join(format!("{}.txt", key));
+        let stdout = fs::read_to_string(&path).map_err(|e| {
+            log::debug!("ReplayRunner: no fixture at {:?}", path);
+            io::Error::new(e.kind(), format!("no fixture for {}", key))
+        })?;
+        let success = !self.dir.join(format!("{}.failed", key)).exists();
+        Ok(CommandOutput { success, stdout })
+    }
+}
+
+impl CommandRunner for ReplayRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        self.replay(&command_key(program, args))
+    }
+
+    fn read_file(&self, path: &str) -> io::Result<String> {
+        self.replay(&file_key(path)).map(|output| output.stdout)
+    }
+}
+
+fn write_fixture(dir: &Path, key: &str, contents: &str, success: bool) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(format!("{}.txt", key)), contents)?;
+    let failed_marker = dir.join(format!("{}.failed", key));
+    if success {
+        if failed_marker.exists() {
+            fs::remove_file(failed_marker)?;
+        }
+    } else {
+        fs::write(failed_marker, "")?;
+    }
+    Ok(())
+}
+
+fn command_key(program: &str, args: &[&str]) -> String {
+    let mut parts = vec![program];
+    parts.extend_from_slice(args);
+    sanitize_key(&parts.join(" "))
+}
+
+fn file_key(path: &str) -> String {
+    sanitize_key(&format!("file {}", path))
+}
+
+fn sanitize_key(raw: &str) -> String {
+    raw.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixture_keys() {
+        assert_eq!(
+            command_key("iw", &["dev", "wlp2s0", "link"]),
+            "iw_dev_wlp2s0_link"
+        );
+        assert_eq!(
+            command_key("ping", &["-c", "3", "-W", "2", "1.1.1.1"]),
+            "ping_-c_3_-W_2_1.1.1.1"
+        );
+        assert_eq!(file_key("/proc/net/route"), "file__proc_net_route");
+    }
+
+    #[test]
+    fn test_record_then_replay() {
+        struct FakeRunner;
+
+        impl CommandRunner for FakeRunner {
+            fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+                Ok(CommandOutput {
+                    success: program != "false",
+                    stdout: format!("{} {}", program, args.join(" ")),
+                })
+            }
+
+            fn read_file(&self, path: &str) -> io::Result<String> {
+                Ok(format!("contents of {}", path))
+            }
+        }
+
+        let dir = std::env::temp_dir().join(format!("yfi-runner-test-{}", std::process::id()));
+        let recorder = RecordingRunner::new(FakeRunner, &dir);
+        recorder.run("echo", &["hello"]).unwrap();
+        recorder.run("false", &[]).unwrap();
+        recorder.read_file("/etc/resolv.conf").unwrap();
+
+        let replay = ReplayRunner::new(&dir);
+        let output = replay.run("echo", &["hello"]).unwrap();
+        assert!(output.success);
+        assert_eq!(output.stdout, "echo hello");
+        assert!(!replay.run("false", &[]).unwrap().success);
+        assert_eq!(
+            replay.read_file("/etc/resolv.conf").unwrap(),
+            "contents of /etc/resolv.conf"
+        );
+        assert_eq!(
+            replay.run("dig", &[]).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,327 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::http;
+use crate::network::summarize_samples;
+
+// Cloudflare's speed test backend. Self-hosted servers need the same
+// `GET /__down?bytes=N` and `POST /__up` endpoints.
+pub const DEFAULT_SERVER: &str = "https://speed.cloudflare.com";
+const LATENCY_TIMEOUT: Duration = Duration::from_secs(5);
+const TRANSFER_TIMEOUT: Duration = Duration::from_secs(5);
+// Per request; streams start a new request whenever one finishes.
+const TRANSFER_BYTES: u64 = 25_000_000;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone)]
+pub struct SpeedTestConfig {
+    pub server: String,
+    pub latency_samples: u32,
+    pub streams: usize,
+    pub phase_duration: Duration,
+}
+
+impl Default for SpeedTestConfig {
+    fn default() -> Self {
+        Self {
+            server: DEFAULT_SERVER.to_string(),
+            latency_samples: 20,
+            streams: 4,
+            phase_duration: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpeedTestPhase {
+    Latency,
+    Download,
+    Upload,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeedTestProgress {
+    pub phase: SpeedTestPhase,
+    // Share of the current phase that has run, from 0 to 1.
+    pub fraction: f64,
+    pub mbps: Option<f64>,
+    pub latency_ms: Option<f64>,
+}
+
+// Mirrors the frontend's SpeedTestResults, hence the camelCase names.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeedTestResult {
+    pub server: String,
+    pub download_bandwidth: f64,
+    pub upload_bandwidth: f64,
+    pub latency: f64,
+    pub jitter: f64,
+}
+
+pub fn run(
+    config: &SpeedTestConfig,
+    mut on_progress: impl FnMut(&SpeedTestProgress),
+) -> io::Result<SpeedTestResult> {
+    let server = config.server.trim_end_matches('/');
+    let (latency, jitter) = measure_latency(server, config.latency_samples, &mut on_progress)?;
+
+    let download = throughput_phase(
+        config,
+        SpeedTestPhase::Download,
+        download_transfer(server),
+        &mut on_progress,
+    )
+    .ok_or_else(|| io::Error::other(format!("no data downloaded from {}", server)))?;
+    let upload = throughput_phase(
+        config,
+        SpeedTestPhase::Upload,
+        upload_transfer(server),
+        &mut on_progress,
+    )
+    .ok_or_else(|| io::Error::other(format!("no data uploaded to {}", server)))?;
+
+    log::debug!(
+        "speedtest::run: {} - download: {:.1}Mbps, upload: {:.1}Mbps, latency: {:.1}ms, jitter: {:.1}ms",
+        server,
+        download,
+        upload,
+        latency,
+        jitter
+    );
+    Ok(SpeedTestResult {
+        server: server.to_string(),
+        download_bandwidth: download,
+        upload_bandwidth: upload,
+        latency,
+        jitter,
+    })
+}
+
+// Median and jitter of the time to first byte of empty downloads.
+fn measure_latency(
+    server: &str,
+    count: u32,
+    on_progress: &mut impl FnMut(&SpeedTestProgress),
+) -> io::Result<(f64, f64)> {
+    let url = format!("{}/__down?bytes=0", server);
+    let mut samples = Vec::new();
+    for i in 1..=count {
+        let timing = http::measure(&url, LATENCY_TIMEOUT);
+        match timing.first_byte_ms {
+            Some(ms) if timing.error.is_none() => samples.push(ms),
+            _ => log::debug!(
+                "speedtest::measure_latency: {} failed: {:?}",
+                url,
+                timing.error
+            ),
+        }
+        on_progress(&SpeedTestProgress {
+            phase: SpeedTestPhase::Latency,
+            fraction: i as f64 / count as f64,
+            mbps: None,
+            latency_ms: samples.last().copied(),
+        });
+    }
+
+    let result = summarize_samples(count, samples);
+    match (result.median_ms, result.jitter_ms) {
+        (Some(median), jitter) => Ok((median, jitter.unwrap_or(0.0))),
+        (None, _) => Err(io::Error::other(format!("{} did not answer", server))),
+    }
+}
+
+fn throughput_phase(
+    config: &SpeedTestConfig,
+    phase: SpeedTestPhase,
+    transfer: impl Fn(&AtomicBool, &AtomicU64) -> io::Result<()> + Sync,
+    on_progress: &mut impl FnMut(&SpeedTestProgress),
+) -> Option<f64> {
+    let duration = config.phase_duration;
+    let warmup = duration / 5;
+    let ((), mbps) = with_load(config.streams, warmup, transfer, |bytes| {
+        let start = Instant::now();
+        let start_bytes = bytes.load(Ordering::Relaxed);
+        while start.elapsed() + warmup < duration {
+            let remaining = (duration - warmup).saturating_sub(start.elapsed());
+            thread::sleep(PROGRESS_INTERVAL.min(remaining));
+            on_progress(&SpeedTestProgress {
+                phase,
+                fraction: ((start.elapsed() + warmup).as_secs_f64() / duration.as_secs_f64())
+                    .min(1.0),
+                mbps: megabits_per_second(
+                    bytes.load(Ordering::Relaxed) - start_bytes,
+                    start.elapsed(),
+                ),
+                latency_ms: None,
+            });
+        }
+    });
+    mbps
+}
+
+pub(crate) fn download_transfer(
+    server: &str,
+) -> impl Fn(&AtomicBool, &AtomicU64) -> io::Result<()> + Sync {
+    let url = format!("{}/__down?bytes={}", server, TRANSFER_BYTES);
+    move |stop, received| http::download(&url, TRANSFER_TIMEOUT, stop, received)
+}
+
+pub(crate) fn upload_transfer(
+    server: &str,
+) -> impl Fn(&AtomicBool, &AtomicU64) -> io::Result<()> + Sync {
+    let url = format!("{}/__up", server);
+    move |stop, sent| http::upload(&url, TRANSFER_BYTES, TRANSFER_TIMEOUT, stop, sent)
+}
+
+// Runs `transfer` on every stream, restarting it whenever it finishes, while
+// `during` runs on this thread with the running byte count. Queues take a
+// moment to fill, so `during` starts after the warmup; the throughput returned
+// covers only the time it ran.
+pub(crate) fn with_load<T>(
+    streams: usize,
+    warmup: Duration,
+    transfer: impl Fn(&AtomicBool, &AtomicU64) -> io::Result<()> + Sync,
+    during: impl FnOnce(&AtomicU64) -> T,
+) -> (T, Option<f64>) {
+    let stop = AtomicBool::new(false);
+    let bytes = AtomicU64::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..streams {
+            scope.spawn(|| {
+                while !stop.load(Ordering::Relaxed) {
+                    if let Err(e) = transfer(&stop, &bytes) {
+                        log::debug!("speedtest::with_load: transfer failed: {}", e);
+                        break;
+                    }
+                }
+            });
+        }
+
+        thread::sleep(warmup);
+        let start = Instant::now();
+        let start_bytes = bytes.load(Ordering::Relaxed);
+        let value = during(&bytes);
+        let mbps =
+            megabits_per_second(bytes.load(Ordering::Relaxed) - start_bytes, start.elapsed());
+        stop.store(true, Ordering::Relaxed);
+        (value, mbps)
+    })
+}
+
+fn megabits_per_second(bytes: u64, elapsed: Duration) -> Option<f64> {
+    (bytes > 0 && !elapsed.is_zero())
+        .then(|| bytes as f64 * 8.0 / elapsed.as_secs_f64() / 1_000_000.0)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+
+    // A bare-bones `__down`/`__up` server. Connections that hang up without
+    // sending a request, like latency probes, are ignored.
+    pub(crate) fn serve_speed_endpoint() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                thread::spawn(move || handle(stream));
+            }
+        });
+        port
+    }
+
+    fn handle(mut stream: TcpStream) {
+        let mut head = Vec::new();
+        let mut buf = [0u8; 16 * 1024];
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+            match stream.read(&mut buf) {
+                Ok(0) | Err(_) => return,
+                Ok(n) => head.extend_from_slice(&buf[..n]),
+            }
+        }
+
+        let head = String::from_utf8_lossy(&head);
+        if let Some(query) = head.strip_prefix("GET /__down?bytes=") {
+            let size: usize = query
+                .split(|c: char| !c.is_ascii_digit())
+                .next()
+                .and_then(|n| n.parse().ok())
+                .unwrap_or(0)
+                .min(1_000_000);
+            let header = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", size);
+            let _ = stream.write_all(header.as_bytes());
+            let _ = stream.write_all(&vec![0u8; size]);
+        } else if head.starts_with("POST /__up") {
+            while let Ok(n) = stream.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+            }
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+        }
+    }
+
+    #[test]
+    fn test_run_local_server() {
+        let port = serve_speed_endpoint();
+        let config = SpeedTestConfig {
+            server: format!("http://127.0.0.1:{}/", port),
+            latency_samples: 5,
+            streams: 2,
+            phase_duration: Duration::from_millis(600),
+        };
+
+        let mut updates = Vec::new();
+        let result = run(&config, |progress| updates.push(progress.clone())).unwrap();
+
+        assert_eq!(result.server, format!("http://127.0.0.1:{}", port));
+        assert!(result.download_bandwidth > 0.0);
+        assert!(result.upload_bandwidth > 0.0);
+        assert!(result.latency > 0.0);
+        assert!(result.jitter >= 0.0);
+
+        let latency: Vec<_> = updates
+            .iter()
+            .filter(|u| u.phase == SpeedTestPhase::Latency)
+            .collect();
+        assert_eq!(latency.len(), 5);
+        assert_eq!(latency[4].fraction, 1.0);
+        assert!(latency.iter().all(|u| u.latency_ms.is_some()));
+        let phases: Vec<_> = updates.iter().map(|u| u.phase).collect();
+        let download = phases
+            .iter()
+            .position(|p| *p == SpeedTestPhase::Download)
+            .unwrap();
+        let upload = phases
+            .iter()
+            .position(|p| *p == SpeedTestPhase::Upload)
+            .unwrap();
+        assert!(download < upload);
+        assert!(updates.iter().all(|u| (0.0..=1.0).contains(&u.fraction)));
+    }
+
+    #[test]
+    fn test_run_unreachable_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let config = SpeedTestConfig {
+            server: format!("http://127.0.0.1:{}", port),
+            latency_samples: 3,
+            ..Default::default()
+        };
+        let mut updates = 0;
+        let error = run(&config, |_| updates += 1).unwrap_err();
+        assert!(error.to_string().contains("did not answer"));
+        assert_eq!(updates, 3);
+    }
+}
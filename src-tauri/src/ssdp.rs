@@ -0,0 +1,82 @@
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+pub const SSDP_ADDR: SocketAddr =
+    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(239, 255, 255, 250), 1900));
+
+// Devices wait a random time of up to this many seconds before answering.
+const MX_SECS: u32 = 1;
+
+pub fn build_search(target: SocketAddr) -> Vec<u8> {
+    format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: {}\r\nST: ssdp:all\r\n\r\n",
+        target, MX_SECS
+    )
+    .into_bytes()
+}
+
+// The device or service type a search response advertises, such as
+// "MediaRenderer" for `urn:schemas-upnp-org:device:MediaRenderer:1`. Root
+// device and UUID responses say nothing about what the device does, so they
+// count as an answer without a type.
+pub fn parse_response(data: &[u8]) -> Option<Option<String>> {
+    let text = String::from_utf8_lossy(data);
+    let mut lines = text.lines();
+    let status = lines.next()?;
+    if !status.starts_with("HTTP/1.1 200") && !status.starts_with("HTTP/1.0 200") {
+        return None;
+    }
+
+    let search_target = lines.find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("st")
+            .then(|| value.trim().to_string())
+    })?;
+    Some(type_label(&search_target))
+}
+
+fn type_label(search_target: &str) -> Option<String> {
+    let mut parts = search_target.strip_prefix("urn:")?.split(':');
+    let (_domain, kind, name) = (parts.next()?, parts.next()?, parts.next()?);
+    if !matches!(kind, "device" | "service") || name.is_empty() {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_search() {
+        let request = String::from_utf8(build_search(SSDP_ADDR)).unwrap();
+        assert!(request.starts_with("M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\n"));
+        assert!(request.contains("\r\nMAN: \"ssdp:discover\"\r\n"));
+        assert!(request.contains("\r\nST: ssdp:all\r\n"));
+        assert!(request.ends_with("\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let response = b"HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\nLOCATION: http://192.168.1.52:1400/xml/device_description.xml\r\nSERVER: Linux UPnP/1.0 Sonos/70.3\r\nst: urn:schemas-upnp-org:device:ZonePlayer:1\r\nUSN: uuid:RINCON_48A6B8017EC301400::urn:schemas-upnp-org:device:ZonePlayer:1\r\n\r\n";
+        assert_eq!(
+            parse_response(response),
+            Some(Some("ZonePlayer".to_string()))
+        );
+
+        let response = b"HTTP/1.1 200 OK\r\nST: urn:dial-multiscreen-org:service:dial:1\r\n\r\n";
+        assert_eq!(parse_response(response), Some(Some("dial".to_string())));
+
+        let response = b"HTTP/1.1 200 OK\r\nST: upnp:rootdevice\r\n\r\n";
+        assert_eq!(parse_response(response), Some(None));
+
+        // Other searchers' requests and announcements are not answers.
+        assert_eq!(parse_response(&build_search(SSDP_ADDR)), None);
+        assert_eq!(
+            parse_response(b"NOTIFY * HTTP/1.1\r\nNT: upnp:rootdevice\r\n\r\n"),
+            None
+        );
+        assert_eq!(parse_response(b"HTTP/1.1 200 OK\r\n\r\n"), None);
+    }
+}
@@ -0,0 +1,182 @@
+use std::io;
+use std::mem;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::os::fd::{AsRawFd, OwnedFd};
+use std::time::{Duration, Instant};
+
+use crate::icmp::{bind_to_interface, open_socket, sockaddr_from};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectReport {
+    pub sent: u32,
+    pub rtts_ms: Vec<f64>,
+}
+
+// Times `count` TCP handshakes against host:port. Each completed handshake is
+// one sample; refused or timed-out attempts count as lost.
+pub fn connect_times(
+    host: &str,
+    port: u16,
+    count: u32,
+    interval: Duration,
+    timeout: Duration,
+    interface: Option<&str>,
+) -> io::Result<ConnectReport> {
+    let addr = (host, port).to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("no address for {}", host))
+    })?;
+
+    let mut report = ConnectReport::default();
+    for attempt in 0..count {
+        if attempt > 0 {
+            std::thread::sleep(interval);
+        }
+        report.sent += 1;
+        match connect_once(&addr, timeout, interface) {
+            Ok(elapsed) => report.rtts_ms.push(elapsed.as_secs_f64() * 1000.0),
+            Err(e) => log::debug!("tcp::connect_times: connect to {} failed: {}", addr, e),
+        }
+    }
+
+    log::debug!(
+        "tcp::connect_times: {} - sent {}, connected {}",
+        addr,
+        report.sent,
+        report.rtts_ms.len()
+    );
+    Ok(report)
+}
+
+fn connect_once(
+    addr: &SocketAddr,
+    timeout: Duration,
+    interface: Option<&str>,
+) -> io::Result<Duration> {
+    let domain = if addr.is_ipv6() {
+        libc::AF_INET6
+    } else {
+        libc::AF_INET
+    };
+    let fd = open_socket(domain, libc::SOCK_STREAM, 0)?;
+    if let Some(interface) = interface {
+        bind_to_interface(fd.as_raw_fd(), interface, addr.is_ipv6())?;
+    }
+    set_nonblocking(&fd)?;
+
+    let (storage, len) = sockaddr_from(addr);
+    let start = Instant::now();
+    let ret = unsafe {
+        libc::connect(
+            fd.as_raw_fd(),
+            &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+            len,
+        )
+    };
+    if ret < 0 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EINPROGRESS) {
+            return Err(err);
+        }
+    }
+
+    let deadline = start + timeout;
+    loop {
+        let mut pollfd = libc::pollfd {
+            fd: fd.as_raw_fd(),
+            events: libc::POLLOUT,
+            revents: 0,
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let timeout_ms = remaining.as_millis().min(i32::MAX as u128) as libc::c_int;
+        let ready = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+        if ready > 0 {
+            break;
+        }
+        if ready == 0 {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "connect timed out"));
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+    let elapsed = start.elapsed();
+
+    let mut error: libc::c_int = 0;
+    let mut error_len = mem::size_of::<libc::c_int>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_ERROR,
+            &mut error as *mut libc::c_int as *mut libc::c_void,
+            &mut error_len,
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if error != 0 {
+        return Err(io::Error::from_raw_os_error(error));
+    }
+    Ok(elapsed)
+}
+
+fn set_nonblocking(fd: &OwnedFd) -> io::Result<()> {
+    let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFL) };
+    if flags < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_connect_times_local_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let report = connect_times(
+            "127.0.0.1",
+            port,
+            3,
+            Duration::from_millis(10),
+            Duration::from_secs(1),
+            None,
+        )
+        .unwrap();
+        assert_eq!(report.sent, 3);
+        assert_eq!(report.rtts_ms.len(), 3);
+        assert!(report
+            .rtts_ms
+            .iter()
+            .all(|rtt| *rtt >= 0.0 && *rtt < 1000.0));
+    }
+
+    #[test]
+    fn test_connect_times_closed_port() {
+        let port = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+
+        let report = connect_times(
+            "127.0.0.1",
+            port,
+            2,
+            Duration::from_millis(10),
+            Duration::from_secs(1),
+            None,
+        )
+        .unwrap();
+        assert_eq!(report.sent, 2);
+        assert!(report.rtts_ms.is_empty());
+    }
+}
@@ -0,0 +1,411 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use std::io;
+#[cfg(unix)]
+use std::net::{IpAddr, SocketAddr};
+#[cfg(unix)]
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use crate::icmp;
+use crate::interfaces::NetworkInterface;
+use crate::network::{summarize_samples, PingResult};
+use crate::runner::CommandRunner;
+
+static TRACEROUTE_HEADER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^traceroute6? to \S+ \(([0-9A-Fa-f.:]+)\)").unwrap());
+static TRACEROUTE_HOP_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*(\d+)\s+(.*)$").unwrap());
+static TRACEROUTE_PROBE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(\S+) \(([0-9A-Fa-f.:]+)\)|(\*)|([\d.]+) ms").unwrap());
+
+const MAX_HOPS: u8 = 30;
+const FALLBACK_QUERIES: u32 = 3;
+#[cfg(unix)]
+const HOP_TIMEOUT: Duration = Duration::from_secs(2);
+#[cfg(unix)]
+const ROUND_INTERVAL: Duration = Duration::from_secs(1);
+#[cfg(unix)]
+const NAME_BUFFER_SIZE: usize = 1025;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceHop {
+    pub ttl: u8,
+    pub address: Option<String>,
+    pub hostname: Option<String>,
+    pub ping: PingResult,
+}
+
+// The state of a trace after `round` of `rounds` cycles. Every update carries
+// the full hop list so the UI can simply replace what it shows.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TraceUpdate {
+    pub host: String,
+    pub destination: Option<String>,
+    pub round: u32,
+    pub rounds: u32,
+    pub hops: Vec<TraceHop>,
+    pub complete: bool,
+}
+
+pub fn trace_route(
+    runner: &dyn CommandRunner,
+    host: &str,
+    rounds: u32,
+    interface: Option<&NetworkInterface>,
+    mut on_update: impl FnMut(&TraceUpdate),
+) -> TraceUpdate {
+    let interface = interface.map(|i| i.name.as_str());
+
+    #[cfg(unix)]
+    if runner.native_probes() {
+        match trace_native(host, rounds, interface, &mut on_update) {
+            Ok(update) => {
+                log_trace(&update);
+                return update;
+            }
+            Err(e) => log::debug!(
+                "trace_route: in-process trace to {} failed, falling back to traceroute: {}",
+                host,
+                e
+            ),
+        }
+    }
+
+    let update = trace_command(runner, host, interface);
+    log_trace(&update);
+    on_update(&update);
+    update
+}
+
+fn log_trace(update: &TraceUpdate) {
+    log::debug!(
+        "trace_route: {} - {} hops after {} rounds, destination: {:?}",
+        update.host,
+        update.hops.len(),
+        update.round,
+        update.destination
+    );
+}
+
+#[cfg(unix)]
+fn trace_native(
+    host: &str,
+    rounds: u32,
+    interface: Option<&str>,
+    on_update: &mut impl FnMut(&TraceUpdate),
+) -> io::Result<TraceUpdate> {
+    let mut table = HopTable::default();
+    let mut update = TraceUpdate {
+        host: host.to_string(),
+        rounds,
+        ..Default::default()
+    };
+
+    for round in 1..=rounds {
+        let started = Instant::now();
+        let max_hops = table.destination.map_or(MAX_HOPS, |(ttl, _)| ttl);
+        let replies = match icmp::trace_round(host, max_hops, HOP_TIMEOUT, interface) {
+            Ok(replies) => replies,
+            Err(e) if round == 1 => return Err(e),
+            Err(e) => {
+                log::debug!("trace_route: round {} to {} failed: {}", round, host, e);
+                break;
+            }
+        };
+        table.record_round(max_hops, &replies);
+        table.resolve_names();
+
+        update.round = round;
+        update.destination = table.destination.map(|(_, address)| address.to_string());
+        update.hops = table.hops();
+        update.complete = round == rounds;
+        on_update(&update);
+
+        if !update.complete {
+            std::thread::sleep(ROUND_INTERVAL.saturating_sub(started.elapsed()));
+        }
+    }
+
+    if !update.complete {
+        update.complete = true;
+        on_update(&update);
+    }
+    Ok(update)
+}
+
+#[cfg(unix)]
+#[derive(Debug, Default)]
+struct HopStats {
+    sent: u32,
+    samples: Vec<f64>,
+    address: Option<IpAddr>,
+    hostname: Option<String>,
+    looked_up: bool,
+}
+
+// Per-TTL counters across rounds, indexed by TTL - 1.
+#[cfg(unix)]
+#[derive(Debug, Default)]
+struct HopTable {
+    hops: Vec<HopStats>,
+    destination: Option<(u8, IpAddr)>,
+}
+
+#[cfg(unix)]
+impl HopTable {
+    fn record_round(&mut self, probed: u8, replies: &[icmp::HopReply]) {
+        if self.hops.len() < probed as usize {
+            self.hops.resize_with(probed as usize, Default::default);
+        }
+        for hop in &mut self.hops[..probed as usize] {
+            hop.sent += 1;
+        }
+
+        for reply in replies {
+            let Some(hop) = self.hops.get_mut(reply.ttl as usize - 1) else {
+                continue;
+            };
+            hop.samples.push(reply.rtt_ms);
+            hop.address.get_or_insert(reply.address);
+
+            let closer = match self.destination {
+                Some((ttl, _)) => reply.ttl < ttl,
+                None => true,
+            };
+            if reply.terminal && closer {
+                self.destination = Some((reply.ttl, reply.address));
+            }
+        }
+
+        // Probes past the destination are answered by the destination itself.
+        if let Some((ttl, _)) = self.destination {
+            self.hops.truncate(ttl as usize);
+        }
+    }
+
+    fn resolve_names(&mut self) {
+        for hop in &mut self.hops {
+            if let (Some(address), false) = (hop.address, hop.looked_up) {
+                hop.hostname = reverse_name(address);
+                hop.looked_up = true;
+            }
+        }
+    }
+
+    // Hops that have never answered are dropped from the end, since without a
+    // reply from the destination there is no telling how far the path goes.
+    fn hops(&self) -> Vec<TraceHop> {
+        let answered = self
+            .hops
+            .iter()
+            .rposition(|h| h.address.is_some())
+            .map_or(0, |i| i + 1);
+        self.hops[..answered]
+            .iter()
+            .enumerate()
+            .map(|(i, hop)| TraceHop {
+                ttl: i as u8 + 1,
+                address: hop.address.map(|a| a.to_string()),
+                hostname: hop.hostname.clone(),
+                ping: summarize_samples(hop.sent, hop.samples.clone()),
+            })
+            .collect()
+    }
+}
+
+#[cfg(unix)]
+fn reverse_name(address: IpAddr) -> Option<String> {
+    let (storage, len) = icmp::sockaddr_from(&SocketAddr::new(address, 0));
+    let mut name = [0 as libc::c_char; NAME_BUFFER_SIZE];
+    let ret = unsafe {
+        libc::getnameinfo(
+            &storage as *const libc::sockaddr_storage as *const libc::sockaddr,
+            len,
+            name.as_mut_ptr(),
+            name.len() as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+    if ret != 0 {
+        return None;
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+fn trace_command(runner: &dyn CommandRunner, host: &str, interface: Option<&str>) -> TraceUpdate {
+    let queries = FALLBACK_QUERIES.to_string();
+    let max_hops = MAX_HOPS.to_string();
+    let mut args = vec!["-q", &queries, "-w", "2", "-m", &max_hops];
+    if let Some(interface) = interface {
+        args.extend(["-i", interface]);
+    }
+    args.push(host);
+
+    let mut update = match runner.run("traceroute", &args) {
+        Ok(output) => parse_traceroute_output(host, &output.stdout),
+        Err(e) => {
+            log::error!("trace_route: failed to run traceroute to {}: {}", host, e);
+            TraceUpdate {
+                host: host.to_string(),
+                ..Default::default()
+            }
+        }
+    };
+    update.round = FALLBACK_QUERIES;
+    update.rounds = FALLBACK_QUERIES;
+    update.complete = true;
+    update
+}
+
+fn parse_traceroute_output(host: &str, output: &str) -> TraceUpdate {
+    // macOS prints the header on stderr, so fall back to the host we were given.
+    let target = TRACEROUTE_HEADER_RE
+        .captures(output)
+        .map(|caps| caps[1].to_string())
+        .unwrap_or_else(|| host.to_string());
+
+    let mut hops: Vec<TraceHop> = Vec::new();
+    for line in output.lines() {
+        let Some(caps) = TRACEROUTE_HOP_RE.captures(line) else {
+            continue;
+        };
+        let Ok(ttl) = caps[1].parse::<u8>() else {
+            continue;
+        };
+
+        let mut address = None;
+        let mut hostname = None;
+        let mut sent = 0;
+        let mut samples = Vec::new();
+        for probe in TRACEROUTE_PROBE_RE.captures_iter(&caps[2]) {
+            if let (Some(name), Some(ip)) = (probe.get(1), probe.get(2)) {
+                if address.is_none() {
+                    address = Some(ip.as_str().to_string());
+                    hostname = (name.as_str() != ip.as_str()).then(|| name.as_str().to_string());
+                }
+            } else if probe.get(3).is_some() {
+                sent += 1;
+            } else if let Some(rtt) = probe.get(4).and_then(|m| m.as_str().parse().ok()) {
+                sent += 1;
+                samples.push(rtt);
+            }
+        }
+
+        hops.push(TraceHop {
+            ttl,
+            address,
+            hostname,
+            ping: summarize_samples(sent, samples),
+        });
+    }
+
+    while hops.last().is_some_and(|h| h.address.is_none()) {
+        hops.pop();
+    }
+    let destination = hops
+        .last()
+        .and_then(|h| h.address.clone())
+        .filter(|address| *address == target);
+
+    TraceUpdate {
+        host: host.to_string(),
+        destination,
+        hops,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACEROUTE_OUTPUT: &str = "traceroute to 1.1.1.1 (1.1.1.1), 30 hops max, 60 byte packets
+ 1  router.lan (192.168.1.1)  2.113 ms  2.037 ms  1.998 ms
+ 2  * * *
+ 3  100.64.0.1 (100.64.0.1)  9.843 ms  10.201 ms *
+ 4  be-21.cr01.isp.net (68.86.1.2)  11.514 ms 72.14.0.9 (72.14.0.9)  12.012 ms  11.873 ms
+ 5  one.one.one.one (1.1.1.1)  14.201 ms  14.552 ms  14.380 ms
+";
+
+    #[test]
+    fn test_parse_traceroute_output() {
+        let update = parse_traceroute_output("1.1.1.1", TRACEROUTE_OUTPUT);
+        assert_eq!(update.hops.len(), 5);
+        assert_eq!(update.destination.as_deref(), Some("1.1.1.1"));
+
+        let router = &update.hops[0];
+        assert_eq!(router.ttl, 1);
+        assert_eq!(router.address.as_deref(), Some("192.168.1.1"));
+        assert_eq!(router.hostname.as_deref(), Some("router.lan"));
+        assert_eq!(router.ping.samples_ms, vec![2.113, 2.037, 1.998]);
+        assert_eq!(router.ping.packet_loss_percent, Some(0.0));
+
+        let silent = &update.hops[1];
+        assert_eq!(silent.address, None);
+        assert_eq!(silent.ping.sent, Some(3));
+        assert_eq!(silent.ping.packet_loss_percent, Some(100.0));
+
+        let lossy = &update.hops[2];
+        assert_eq!(lossy.hostname, None);
+        assert_eq!(lossy.ping.received, Some(2));
+
+        // Load-balanced hops report the first responder.
+        let balanced = &update.hops[3];
+        assert_eq!(balanced.address.as_deref(), Some("68.86.1.2"));
+        assert_eq!(balanced.ping.received, Some(3));
+    }
+
+    #[test]
+    fn test_parse_traceroute_output_unreached() {
+        let output = "traceroute to 9.9.9.9 (9.9.9.9), 30 hops max, 60 byte packets
+ 1  192.168.1.1 (192.168.1.1)  2.113 ms  2.037 ms  1.998 ms
+ 2  * * *
+ 3  * * *
+";
+        let update = parse_traceroute_output("9.9.9.9", output);
+        assert_eq!(update.hops.len(), 1);
+        assert_eq!(update.destination, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hop_table_rounds() {
+        let reply = |ttl: u8, last: u8, rtt_ms: f64, terminal: bool| icmp::HopReply {
+            ttl,
+            address: IpAddr::from([10, 0, 0, last]),
+            rtt_ms,
+            terminal,
+        };
+
+        let mut table = HopTable::default();
+        table.record_round(
+            MAX_HOPS,
+            &[
+                reply(1, 1, 2.0, false),
+                reply(3, 3, 12.0, true),
+                reply(4, 3, 12.5, true),
+            ],
+        );
+        assert_eq!(table.destination, Some((3, IpAddr::from([10, 0, 0, 3]))));
+        assert_eq!(table.hops.len(), 3);
+
+        table.record_round(3, &[reply(1, 1, 4.0, false), reply(2, 2, 8.0, false)]);
+        let hops = table.hops();
+        assert_eq!(hops.len(), 3);
+        assert_eq!(hops[0].ping.samples_ms, vec![2.0, 4.0]);
+        assert_eq!(hops[1].address.as_deref(), Some("10.0.0.2"));
+        assert_eq!(hops[1].ping.packet_loss_percent, Some(50.0));
+        assert_eq!(hops[2].ping.sent, Some(2));
+        assert_eq!(hops[2].ping.received, Some(1));
+
+        let mut table = HopTable::default();
+        table.record_round(MAX_HOPS, &[reply(1, 1, 2.0, false)]);
+        assert_eq!(table.hops().len(), 1);
+        assert_eq!(table.destination, None);
+    }
+}
@@ -1,7 +1,9 @@
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::process::Command;
+
+use crate::interfaces::NetworkInterface;
+use crate::runner::CommandRunner;
 
 static CURRENT_SSID_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"Current Wi-Fi Network:\s*(.+)").unwrap());
@@ -15,6 +17,18 @@ static TX_RATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"Transmit Rate:\s*([\d
 static SIGNAL_NOISE_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"Signal / Noise:\s*(-?\d+)\s*dBm\s*/\s*(-?\d+)\s*dBm").unwrap());
 
+static IW_SSID_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^\s*SSID:\s*(.+)$").unwrap());
+static IW_FREQ_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^\s*freq:\s*(\d+)").unwrap());
+static IW_SIGNAL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*signal(?: avg)?:\s*(-?\d+)").unwrap());
+static IW_TX_BITRATE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*tx bitrate:\s*([\d.]+) MBit/s(.*)$").unwrap());
+static IW_WIDTH_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(\d+)MHz\b").unwrap());
+static IW_SURVEY_NOISE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^\s*frequency:\s*\d+(?:\.\d+)? MHz \[in use\]\s*\n\s*noise:\s*(-?\d+) dBm")
+        .unwrap()
+});
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WifiInfo {
     pub connected: bool,
@@ -40,13 +54,36 @@ impl Default for WifiInfo {
     }
 }
 
-pub fn get_wifi_info() -> WifiInfo {
-    let ssid = get_current_ssid();
+pub fn get_wifi_info(runner: &dyn CommandRunner, interface: Option<&NetworkInterface>) -> WifiInfo {
+    let Some(interface) = interface else {
+        log::debug!("get_wifi_info: no wireless interface found");
+        return WifiInfo::default();
+    };
+
+    #[cfg(target_os = "linux")]
+    if runner.native_probes() {
+        if let Some(info) = crate::nl80211::get_wifi_info(&interface.name) {
+            return info;
+        }
+        if let Some(info) = crate::networkmanager::get_device_state(&interface.name)
+            .and_then(|state| state.wifi_info())
+        {
+            return info;
+        }
+    }
+
+    if cfg!(target_os = "linux") {
+        get_wifi_info_iw(runner, &interface.name)
+    } else {
+        get_wifi_info_system_profiler(runner, &interface.name)
+    }
+}
+
+fn get_wifi_info_system_profiler(runner: &dyn CommandRunner, iface: &str) -> WifiInfo {
+    let ssid = get_current_ssid(runner, iface);
     log::debug!("get_wifi_info: current SSID: {:?}", ssid);
 
-    let output = Command::new("system_profiler")
-        .args(["SPAirPortDataType"])
-        .output();
+    let output = runner.run("system_profiler", &["SPAirPortDataType"]);
 
     let output = match output {
         Ok(o) => o,
@@ -60,8 +97,8 @@ pub fn get_wifi_info() -> WifiInfo {
         }
     };
 
-    if !output.status.success() {
-        log::error!("get_wifi_info: system_profiler failed with status: {}", output.status);
+    if !output.success {
+        log::error!("get_wifi_info: system_profiler failed");
         return WifiInfo {
             connected: ssid.is_some(),
             ssid,
@@ -69,8 +106,7 @@ pub fn get_wifi_info() -> WifiInfo {
         };
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut info = parse_wifi_info(&stdout);
+    let mut info = parse_wifi_info(&output.stdout);
     info.ssid = ssid;
     info.connected = info.ssid.is_some();
 
@@ -85,19 +121,17 @@ pub fn get_wifi_info() -> WifiInfo {
     info
 }
 
-fn get_current_ssid() -> Option<String> {
-    let output = Command::new("networksetup")
-        .args(["-getairportnetwork", "en0"])
-        .output()
+fn get_current_ssid(runner: &dyn CommandRunner, iface: &str) -> Option<String> {
+    let output = runner
+        .run("networksetup", &["-getairportnetwork", iface])
         .ok()?;
 
-    if !output.status.success() {
+    if !output.success {
         return None;
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
     CURRENT_SSID_RE
-        .captures(&stdout)
+        .captures(&output.stdout)
         .map(|caps| caps[1].trim().to_string())
         .filter(|s| !s.is_empty() && s != "You are not associated with an AirPort network.")
 }
@@ -152,6 +186,147 @@ fn parse_wifi_info(output: &str) -> WifiInfo {
     info
 }
 
+fn get_wifi_info_iw(runner: &dyn CommandRunner, iface: &str) -> WifiInfo {
+    let Some(link) = run_iw(runner, &["dev", iface, "link"]) else {
+        return WifiInfo::default();
+    };
+
+    let mut info = parse_iw_link(&link);
+    if !info.connected {
+        log::debug!("get_wifi_info: {} is not connected", iface);
+        return info;
+    }
+
+    if let Some(station) = run_iw(runner, &["dev", iface, "station", "dump"]) {
+        apply_iw_station_dump(&mut info, &station);
+    }
+
+    if let Some(survey) = run_iw(runner, &["dev", iface, "survey", "dump"]) {
+        info.noise_dbm = parse_iw_survey_noise(&survey);
+    }
+
+    log::debug!(
+        "get_wifi_info: {} signal: {:?}dBm, noise: {:?}dBm, channel: {:?}, rate: {:?}Mbps",
+        iface,
+        info.signal_dbm,
+        info.noise_dbm,
+        info.channel,
+        info.link_rate_mbps
+    );
+
+    info
+}
+
+pub(crate) fn run_iw(runner: &dyn CommandRunner, args: &[&str]) -> Option<String> {
+    let output = match runner.run("iw", args) {
+        Ok(o) => o,
+        Err(e) => {
+            log::error!("run_iw: failed to run iw {}: {}", args.join(" "), e);
+            return None;
+        }
+    };
+
+    if !output.success {
+        log::debug!("run_iw: iw {} failed", args.join(" "));
+        return None;
+    }
+
+    Some(output.stdout)
+}
+
+fn parse_iw_link(output: &str) -> WifiInfo {
+    let mut info = WifiInfo::default();
+
+    if !output.trim_start().starts_with("Connected to") {
+        return info;
+    }
+
+    info.connected = true;
+    info.ssid = IW_SSID_RE
+        .captures(output)
+        .map(|caps| caps[1].trim().to_string());
+    info.signal_dbm = IW_SIGNAL_RE
+        .captures(output)
+        .and_then(|caps| caps[1].parse().ok());
+
+    let freq_mhz: Option<u32> = IW_FREQ_RE
+        .captures(output)
+        .and_then(|caps| caps[1].parse().ok());
+
+    let mut width_mhz = 20;
+    if let Some(caps) = IW_TX_BITRATE_RE.captures(output) {
+        info.link_rate_mbps = caps[1].parse().ok();
+        info.frequency_band = phy_generation_from_bitrate(&caps[2]);
+        width_mhz = channel_width_from_bitrate(&caps[2]);
+    }
+
+    if let Some(freq) = freq_mhz {
+        info.channel = format_channel(freq, width_mhz);
+    }
+
+    info
+}
+
+fn apply_iw_station_dump(info: &mut WifiInfo, output: &str) {
+    if let Some(caps) = IW_SIGNAL_RE.captures(output) {
+        if info.signal_dbm.is_none() {
+            info.signal_dbm = caps[1].parse().ok();
+        }
+    }
+
+    if let Some(caps) = IW_TX_BITRATE_RE.captures(output) {
+        if info.link_rate_mbps.is_none() {
+            info.link_rate_mbps = caps[1].parse().ok();
+        }
+        if info.frequency_band.is_none() {
+            info.frequency_band = phy_generation_from_bitrate(&caps[2]);
+        }
+    }
+}
+
+fn parse_iw_survey_noise(output: &str) -> Option<i32> {
+    IW_SURVEY_NOISE_RE
+        .captures(output)
+        .and_then(|caps| caps[1].parse().ok())
+}
+
+fn phy_generation_from_bitrate(flags: &str) -> Option<String> {
+    let generation = if flags.contains("EHT-MCS") {
+        "Wi-Fi 7"
+    } else if flags.contains("HE-MCS") {
+        "Wi-Fi 6"
+    } else if flags.contains("VHT-MCS") {
+        "Wi-Fi 5"
+    } else if flags.contains("MCS") {
+        "Wi-Fi 4"
+    } else {
+        return None;
+    };
+    Some(generation.to_string())
+}
+
+fn channel_width_from_bitrate(flags: &str) -> u32 {
+    IW_WIDTH_RE
+        .captures(flags)
+        .and_then(|caps| caps[1].parse().ok())
+        .unwrap_or(20)
+}
+
+pub(crate) fn frequency_to_channel(freq_mhz: u32) -> Option<(u32, f64)> {
+    match freq_mhz {
+        2484 => Some((14, 2.4)),
+        2412..=2472 => Some(((freq_mhz - 2407) / 5, 2.4)),
+        5955..=7115 => Some(((freq_mhz - 5950) / 5, 6.0)),
+        5000..=5950 => Some(((freq_mhz - 5000) / 5, 5.0)),
+        _ => None,
+    }
+}
+
+pub(crate) fn format_channel(freq_mhz: u32, width_mhz: u32) -> Option<String> {
+    let (channel, band_ghz) = frequency_to_channel(freq_mhz)?;
+    Some(format!("ch {}, {} GHz, {} MHz", channel, band_ghz, width_mhz))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +375,71 @@ Wi-Fi:
         assert_eq!(info.noise_dbm, Some(-90));
         assert_eq!(info.link_rate_mbps, Some(576.0));
     }
+
+    #[test]
+    fn test_parse_iw_link() {
+        let sample = r#"Connected to 3c:37:86:aa:bb:cc (on wlp2s0)
+	SSID: MyNetwork
+	freq: 5745.0
+	RX: 183721032 bytes (150322 packets)
+	TX: 10393421 bytes (51203 packets)
+	signal: -58 dBm
+	rx bitrate: 1200.9 MBit/s 80MHz HE-MCS 11 HE-NSS 2 HE-GI 0 HE-DCM 0
+	tx bitrate: 960.7 MBit/s 80MHz HE-MCS 9 HE-NSS 2 HE-GI 0 HE-DCM 0
+
+	bss flags:	short-slot-time
+	dtim period:	1
+	beacon int:	100
+"#;
+
+        let info = parse_iw_link(sample);
+        assert!(info.connected);
+        assert_eq!(info.ssid, Some("MyNetwork".to_string()));
+        assert_eq!(info.frequency_band, Some("Wi-Fi 6".to_string()));
+        assert_eq!(info.channel, Some("ch 149, 5 GHz, 80 MHz".to_string()));
+        assert_eq!(info.signal_dbm, Some(-58));
+        assert_eq!(info.link_rate_mbps, Some(960.7));
+
+        let info = parse_iw_link("Not connected.\n");
+        assert!(!info.connected);
+        assert_eq!(info.ssid, None);
+    }
+
+    #[test]
+    fn test_parse_iw_station_dump_and_survey() {
+        let link = r#"Connected to 3c:37:86:aa:bb:cc (on wlp2s0)
+	SSID: HomeNet
+	freq: 2437
+"#;
+        let station = r#"Station 3c:37:86:aa:bb:cc (on wlp2s0)
+	inactive time:	1200 ms
+	rx bytes:	183721032
+	signal:  	-47 [-49, -51] dBm
+	signal avg:	-48 [-50, -52] dBm
+	tx bitrate:	144.4 MBit/s MCS 15 short GI
+	rx bitrate:	130.0 MBit/s MCS 15
+	connected time:	3600 seconds
+"#;
+        let survey = r#"Survey data from wlp2s0
+	frequency:			2412 MHz
+	noise:				-91 dBm
+Survey data from wlp2s0
+	frequency:			2437 MHz [in use]
+	noise:				-95 dBm
+	channel active time:		120345 ms
+	channel busy time:		40321 ms
+"#;
+
+        let mut info = parse_iw_link(link);
+        apply_iw_station_dump(&mut info, station);
+        info.noise_dbm = parse_iw_survey_noise(survey);
+
+        assert!(info.connected);
+        assert_eq!(info.ssid, Some("HomeNet".to_string()));
+        assert_eq!(info.channel, Some("ch 6, 2.4 GHz, 20 MHz".to_string()));
+        assert_eq!(info.frequency_band, Some("Wi-Fi 4".to_string()));
+        assert_eq!(info.signal_dbm, Some(-47));
+        assert_eq!(info.noise_dbm, Some(-95));
+        assert_eq!(info.link_rate_mbps, Some(144.4));
+    }
 }
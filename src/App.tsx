@@ -1,11 +1,17 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { attachConsole, info, debug } from "@tauri-apps/plugin-log";
+import { openUrl } from "@tauri-apps/plugin-opener";
 import "./App.css";
 import { cn } from "./utils/cn";
 import { useWifiMetrics } from "./hooks/useWifiMetrics";
 import { useInterferenceCheck } from "./hooks/useInterferenceCheck";
 import { useSpeedTest } from "./hooks/useSpeedTest";
+import { useHttpTiming } from "./hooks/useHttpTiming";
+import { useBufferbloat } from "./hooks/useBufferbloat";
+import { useTraceRoute } from "./hooks/useTraceRoute";
+import { useDnsBenchmark } from "./hooks/useDnsBenchmark";
+import { useLanDiscovery } from "./hooks/useLanDiscovery";
 import { useSettings } from "./hooks/useSettings";
 import { useAIDiagnosis } from "./hooks/useAIDiagnosis";
 import { Section } from "./components/Section";
@@ -13,6 +19,11 @@ import { MetricRow } from "./components/MetricRow";
 import { Spinner } from "./components/Spinner";
 import { InterferencePanel } from "./components/InterferencePanel";
 import { SpeedTestPanel } from "./components/SpeedTestPanel";
+import { HttpTimingPanel } from "./components/HttpTimingPanel";
+import { BufferbloatPanel } from "./components/BufferbloatPanel";
+import { TraceRoutePanel } from "./components/TraceRoutePanel";
+import { DnsBenchmarkPanel } from "./components/DnsBenchmarkPanel";
+import { LanDevicesPanel } from "./components/LanDevicesPanel";
 import { SettingsPanel } from "./components/SettingsPanel";
 import { DiagnosisPanel } from "./components/DiagnosisPanel";
 import {
@@ -21,17 +32,41 @@ import {
   getJitterStatus,
   getLossStatus,
   getLinkRateStatus,
+  getIpv6Status,
+  getIpv6StatusLabel,
+  isDualStackPending,
   WifiInfo,
+  NetworkInterface,
+  NetworkMetrics,
 } from "./types/metrics";
+import { getTransportLabel } from "./types/dns";
+import { getPortalSignalLabel } from "./types/portal";
+import { formatDuration, getLeaseStatus } from "./types/dhcp";
 
 function App() {
-  const { metrics, history, loading, error } = useWifiMetrics();
+  const {
+    settings,
+    saveApiKey,
+    clearApiKey,
+    saveWifiInterface,
+    saveInternetTargets,
+    saveHttpTimingUrl,
+    savePortalCheckUrl,
+    saveSpeedTestServer,
+    saveDnsResolvers,
+    hasApiKey,
+  } = useSettings();
+  const { metrics, history, loading, error } = useWifiMetrics(
+    settings.wifiInterface,
+    settings.internetTargets,
+    settings.portalCheckUrl
+  );
   const {
     analysis: interferenceAnalysis,
     loading: interferenceLoading,
     checkInterference,
     clearAnalysis,
-  } = useInterferenceCheck();
+  } = useInterferenceCheck(settings.wifiInterface, settings.internetTargets);
   const {
     results: speedTestResults,
     loading: speedTestLoading,
@@ -39,8 +74,42 @@ function App() {
     status: speedTestStatus,
     runSpeedTest,
     clearResults: clearSpeedTest,
-  } = useSpeedTest();
-  const { settings, saveApiKey, clearApiKey, hasApiKey } = useSettings();
+  } = useSpeedTest(settings.speedTestServer);
+  const {
+    timing: httpTiming,
+    loading: httpTimingLoading,
+    error: httpTimingError,
+    measureTiming,
+    clearTiming,
+  } = useHttpTiming(settings.httpTimingUrl);
+  const {
+    result: bufferbloat,
+    loading: bufferbloatLoading,
+    error: bufferbloatError,
+    measureBufferbloat,
+    clearResult: clearBufferbloat,
+  } = useBufferbloat(settings.speedTestServer);
+  const {
+    trace,
+    loading: traceLoading,
+    error: traceError,
+    traceRoute,
+    clearTrace,
+  } = useTraceRoute(settings.wifiInterface);
+  const {
+    result: dnsBenchmark,
+    loading: dnsBenchmarkLoading,
+    error: dnsBenchmarkError,
+    runBenchmark,
+    clearResult: clearDnsBenchmark,
+  } = useDnsBenchmark(settings.wifiInterface, settings.dnsResolvers);
+  const {
+    inventory: lanInventory,
+    loading: lanLoading,
+    error: lanError,
+    discover: discoverLan,
+    clearInventory: clearLanInventory,
+  } = useLanDiscovery(settings.wifiInterface);
   const {
     result: diagnosisResult,
     loading: diagnosisLoading,
@@ -75,9 +144,19 @@ function App() {
       history,
       interferenceAnalysis,
       speedTestResults,
+      httpTiming,
+      bufferbloat,
+      trace,
+      dnsBenchmark,
+      lanInventory,
     });
   };
 
+  const handleTraceRoute = () => {
+    const best = metrics?.internet_targets.find((r) => r.target.label === metrics.internet_summary.best_target);
+    traceRoute(best?.target.host ?? settings.internetTargets[0]?.host ?? null);
+  };
+
   const formatValue = (value: number | null | undefined, decimals = 0): string => {
     if (value === null || value === undefined) return "—";
     return value.toFixed(decimals);
@@ -90,13 +169,34 @@ function App() {
     return first;
   };
 
-  const formatWifiSubtitle = (wifi: WifiInfo): string | undefined => {
-    if (!wifi.channel) return undefined;
-    return wifi.channel;
+  const formatDnsSubtitle = (m: NetworkMetrics): string => {
+    const using = `Using ${truncateDns(m.dns.servers)}`;
+    const transport = m.dns.lookup_transport;
+    return transport === "tls" || transport === "https" ? `${using} over ${getTransportLabel(transport)}` : using;
+  };
+
+  const formatWifiSubtitle = (wifi: WifiInfo, iface: NetworkInterface | null): string | undefined => {
+    if (!wifi.channel) return iface?.name;
+    return iface ? `${wifi.channel} on ${iface.name}` : wifi.channel;
+  };
+
+  const formatAddressSubtitle = (m: NetworkMetrics): string | undefined => {
+    const address = m.addressing.ipv4[0] ?? m.addressing.ipv6[0];
+    if (!address) return undefined;
+    const cidr = `${address.address}/${address.prefix_len}`;
+    return m.addressing.lease?.server ? `${cidr} from ${m.addressing.lease.server}` : cidr;
+  };
+
+  const formatInternetSubtitle = (m: NetworkMetrics): string | undefined => {
+    const best = m.internet_targets.find((r) => r.target.label === m.internet_summary.best_target);
+    if (!best) return m.internet_targets.length > 0 ? "No targets reachable" : undefined;
+    return `Best: ${best.target.label} (${best.target.host})`;
   };
 
-  const isAnyPanelOpen = showSettings || diagnosisResult || interferenceAnalysis || speedTestResults;
-  const isAnyTaskRunning = interferenceLoading || speedTestLoading || diagnosisLoading;
+  const isAnyPanelOpen =
+    showSettings || diagnosisResult || interferenceAnalysis || speedTestResults || httpTiming || bufferbloat || trace || dnsBenchmark || lanInventory;
+  const isAnyTaskRunning =
+    interferenceLoading || speedTestLoading || httpTimingLoading || bufferbloatLoading || traceLoading || dnsBenchmarkLoading || lanLoading || diagnosisLoading;
 
   return (
     <div className="popover-wrapper">
@@ -137,8 +237,20 @@ function App() {
         {showSettings && (
           <SettingsPanel
             apiKey={settings.openaiApiKey}
+            wifiInterface={settings.wifiInterface}
+            internetTargets={settings.internetTargets}
+            httpTimingUrl={settings.httpTimingUrl}
+            portalCheckUrl={settings.portalCheckUrl}
+            speedTestServer={settings.speedTestServer}
+            dnsResolvers={settings.dnsResolvers}
             onSave={saveApiKey}
             onClear={clearApiKey}
+            onSelectInterface={saveWifiInterface}
+            onSaveTargets={saveInternetTargets}
+            onSaveHttpTimingUrl={saveHttpTimingUrl}
+            onSavePortalCheckUrl={savePortalCheckUrl}
+            onSaveSpeedTestServer={saveSpeedTestServer}
+            onSaveDnsResolvers={saveDnsResolvers}
             onClose={() => setShowSettings(false)}
           />
         )}
@@ -155,9 +267,46 @@ function App() {
           <SpeedTestPanel results={speedTestResults} onClose={clearSpeedTest} />
         )}
 
+        {!showSettings && !diagnosisResult && !interferenceAnalysis && !speedTestResults && httpTiming && (
+          <HttpTimingPanel timing={httpTiming} onClose={clearTiming} />
+        )}
+
+        {!showSettings && !diagnosisResult && !interferenceAnalysis && !speedTestResults && !httpTiming && bufferbloat && (
+          <BufferbloatPanel result={bufferbloat} onClose={clearBufferbloat} />
+        )}
+
+        {!showSettings && !diagnosisResult && !interferenceAnalysis && !speedTestResults && !httpTiming && !bufferbloat && trace && (
+          <TraceRoutePanel trace={trace} onClose={clearTrace} />
+        )}
+
+        {!showSettings && !diagnosisResult && !interferenceAnalysis && !speedTestResults && !httpTiming && !bufferbloat && !trace && dnsBenchmark && (
+          <DnsBenchmarkPanel result={dnsBenchmark} onClose={clearDnsBenchmark} />
+        )}
+
+        {!showSettings && !diagnosisResult && !interferenceAnalysis && !speedTestResults && !httpTiming && !bufferbloat && !trace && !dnsBenchmark && lanInventory && (
+          <LanDevicesPanel inventory={lanInventory} onClose={clearLanInventory} />
+        )}
+
         {!isAnyPanelOpen && metrics && (
           <div className="metrics-container">
-            <Section title="Connection to your router" subtitle={formatWifiSubtitle(metrics.wifi)}>
+            {metrics.captive_portal.state === "portal" && (
+              <div className="portal-banner">
+                <span>
+                  This network wants you to sign in before it lets you online. It{" "}
+                  {getPortalSignalLabel(metrics.captive_portal.signal)}.
+                </span>
+                {metrics.captive_portal.portal_url && (
+                  <button
+                    className="portal-banner-button"
+                    onClick={() => openUrl(metrics.captive_portal.portal_url!)}
+                  >
+                    Open sign-in page
+                  </button>
+                )}
+              </div>
+            )}
+
+            <Section title="Connection to your router" subtitle={formatWifiSubtitle(metrics.wifi, metrics.interface)}>
               <MetricRow
                 label="Link Rate"
                 value={formatValue(metrics.wifi.link_rate_mbps)}
@@ -197,6 +346,14 @@ function App() {
                     history={history.routerPing}
                     tooltip="How long it takes to send a message to your router and get a reply. Lower is better."
                   />
+                  <MetricRow
+                    label="Ping (p95)"
+                    value={formatValue(metrics.router_ping.p95_ms, 1)}
+                    unit=" ms"
+                    status={getPingStatus(metrics.router_ping.p95_ms)}
+                    history={history.routerP95}
+                    tooltip="The slowest replies from your router, ignoring rare outliers. A big gap from the normal ping means occasional stalls."
+                  />
                   <MetricRow
                     label="Jitter"
                     value={formatValue(metrics.router_ping.jitter_ms, 1)}
@@ -219,7 +376,32 @@ function App() {
               )}
             </Section>
 
-            <Section title="Connection to the internet" subtitle="Connected to 1.1.1.1">
+            <Section title="Your address on this network" subtitle={formatAddressSubtitle(metrics)}>
+              {metrics.addressing.ipv4.length === 0 && metrics.addressing.ipv6.length === 0 ? (
+                <div className="no-data">No address assigned</div>
+              ) : (
+                <>
+                  <MetricRow
+                    label="DHCP lease left"
+                    value={formatDuration(metrics.addressing.lease?.remaining_secs ?? null)}
+                    status={getLeaseStatus(metrics.addressing.lease)}
+                    history={[]}
+                    tooltip="How long until your address has to be renewed. It renews halfway through, so a lease running low means the router is not answering."
+                  />
+                  {metrics.addressing.issues.length > 0 && (
+                    <div className="internet-targets">
+                      {metrics.addressing.issues.map((issue) => (
+                        <div key={issue} className="internet-target-row internet-target-row--down">
+                          <span>{issue}</span>
+                        </div>
+                      ))}
+                    </div>
+                  )}
+                </>
+              )}
+            </Section>
+
+            <Section title="Connection to the internet" subtitle={formatInternetSubtitle(metrics)}>
               {metrics.internet_ping ? (
                 <>
                   <MetricRow
@@ -230,6 +412,14 @@ function App() {
                     history={history.internetPing}
                     tooltip="How long it takes to reach the internet and back. Lower is better for gaming and video calls."
                   />
+                  <MetricRow
+                    label="Ping (p95)"
+                    value={formatValue(metrics.internet_ping.p95_ms, 1)}
+                    unit=" ms"
+                    status={getPingStatus(metrics.internet_ping.p95_ms)}
+                    history={history.internetP95}
+                    tooltip="The slowest internet replies, ignoring rare outliers. Spikes here cause freezes in video calls even when the average looks fine."
+                  />
                   <MetricRow
                     label="Jitter"
                     value={formatValue(metrics.internet_ping.jitter_ms, 1)}
@@ -246,15 +436,68 @@ function App() {
                     history={history.internetLoss}
                     tooltip="Percentage of data packets lost on the way to the internet. Should be 0%."
                   />
+                  {metrics.internet_targets.length > 1 && (
+                    <div className="internet-targets">
+                      {metrics.internet_targets.map((r) => (
+                        <div
+                          key={`${r.target.label}-${r.target.host}`}
+                          className={cn(
+                            "internet-target-row",
+                            r.ping.latency_ms === null && "internet-target-row--down"
+                          )}
+                        >
+                          <span>
+                            {r.target.label}
+                            {r.protocol !== r.target.protocol && " (via TCP)"}
+                          </span>
+                          <span>
+                            {r.ping.latency_ms === null
+                              ? "unreachable"
+                              : `${formatValue(r.ping.latency_ms, 1)} ms, ${formatValue(r.ping.packet_loss_percent)}% loss`}
+                          </span>
+                        </div>
+                      ))}
+                    </div>
+                  )}
                 </>
               ) : (
-                <div className="no-data">Cannot reach internet</div>
+                <div className="no-data">
+                  {metrics.captive_portal.state === "portal" ? "Sign in to this network first" : "Cannot reach internet"}
+                </div>
+              )}
+            </Section>
+
+            <Section title="IPv4 vs IPv6" subtitle={`IPv6: ${getIpv6StatusLabel(metrics.dual_stack.ipv6_status)}`}>
+              {metrics.dual_stack.ipv6_status === "unavailable" ? (
+                <div className="no-data">No IPv6 on this network</div>
+              ) : isDualStackPending(metrics.dual_stack) ? (
+                <div className="no-data">Comparing IPv4 and IPv6...</div>
+              ) : (
+                <>
+                  <MetricRow
+                    label="IPv4 Ping"
+                    value={formatValue(metrics.dual_stack.ipv4.internet_summary.median_latency_ms, 1)}
+                    unit=" ms"
+                    status={getPingStatus(metrics.dual_stack.ipv4.internet_summary.median_latency_ms)}
+                    history={[]}
+                    tooltip="Median ping to your internet targets over IPv4."
+                  />
+                  <MetricRow
+                    label="IPv6 Ping"
+                    value={formatValue(metrics.dual_stack.ipv6.internet_summary.median_latency_ms, 1)}
+                    unit=" ms"
+                    status={getIpv6Status(metrics.dual_stack.ipv6_status)}
+                    history={[]}
+                    explanation={metrics.dual_stack.issue}
+                    tooltip="Median ping to the same targets over IPv6. Most apps try IPv6 first, so a broken IPv6 path makes everything slow to start."
+                  />
+                </>
               )}
             </Section>
 
             <Section
               title="Website name lookup"
-              subtitle={`Using ${truncateDns(metrics.dns.servers)}`}
+              subtitle={formatDnsSubtitle(metrics)}
             >
               <MetricRow
                 label="DNS Lookup"
@@ -289,6 +532,71 @@ function App() {
               )}
             </div>
 
+            <div className="speedtest-button-container">
+              <button
+                className={cn("speedtest-button", httpTimingLoading && "speedtest-button--running")}
+                onClick={measureTiming}
+                disabled={isAnyTaskRunning}
+              >
+                {httpTimingLoading ? "Loading website..." : "Website Timing"}
+              </button>
+              {httpTimingError && (
+                <div className="speedtest-error">{httpTimingError}</div>
+              )}
+            </div>
+
+            <div className="speedtest-button-container">
+              <button
+                className={cn("speedtest-button", bufferbloatLoading && "speedtest-button--running")}
+                onClick={measureBufferbloat}
+                disabled={isAnyTaskRunning}
+              >
+                {bufferbloatLoading ? "Loading connection..." : "Bufferbloat"}
+              </button>
+              {bufferbloatError && (
+                <div className="speedtest-error">{bufferbloatError}</div>
+              )}
+            </div>
+
+            <div className="speedtest-button-container">
+              <button
+                className={cn("speedtest-button", traceLoading && "speedtest-button--running")}
+                onClick={handleTraceRoute}
+                disabled={isAnyTaskRunning}
+              >
+                {traceLoading ? "Tracing..." : "Trace Route"}
+              </button>
+              {traceError && (
+                <div className="speedtest-error">{traceError}</div>
+              )}
+            </div>
+
+            <div className="speedtest-button-container">
+              <button
+                className={cn("speedtest-button", dnsBenchmarkLoading && "speedtest-button--running")}
+                onClick={runBenchmark}
+                disabled={isAnyTaskRunning}
+              >
+                {dnsBenchmarkLoading ? "Benchmarking DNS..." : "DNS Benchmark"}
+              </button>
+              {dnsBenchmarkError && (
+                <div className="speedtest-error">{dnsBenchmarkError}</div>
+              )}
+            </div>
+
+            <div className="speedtest-button-container">
+              <button
+                className={cn("speedtest-button", lanLoading && "speedtest-button--running")}
+                onClick={discoverLan}
+                disabled={isAnyTaskRunning}
+              >
+                {lanLoading ? "Looking for devices..." : "Devices on Network"}
+              </button>
+              {lanError && (
+                <div className="speedtest-error">{lanError}</div>
+              )}
+            </div>
+
             <div className="diagnose-button-container">
               <button
                 className={cn("diagnose-button", diagnosisLoading && "diagnose-button--running")}
@@ -0,0 +1,68 @@
+import { BufferbloatResult, getGradeStatus } from "../types/bufferbloat";
+
+interface BufferbloatPanelProps {
+  result: BufferbloatResult;
+  onClose: () => void;
+}
+
+export function BufferbloatPanel({ result, onClose }: BufferbloatPanelProps) {
+  const formatMs = (ms: number | null): string => {
+    return ms === null ? "—" : `${ms.toFixed(0)} ms`;
+  };
+
+  const formatIncrease = (ms: number | null): string => {
+    return ms === null ? "—" : `+${ms.toFixed(0)} ms`;
+  };
+
+  const formatMbps = (mbps: number | null): string => {
+    return mbps === null ? "—" : `${mbps.toFixed(1)} Mbps`;
+  };
+
+  const phases = [
+    { label: "Downloading", latency: result.download, increase: result.download_increase_ms, mbps: result.download_mbps },
+    { label: "Uploading", latency: result.upload, increase: result.upload_increase_ms, mbps: result.upload_mbps },
+  ];
+
+  return (
+    <div className="speedtest-panel">
+      <div className="speedtest-header">
+        <h2>Bufferbloat</h2>
+        <button className="close-button" onClick={onClose}>
+          Close
+        </button>
+      </div>
+
+      <div className="speedtest-content">
+        <div className="speedtest-section">
+          <div className="speedtest-section-title">{result.endpoint}</div>
+          <div className="speedtest-row">
+            <span className="speedtest-label">Grade</span>
+            <span className={`speedtest-value speedtest-value--${getGradeStatus(result.grade)}`}>
+              {result.grade ?? result.error ?? "—"}
+            </span>
+          </div>
+          <div className="speedtest-row">
+            <span className="speedtest-label">Idle latency</span>
+            <span className="speedtest-value">{formatMs(result.idle.median_ms)}</span>
+          </div>
+        </div>
+
+        {phases.map((phase) => (
+          <div key={phase.label} className="speedtest-section">
+            <div className="speedtest-section-title">{phase.label}</div>
+            <div className="speedtest-row">
+              <span className="speedtest-label">Latency</span>
+              <span className="speedtest-value">
+                {formatMs(phase.latency.median_ms)} ({formatIncrease(phase.increase)})
+              </span>
+            </div>
+            <div className="speedtest-row">
+              <span className="speedtest-label">Speed</span>
+              <span className="speedtest-value">{formatMbps(phase.mbps)}</span>
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
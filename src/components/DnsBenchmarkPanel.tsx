@@ -0,0 +1,75 @@
+import { DnsBenchmark, NxdomainBehavior, getTransportLabel } from "../types/dns";
+
+interface DnsBenchmarkPanelProps {
+  result: DnsBenchmark;
+  onClose: () => void;
+}
+
+export function DnsBenchmarkPanel({ result, onClose }: DnsBenchmarkPanelProps) {
+  const formatMs = (median: number | null, p95: number | null): string => {
+    if (median === null) return "—";
+    return p95 === null ? `${median.toFixed(0)} ms` : `${median.toFixed(0)} ms (p95 ${p95.toFixed(0)} ms)`;
+  };
+
+  const formatNxdomain = (behavior: NxdomainBehavior): string => {
+    if (behavior === "correct") return "Correct";
+    if (behavior === "hijacked") return "Redirects missing names";
+    return "—";
+  };
+
+  return (
+    <div className="speedtest-panel">
+      <div className="speedtest-header">
+        <h2>DNS Benchmark</h2>
+        <button className="close-button" onClick={onClose}>
+          Close
+        </button>
+      </div>
+
+      <div className="speedtest-content">
+        {result.recommendation && (
+          <div className="speedtest-section">
+            <div className="speedtest-section-title">Recommendation</div>
+            <div className="speedtest-value">{result.recommendation}</div>
+          </div>
+        )}
+
+        {result.resolvers.map((resolver) => (
+          <div key={`${resolver.system}-${resolver.server}`} className="speedtest-section">
+            <div className="speedtest-section-title">
+              {resolver.label} ({resolver.server}){resolver.system ? " · current" : ""}
+            </div>
+            <div className="speedtest-row">
+              <span className="speedtest-label">Transport</span>
+              <span className="speedtest-value">{getTransportLabel(resolver.transport)}</span>
+            </div>
+            <div className="speedtest-row">
+              <span className="speedtest-label">Cached</span>
+              <span className="speedtest-value">
+                {formatMs(resolver.cached_median_ms, resolver.cached_p95_ms)}
+              </span>
+            </div>
+            <div className="speedtest-row">
+              <span className="speedtest-label">Uncached</span>
+              <span className="speedtest-value">
+                {formatMs(resolver.uncached_median_ms, resolver.uncached_p95_ms)}
+              </span>
+            </div>
+            <div className="speedtest-row">
+              <span className="speedtest-label">Failures</span>
+              <span className={`speedtest-value${resolver.failures > 0 ? " speedtest-value--bad" : ""}`}>
+                {resolver.queries === 0 ? "—" : `${resolver.failures}/${resolver.queries}`}
+              </span>
+            </div>
+            <div className="speedtest-row">
+              <span className="speedtest-label">Missing names</span>
+              <span className={`speedtest-value${resolver.nxdomain === "hijacked" ? " speedtest-value--bad" : ""}`}>
+                {formatNxdomain(resolver.nxdomain)}
+              </span>
+            </div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
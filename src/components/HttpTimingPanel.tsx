@@ -0,0 +1,71 @@
+import { HttpTiming, getHttpPhaseStatus } from "../types/http";
+
+interface HttpTimingPanelProps {
+  timing: HttpTiming;
+  onClose: () => void;
+}
+
+export function HttpTimingPanel({ timing, onClose }: HttpTimingPanelProps) {
+  const phases: { label: string; value: number | null }[] = [
+    { label: "DNS lookup", value: timing.dns_ms },
+    { label: "TCP connect", value: timing.connect_ms },
+    { label: "TLS handshake", value: timing.tls_ms },
+    { label: "First byte", value: timing.first_byte_ms },
+    { label: "Transfer", value: timing.transfer_ms },
+  ];
+
+  const formatMs = (ms: number | null): string => {
+    return ms === null ? "—" : `${ms.toFixed(0)} ms`;
+  };
+
+  return (
+    <div className="speedtest-panel">
+      <div className="speedtest-header">
+        <h2>Website Timing</h2>
+        <button className="close-button" onClick={onClose}>
+          Close
+        </button>
+      </div>
+
+      <div className="speedtest-content">
+        <div className="speedtest-section">
+          <div className="speedtest-section-title">{timing.url}</div>
+          {timing.remote_address && (
+            <div className="speedtest-row">
+              <span className="speedtest-label">Server</span>
+              <span className="speedtest-value">{timing.remote_address}</span>
+            </div>
+          )}
+          <div className="speedtest-row">
+            <span className="speedtest-label">Status</span>
+            <span className={`speedtest-value ${timing.error ? "speedtest-value--bad" : ""}`}>
+              {timing.error ?? timing.status ?? "—"}
+            </span>
+          </div>
+        </div>
+
+        <div className="speedtest-section">
+          <div className="speedtest-section-title">Phases</div>
+          {phases.map((phase) => (
+            <div key={phase.label} className="speedtest-row">
+              <span className="speedtest-label">{phase.label}</span>
+              <span
+                className={
+                  phase.value === null
+                    ? "speedtest-value"
+                    : `speedtest-value speedtest-value--${getHttpPhaseStatus(phase.value)}`
+                }
+              >
+                {formatMs(phase.value)}
+              </span>
+            </div>
+          ))}
+          <div className="speedtest-row">
+            <span className="speedtest-label">Total</span>
+            <span className="speedtest-value">{formatMs(timing.total_ms)}</span>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
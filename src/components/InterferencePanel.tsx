@@ -1,6 +1,7 @@
 import { useState } from "react";
 import {
   InterferenceAnalysis,
+  formatPathMtu,
   getInterferenceLevelStatus,
   getSnrStatus,
 } from "../types/interference";
@@ -76,6 +77,20 @@ export function InterferencePanel({ analysis, onClose }: InterferencePanelProps)
           </div>
         </div>
 
+        {analysis.path_mtu.length > 0 && (
+          <div className="interference-section">
+            <div className="interference-section-title">Path MTU</div>
+            {analysis.path_mtu.map((result) => (
+              <div key={result.host} className="interference-row">
+                <span className="interference-label">{result.host}</span>
+                <span className={`interference-value ${result.mismatch ? "interference-value--warning" : ""}`}>
+                  {formatPathMtu(result)}
+                </span>
+              </div>
+            ))}
+          </div>
+        )}
+
         {analysis.suggestions.length > 0 && (
           <div className="interference-section">
             <div className="interference-section-title">Suggestions</div>
@@ -97,7 +112,9 @@ export function InterferencePanel({ analysis, onClose }: InterferencePanelProps)
                 <div key={idx} className="nearby-network-row">
                   <span className="network-ssid">{network.ssid}</span>
                   <span className="network-channel">
-                    Ch {network.channel} ({formatFrequency(network.frequency_ghz)})
+                    Ch {network.channel} ({formatFrequency(network.frequency_ghz)}
+                    {network.width_mhz ? `, ${network.width_mhz} MHz` : ""})
+                    {network.signal_dbm !== null && ` · ${network.signal_dbm} dBm`}
                   </span>
                 </div>
               ))}
@@ -0,0 +1,69 @@
+import { LanInventory, getDeviceLabel } from "../types/lan";
+
+interface LanDevicesPanelProps {
+  inventory: LanInventory;
+  onClose: () => void;
+}
+
+export function LanDevicesPanel({ inventory, onClose }: LanDevicesPanelProps) {
+  const count = inventory.devices.length;
+
+  return (
+    <div className="speedtest-panel">
+      <div className="speedtest-header">
+        <h2>Devices on Network</h2>
+        <button className="close-button" onClick={onClose}>
+          Close
+        </button>
+      </div>
+
+      <div className="speedtest-content">
+        <div className="speedtest-section">
+          <div className="speedtest-section-title">
+            {count === 1 ? "1 device" : `${count} devices`}
+            {inventory.interface ? ` on ${inventory.interface}` : ""}
+          </div>
+          <div className="speedtest-value">
+            {inventory.browsed
+              ? "Devices this computer has talked to, plus everything that answered an mDNS or SSDP query. Quiet devices may be missing."
+              : "Only devices this computer has talked to recently."}
+          </div>
+        </div>
+
+        {inventory.devices.map((device) => (
+          <div key={device.ip} className="speedtest-section">
+            <div className="speedtest-section-title">
+              {getDeviceLabel(device)}
+              {device.gateway ? " · router" : ""}
+            </div>
+            <div className="speedtest-row">
+              <span className="speedtest-label">Address</span>
+              <span className="speedtest-value">{device.ip}</span>
+            </div>
+            {device.mac && (
+              <div className="speedtest-row">
+                <span className="speedtest-label">MAC</span>
+                <span className="speedtest-value">
+                  {device.mac}
+                  {device.private_mac ? " (private)" : device.vendor ? ` (${device.vendor})` : ""}
+                </span>
+              </div>
+            )}
+            {device.model && device.model !== getDeviceLabel(device) && (
+              <div className="speedtest-row">
+                <span className="speedtest-label">Model</span>
+                <span className="speedtest-value">{device.model}</span>
+              </div>
+            )}
+            {device.services.length > 0 && (
+              <div className="speedtest-row">
+                <span className="speedtest-label">Services</span>
+                <span className="speedtest-value">{device.services.join(", ")}</span>
+              </div>
+            )}
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
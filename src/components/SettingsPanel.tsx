@@ -1,17 +1,172 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { error as logError } from "@tauri-apps/plugin-log";
+import { InternetTarget, NetworkInterface, ProbeProtocol } from "../types/metrics";
+import { DEFAULT_INTERNET_TARGETS } from "../types/settings";
+import { DEFAULT_HTTP_TIMING_URL } from "../types/http";
+import { DEFAULT_PORTAL_CHECK_URL } from "../types/portal";
+import { DEFAULT_SPEED_TEST_SERVER } from "../types/speedtest";
+import { DEFAULT_DNS_RESOLVERS, ResolverTarget } from "../types/dns";
 
 interface SettingsPanelProps {
   apiKey: string | null;
+  wifiInterface: string | null;
+  internetTargets: InternetTarget[];
+  httpTimingUrl: string | null;
+  portalCheckUrl: string | null;
+  speedTestServer: string | null;
+  dnsResolvers: ResolverTarget[];
   onSave: (apiKey: string) => Promise<void>;
   onClear: () => Promise<void>;
+  onSelectInterface: (wifiInterface: string | null) => Promise<void>;
+  onSaveTargets: (internetTargets: InternetTarget[]) => Promise<void>;
+  onSaveHttpTimingUrl: (httpTimingUrl: string | null) => Promise<void>;
+  onSavePortalCheckUrl: (portalCheckUrl: string | null) => Promise<void>;
+  onSaveSpeedTestServer: (speedTestServer: string | null) => Promise<void>;
+  onSaveDnsResolvers: (dnsResolvers: ResolverTarget[]) => Promise<void>;
   onClose: () => void;
 }
 
-export function SettingsPanel({ apiKey, onSave, onClear, onClose }: SettingsPanelProps) {
+export function SettingsPanel({
+  apiKey,
+  wifiInterface,
+  internetTargets,
+  httpTimingUrl,
+  portalCheckUrl,
+  speedTestServer,
+  dnsResolvers,
+  onSave,
+  onClear,
+  onSelectInterface,
+  onSaveTargets,
+  onSaveHttpTimingUrl,
+  onSavePortalCheckUrl,
+  onSaveSpeedTestServer,
+  onSaveDnsResolvers,
+  onClose,
+}: SettingsPanelProps) {
   const [inputValue, setInputValue] = useState(apiKey || "");
   const [showKey, setShowKey] = useState(false);
   const [saving, setSaving] = useState(false);
   const [status, setStatus] = useState<{ type: "success" | "error"; message: string } | null>(null);
+  const [interfaces, setInterfaces] = useState<NetworkInterface[]>([]);
+  const [targets, setTargets] = useState<InternetTarget[]>(internetTargets);
+  const [targetStatus, setTargetStatus] = useState<{ type: "success" | "error"; message: string } | null>(null);
+  const [timingUrl, setTimingUrl] = useState(httpTimingUrl || "");
+  const [timingUrlStatus, setTimingUrlStatus] = useState<{ type: "success" | "error"; message: string } | null>(null);
+  const [portalUrl, setPortalUrl] = useState(portalCheckUrl || "");
+  const [portalUrlStatus, setPortalUrlStatus] = useState<{ type: "success" | "error"; message: string } | null>(null);
+  const [serverUrl, setServerUrl] = useState(speedTestServer || "");
+  const [serverStatus, setServerStatus] = useState<{ type: "success" | "error"; message: string } | null>(null);
+  const [resolvers, setResolvers] = useState<ResolverTarget[]>(dnsResolvers);
+  const [resolverStatus, setResolverStatus] = useState<{ type: "success" | "error"; message: string } | null>(null);
+
+  useEffect(() => {
+    invoke<NetworkInterface[]>("list_network_interfaces")
+      .then(setInterfaces)
+      .catch((e) => logError(`SettingsPanel: failed to list interfaces - ${e}`));
+  }, []);
+
+  const handleSelectInterface = async (value: string) => {
+    try {
+      await onSelectInterface(value || null);
+    } catch {
+      setStatus({ type: "error", message: "Failed to save interface" });
+    }
+  };
+
+  const updateTarget = (index: number, changes: Partial<InternetTarget>) => {
+    setTargets(targets.map((target, i) => (i === index ? { ...target, ...changes } : target)));
+  };
+
+  const handleSaveTargets = async (next: InternetTarget[]) => {
+    const cleaned = next
+      .map((target) => ({
+        ...target,
+        label: target.label.trim(),
+        host: target.host.trim(),
+        ipv6_host: target.ipv6_host?.trim() || null,
+      }))
+      .filter((target) => target.host);
+    if (cleaned.length === 0) {
+      setTargetStatus({ type: "error", message: "Add at least one target" });
+      return;
+    }
+
+    try {
+      await onSaveTargets(cleaned.map((target) => ({ ...target, label: target.label || target.host })));
+      setTargets(cleaned);
+      setTargetStatus({ type: "success", message: "Targets saved" });
+    } catch {
+      setTargetStatus({ type: "error", message: "Failed to save targets" });
+    }
+  };
+
+  const handleSaveTimingUrl = async () => {
+    const url = timingUrl.trim();
+    if (url && !/^https?:\/\//.test(url)) {
+      setTimingUrlStatus({ type: "error", message: "URL must start with http:// or https://" });
+      return;
+    }
+
+    try {
+      await onSaveHttpTimingUrl(url || null);
+      setTimingUrl(url);
+      setTimingUrlStatus({ type: "success", message: "URL saved" });
+    } catch {
+      setTimingUrlStatus({ type: "error", message: "Failed to save URL" });
+    }
+  };
+
+  const handleSavePortalUrl = async () => {
+    const url = portalUrl.trim();
+    if (url && !/^https?:\/\//.test(url)) {
+      setPortalUrlStatus({ type: "error", message: "URL must start with http:// or https://" });
+      return;
+    }
+
+    try {
+      await onSavePortalCheckUrl(url || null);
+      setPortalUrl(url);
+      setPortalUrlStatus({ type: "success", message: "URL saved" });
+    } catch {
+      setPortalUrlStatus({ type: "error", message: "Failed to save URL" });
+    }
+  };
+
+  const handleSaveServer = async () => {
+    const server = serverUrl.trim();
+    if (server && !/^https?:\/\//.test(server)) {
+      setServerStatus({ type: "error", message: "URL must start with http:// or https://" });
+      return;
+    }
+
+    try {
+      await onSaveSpeedTestServer(server || null);
+      setServerUrl(server);
+      setServerStatus({ type: "success", message: "Server saved" });
+    } catch {
+      setServerStatus({ type: "error", message: "Failed to save server" });
+    }
+  };
+
+  const updateResolver = (index: number, changes: Partial<ResolverTarget>) => {
+    setResolvers(resolvers.map((resolver, i) => (i === index ? { ...resolver, ...changes } : resolver)));
+  };
+
+  const handleSaveResolvers = async (next: ResolverTarget[]) => {
+    const cleaned = next
+      .map((resolver) => ({ label: resolver.label.trim(), server: resolver.server.trim() }))
+      .filter((resolver) => resolver.server);
+
+    try {
+      await onSaveDnsResolvers(cleaned.map((resolver) => ({ ...resolver, label: resolver.label || resolver.server })));
+      setResolvers(cleaned);
+      setResolverStatus({ type: "success", message: "Resolvers saved" });
+    } catch {
+      setResolverStatus({ type: "error", message: "Failed to save resolvers" });
+    }
+  };
 
   const handleSave = async () => {
     if (!inputValue.trim()) {
@@ -57,6 +212,271 @@ export function SettingsPanel({ apiKey, onSave, onClear, onClose }: SettingsPane
       </div>
 
       <div className="settings-content">
+        <div className="settings-section">
+          <div className="settings-section-title">Wi-Fi Interface</div>
+          <p className="settings-description">
+            Choose which adapter to measure, or let Yfi pick the active wireless one.
+          </p>
+
+          <select
+            className="interface-select"
+            value={wifiInterface ?? ""}
+            onChange={(e) => handleSelectInterface(e.target.value)}
+          >
+            <option value="">Automatic</option>
+            {interfaces
+              .filter((iface) => iface.kind !== "loopback")
+              .map((iface) => (
+                <option key={iface.name} value={iface.name}>
+                  {iface.service ? `${iface.service} (${iface.name})` : iface.name} — {iface.kind}
+                  {iface.up ? "" : ", down"}
+                </option>
+              ))}
+          </select>
+        </div>
+
+        <div className="settings-section">
+          <div className="settings-section-title">Internet Targets</div>
+          <p className="settings-description">
+            Hosts probed to measure your internet connection. Use several providers so one
+            blocked or slow service does not look like an outage. ICMP targets fall back to a
+            TCP handshake on the port (443 if empty) when ping is blocked. An IPv6 host lets an
+            IPv4 address be compared against the same service over IPv6.
+          </p>
+
+          {targets.map((target, idx) => (
+            <div key={idx} className="target-row">
+              <input
+                className="target-input target-input--label"
+                value={target.label}
+                onChange={(e) => updateTarget(idx, { label: e.target.value })}
+                placeholder="Label"
+              />
+              <input
+                className="target-input"
+                value={target.host}
+                onChange={(e) => updateTarget(idx, { host: e.target.value })}
+                placeholder="Host"
+              />
+              <input
+                className="target-input"
+                value={target.ipv6_host ?? ""}
+                onChange={(e) => updateTarget(idx, { ipv6_host: e.target.value || null })}
+                placeholder="IPv6 host"
+              />
+              <select
+                className="target-protocol"
+                value={target.protocol}
+                onChange={(e) => updateTarget(idx, { protocol: e.target.value as ProbeProtocol })}
+              >
+                <option value="icmp">ICMP</option>
+                <option value="tcp">TCP</option>
+              </select>
+              <input
+                className="target-input target-input--port"
+                value={target.port ?? ""}
+                onChange={(e) => {
+                  const port = parseInt(e.target.value, 10);
+                  updateTarget(idx, { port: port > 0 && port < 65536 ? port : null });
+                }}
+                placeholder="443"
+                inputMode="numeric"
+              />
+              <button
+                className="show-key-button"
+                onClick={() => setTargets(targets.filter((_, i) => i !== idx))}
+                type="button"
+              >
+                Remove
+              </button>
+            </div>
+          ))}
+
+          {targetStatus && (
+            <div className={`settings-status settings-status--${targetStatus.type}`}>
+              {targetStatus.message}
+            </div>
+          )}
+
+          <div className="settings-actions">
+            <button
+              className="settings-button settings-button--primary"
+              onClick={() => handleSaveTargets(targets)}
+            >
+              Save
+            </button>
+            <button
+              className="settings-button settings-button--secondary"
+              onClick={() => setTargets([...targets, { label: "", host: "", protocol: "icmp" }])}
+            >
+              Add
+            </button>
+            <button
+              className="settings-button settings-button--secondary"
+              onClick={() => handleSaveTargets(DEFAULT_INTERNET_TARGETS)}
+            >
+              Reset
+            </button>
+          </div>
+        </div>
+
+        <div className="settings-section">
+          <div className="settings-section-title">Website Timing URL</div>
+          <p className="settings-description">
+            Page fetched to time each step of loading a website: name lookup, connecting,
+            the secure handshake and the first byte of the reply.
+          </p>
+
+          <div className="api-key-input-container">
+            <input
+              className="api-key-input"
+              value={timingUrl}
+              onChange={(e) => setTimingUrl(e.target.value)}
+              placeholder={DEFAULT_HTTP_TIMING_URL}
+            />
+          </div>
+
+          {timingUrlStatus && (
+            <div className={`settings-status settings-status--${timingUrlStatus.type}`}>
+              {timingUrlStatus.message}
+            </div>
+          )}
+
+          <div className="settings-actions">
+            <button
+              className="settings-button settings-button--primary"
+              onClick={handleSaveTimingUrl}
+            >
+              Save
+            </button>
+          </div>
+        </div>
+
+        <div className="settings-section">
+          <div className="settings-section-title">Captive Portal Check</div>
+          <p className="settings-description">
+            Page fetched to spot hotel and café sign-in pages. It must answer with an empty
+            204 reply; use http:// so a sign-in page can stand in for it and be detected.
+          </p>
+
+          <div className="api-key-input-container">
+            <input
+              className="api-key-input"
+              value={portalUrl}
+              onChange={(e) => setPortalUrl(e.target.value)}
+              placeholder={DEFAULT_PORTAL_CHECK_URL}
+            />
+          </div>
+
+          {portalUrlStatus && (
+            <div className={`settings-status settings-status--${portalUrlStatus.type}`}>
+              {portalUrlStatus.message}
+            </div>
+          )}
+
+          <div className="settings-actions">
+            <button
+              className="settings-button settings-button--primary"
+              onClick={handleSavePortalUrl}
+            >
+              Save
+            </button>
+          </div>
+        </div>
+
+        <div className="settings-section">
+          <div className="settings-section-title">Speed Test Server</div>
+          <p className="settings-description">
+            Server used by the speed test and to saturate your connection for the bufferbloat
+            test. It must answer Cloudflare-style /__down and /__up requests, so a self-hosted
+            server works too.
+          </p>
+
+          <div className="api-key-input-container">
+            <input
+              className="api-key-input"
+              value={serverUrl}
+              onChange={(e) => setServerUrl(e.target.value)}
+              placeholder={DEFAULT_SPEED_TEST_SERVER}
+            />
+          </div>
+
+          {serverStatus && (
+            <div className={`settings-status settings-status--${serverStatus.type}`}>
+              {serverStatus.message}
+            </div>
+          )}
+
+          <div className="settings-actions">
+            <button
+              className="settings-button settings-button--primary"
+              onClick={handleSaveServer}
+            >
+              Save
+            </button>
+          </div>
+        </div>
+
+        <div className="settings-section">
+          <div className="settings-section-title">DNS Resolvers</div>
+          <p className="settings-description">
+            Public resolvers the DNS benchmark compares with the ones your network hands out.
+            Use an IP address, optionally with a port, tls://host for DNS over TLS, or an
+            https:// URL for DNS over HTTPS.
+          </p>
+
+          {resolvers.map((resolver, idx) => (
+            <div key={idx} className="target-row">
+              <input
+                className="target-input target-input--label"
+                value={resolver.label}
+                onChange={(e) => updateResolver(idx, { label: e.target.value })}
+                placeholder="Label"
+              />
+              <input
+                className="target-input"
+                value={resolver.server}
+                onChange={(e) => updateResolver(idx, { server: e.target.value })}
+                placeholder="Address"
+              />
+              <button
+                className="show-key-button"
+                onClick={() => setResolvers(resolvers.filter((_, i) => i !== idx))}
+                type="button"
+              >
+                Remove
+              </button>
+            </div>
+          ))}
+
+          {resolverStatus && (
+            <div className={`settings-status settings-status--${resolverStatus.type}`}>
+              {resolverStatus.message}
+            </div>
+          )}
+
+          <div className="settings-actions">
+            <button
+              className="settings-button settings-button--primary"
+              onClick={() => handleSaveResolvers(resolvers)}
+            >
+              Save
+            </button>
+            <button
+              className="settings-button settings-button--secondary"
+              onClick={() => setResolvers([...resolvers, { label: "", server: "" }])}
+            >
+              Add
+            </button>
+            <button
+              className="settings-button settings-button--secondary"
+              onClick={() => handleSaveResolvers(DEFAULT_DNS_RESOLVERS)}
+            >
+              Reset
+            </button>
+          </div>
+        </div>
+
         <div className="settings-section">
           <div className="settings-section-title">OpenAI API Key</div>
           <p className="settings-description">
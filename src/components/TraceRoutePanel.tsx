@@ -0,0 +1,53 @@
+import { TraceUpdate, getHopLossStatus } from "../types/traceroute";
+
+interface TraceRoutePanelProps {
+  trace: TraceUpdate;
+  onClose: () => void;
+}
+
+export function TraceRoutePanel({ trace, onClose }: TraceRoutePanelProps) {
+  const formatMs = (ms: number | null): string => {
+    return ms === null ? "—" : ms.toFixed(1);
+  };
+
+  return (
+    <div className="speedtest-panel">
+      <div className="speedtest-header">
+        <h2>Route to {trace.host}</h2>
+        <button className="close-button" onClick={onClose}>
+          Close
+        </button>
+      </div>
+
+      <div className="speedtest-content">
+        <div className="speedtest-section">
+          <div className="speedtest-section-title">
+            {trace.complete
+              ? `${trace.rounds} rounds${trace.destination ? "" : ", destination did not answer"}`
+              : `Round ${trace.round} of ${trace.rounds}…`}
+          </div>
+          <div className="trace-hop-row trace-hop-row--header">
+            <span className="trace-hop-ttl">#</span>
+            <span className="trace-hop-host">Host</span>
+            <span className="trace-hop-stat">Loss</span>
+            <span className="trace-hop-stat">Avg</span>
+            <span className="trace-hop-stat">Worst</span>
+          </div>
+          {trace.hops.map((hop) => (
+            <div key={hop.ttl} className="trace-hop-row">
+              <span className="trace-hop-ttl">{hop.ttl}</span>
+              <span className="trace-hop-host" title={hop.address ?? undefined}>
+                {hop.hostname ?? hop.address ?? "???"}
+              </span>
+              <span className={`trace-hop-stat speedtest-value--${getHopLossStatus(hop.ping.packet_loss_percent)}`}>
+                {hop.ping.packet_loss_percent === null ? "—" : `${hop.ping.packet_loss_percent.toFixed(0)}%`}
+              </span>
+              <span className="trace-hop-stat">{formatMs(hop.ping.latency_ms)}</span>
+              <span className="trace-hop-stat">{formatMs(hop.ping.max_ms)}</span>
+            </div>
+          ))}
+        </div>
+      </div>
+    </div>
+  );
+}
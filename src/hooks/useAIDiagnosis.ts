@@ -2,15 +2,27 @@ import { useState, useCallback } from "react";
 import OpenAI from "openai";
 import { debug, info, error as logError } from "@tauri-apps/plugin-log";
 import { DiagnosisResult } from "../types/diagnosis";
-import { NetworkMetrics, MetricHistory } from "../types/metrics";
-import { InterferenceAnalysis } from "../types/interference";
+import { NetworkMetrics, MetricHistory, PingResult, FamilyMetrics, getIpv6StatusLabel, isDualStackPending } from "../types/metrics";
+import { InterferenceAnalysis, formatPathMtu } from "../types/interference";
 import { SpeedTestResults } from "../types/speedtest";
+import { HttpTiming } from "../types/http";
+import { BufferbloatResult } from "../types/bufferbloat";
+import { TraceUpdate } from "../types/traceroute";
+import { DnsBenchmark, getTransportLabel } from "../types/dns";
+import { CaptivePortal, getPortalSignalLabel } from "../types/portal";
+import { Addressing, formatDuration } from "../types/dhcp";
+import { LanInventory, getDeviceLabel } from "../types/lan";
 
 interface DiagnosisInput {
   metrics: NetworkMetrics | null;
   history: MetricHistory;
   interferenceAnalysis: InterferenceAnalysis | null;
   speedTestResults: SpeedTestResults | null;
+  httpTiming: HttpTiming | null;
+  bufferbloat: BufferbloatResult | null;
+  trace: TraceUpdate | null;
+  dnsBenchmark: DnsBenchmark | null;
+  lanInventory: LanInventory | null;
 }
 
 interface UseAIDiagnosisResult {
@@ -27,8 +39,98 @@ function formatTimeSeries(data: number[], unit: string): string {
   return `[${last10.join(", ")}] ${unit}`;
 }
 
+function formatLatencyDistribution(ping: PingResult | null): string {
+  if (!ping || ping.samples_ms.length === 0) return "No data";
+  const ms = (value: number | null) => (value === null ? "?" : `${value.toFixed(1)} ms`);
+  return `min ${ms(ping.min_ms)}, median ${ms(ping.median_ms)}, p95 ${ms(ping.p95_ms)}, p99 ${ms(ping.p99_ms)}, max ${ms(ping.max_ms)}, stddev ${ms(ping.stddev_ms)} (sent ${ping.sent ?? "?"}, received ${ping.received ?? "?"}, duplicates ${ping.duplicates ?? "?"})`;
+}
+
+function formatInternetTargets(metrics: NetworkMetrics | null): string {
+  if (!metrics || metrics.internet_targets.length === 0) return "No data";
+  return metrics.internet_targets
+    .map(({ target, protocol, ping }) => {
+      const via = protocol === target.protocol ? protocol : `${protocol}, ${target.protocol} got no replies`;
+      return ping.latency_ms === null
+        ? `\n  - ${target.label} (${target.host}, ${via}): unreachable`
+        : `\n  - ${target.label} (${target.host}, ${via}): ${ping.latency_ms.toFixed(1)} ms, ${ping.packet_loss_percent ?? "?"}% loss`;
+    })
+    .join("");
+}
+
+function formatDnsTransport(metrics: NetworkMetrics): string {
+  const { lookup_transport, lookup_connect_ms, lookup_query_ms } = metrics.dns;
+  if (!lookup_transport) return "Unknown";
+  const label = getTransportLabel(lookup_transport);
+  if (lookup_connect_ms === null || lookup_query_ms === null) return label;
+  return `${label} (connection setup ${lookup_connect_ms.toFixed(1)} ms, query ${lookup_query_ms.toFixed(1)} ms)`;
+}
+
+function formatFamily(family: FamilyMetrics): string {
+  const { median_latency_ms, packet_loss_percent, reachable_targets, total_targets } = family.internet_summary;
+  const latency = median_latency_ms === null ? "no replies" : `median ${median_latency_ms.toFixed(1)} ms`;
+  const loss = packet_loss_percent === null ? "" : `, ${packet_loss_percent.toFixed(0)}% loss`;
+  const router = family.router_ip ?? "none";
+  const dns = family.dns_servers.length > 0 ? family.dns_servers.join(", ") : "none";
+  return `router ${router}, ${reachable_targets}/${total_targets} targets reachable, ${latency}${loss}, DNS servers ${dns}`;
+}
+
+function formatCaptivePortal(portal: CaptivePortal): string {
+  switch (portal.state) {
+    case "open":
+      return `None (${portal.check_url} answered normally)`;
+    case "portal":
+      return `Detected - the network ${getPortalSignalLabel(portal.signal)}, sign-in page ${portal.portal_url ?? "unknown"}`;
+    case "blocked":
+      return `Check URL unreachable (${portal.error ?? "no reply"})`;
+    default:
+      return "Not checked";
+  }
+}
+
+function formatAddresses(addressing: Addressing): string {
+  const addresses = [...addressing.ipv4, ...addressing.ipv6].map((a) => `${a.address}/${a.prefix_len}`);
+  return addresses.length > 0 ? addresses.join(", ") : "None";
+}
+
+function formatLease(addressing: Addressing): string {
+  const { lease } = addressing;
+  if (!lease) return "Not found";
+  return `from ${lease.server ?? "unknown server"} via ${lease.source}, ${formatDuration(lease.remaining_secs)} left of ${formatDuration(lease.lease_time_secs)}`;
+}
+
+function formatTraceHops(trace: TraceUpdate): string {
+  return trace.hops
+    .map(({ ttl, address, hostname, ping }) => {
+      const name = hostname ? `${hostname} (${address})` : address ?? "no reply";
+      const latency = ping.latency_ms === null ? "" : `, avg ${ping.latency_ms.toFixed(1)} ms, worst ${ping.max_ms?.toFixed(1) ?? "?"} ms`;
+      return `\n- Hop ${ttl}: ${name}, ${ping.packet_loss_percent?.toFixed(0) ?? "?"}% loss${latency}`;
+    })
+    .join("");
+}
+
+function formatResolverBenchmarks(benchmark: DnsBenchmark): string {
+  const ms = (value: number | null) => (value === null ? "n/a" : `${value.toFixed(0)} ms`);
+  return benchmark.resolvers
+    .map((r) => {
+      const origin = `${r.system ? "current" : "alternative"}, ${getTransportLabel(r.transport)}`;
+      const nxdomain = r.nxdomain === "hijacked" ? ", redirects missing names" : "";
+      return `\n- ${r.label} (${r.server}, ${origin}): cached ${ms(r.cached_median_ms)}, uncached ${ms(r.uncached_median_ms)} (p95 ${ms(r.uncached_p95_ms)}), ${r.failures}/${r.queries} failed${nxdomain}`;
+    })
+    .join("");
+}
+
+function formatLanDevices(inventory: LanInventory): string {
+  return inventory.devices
+    .map((d) => {
+      const details = [d.vendor, d.model, d.gateway ? "router" : null].filter(Boolean).join(", ");
+      const services = d.services.length > 0 ? `, advertises ${d.services.join(", ")}` : "";
+      return `\n- ${getDeviceLabel(d)} (${d.ip}${details ? `, ${details}` : ""})${services}`;
+    })
+    .join("");
+}
+
 function buildPrompt(input: DiagnosisInput): string {
-  const { metrics, history, interferenceAnalysis, speedTestResults } = input;
+  const { metrics, history, interferenceAnalysis, speedTestResults, httpTiming, bufferbloat, trace, dnsBenchmark, lanInventory } = input;
 
   let prompt = `You are a Wi-Fi network diagnostic expert. Analyze the following network metrics and provide actionable recommendations to improve the user's Wi-Fi experience.
 
@@ -41,6 +143,13 @@ The data below includes time series measurements (oldest to newest) to help you
     prompt += `- Frequency Band: ${metrics.wifi.frequency_band || "Unknown"}
 - Channel: ${metrics.wifi.channel || "Unknown"}
 - DNS Servers: ${metrics.dns.servers.length > 0 ? metrics.dns.servers.join(", ") : "None configured"}
+- Last DNS Lookup Status: ${metrics.dns.lookup_rcode || "Unknown"}
+- DNS Transport: ${formatDnsTransport(metrics)}
+- Captive Portal: ${formatCaptivePortal(metrics.captive_portal)}
+- Addresses: ${formatAddresses(metrics.addressing)}
+- Default Gateway: ${metrics.addressing.gateway ?? "None"}
+- DHCP Lease: ${formatLease(metrics.addressing)}
+- Addressing Issues: ${metrics.addressing.issues.length > 0 ? metrics.addressing.issues.join("; ") : "None"}
 `;
   }
 
@@ -57,17 +166,31 @@ The data below includes time series measurements (oldest to newest) to help you
 ### Router Connection
 - Latency (ms): ${formatTimeSeries(history.routerPing, "ms")}
 - Jitter (ms): ${formatTimeSeries(history.routerJitter, "ms")}
+- p95 Latency (ms): ${formatTimeSeries(history.routerP95, "ms")}
+- Latest Latency Distribution: ${formatLatencyDistribution(metrics?.router_ping ?? null)}
 - Packet Loss (%): ${formatTimeSeries(history.routerLoss, "%")}
 
-### Internet Connection (to 1.1.1.1)
+### Internet Connection (best target: ${metrics?.internet_summary.best_target ?? "none reachable"})
 - Latency (ms): ${formatTimeSeries(history.internetPing, "ms")}
 - Jitter (ms): ${formatTimeSeries(history.internetJitter, "ms")}
+- p95 Latency (ms): ${formatTimeSeries(history.internetP95, "ms")}
+- Latest Latency Distribution: ${formatLatencyDistribution(metrics?.internet_ping ?? null)}
+- Per-Target Results: ${formatInternetTargets(metrics)}
 - Packet Loss (%): ${formatTimeSeries(history.internetLoss, "%")}
 
 ### DNS
 - Lookup Latency (ms): ${formatTimeSeries(history.dnsLookup, "ms")}
 `;
 
+  if (metrics && !isDualStackPending(metrics.dual_stack)) {
+    const { ipv4, ipv6, ipv6_status, issue } = metrics.dual_stack;
+    prompt += `
+### IPv4 vs IPv6
+- IPv6 Status: ${getIpv6StatusLabel(ipv6_status)}
+- IPv4: ${formatFamily(ipv4)}
+${ipv6_status === "unavailable" ? "" : `- IPv6: ${formatFamily(ipv6)}\n`}${issue ? `- Issue: ${issue}\n` : ""}`;
+  }
+
   if (interferenceAnalysis) {
     prompt += `
 ### Interference Analysis
@@ -77,6 +200,7 @@ The data below includes time series measurements (oldest to newest) to help you
 - Networks on Same Channel: ${interferenceAnalysis.same_channel_count}
 - Overlapping Networks: ${interferenceAnalysis.overlapping_count}
 - Total Nearby Networks: ${interferenceAnalysis.nearby_networks.length}
+- Path MTU: ${interferenceAnalysis.path_mtu.map((result) => `${result.host} ${formatPathMtu(result)}`).join("; ") || "Unknown"}
 `;
   }
 
@@ -90,6 +214,52 @@ The data below includes time series measurements (oldest to newest) to help you
 `;
   }
 
+  if (httpTiming) {
+    const ms = (value: number | null) => (value === null ? "n/a" : `${value.toFixed(0)} ms`);
+    prompt += `
+### Website Load Timing (${httpTiming.url})
+- Result: ${httpTiming.error ? `failed - ${httpTiming.error}` : `HTTP ${httpTiming.status ?? "?"}`}
+- DNS Lookup: ${ms(httpTiming.dns_ms)}
+- TCP Connect: ${ms(httpTiming.connect_ms)}
+- TLS Handshake: ${ms(httpTiming.tls_ms)}
+- Time to First Byte: ${ms(httpTiming.first_byte_ms)}
+- Transfer: ${ms(httpTiming.transfer_ms)}
+- Total: ${ms(httpTiming.total_ms)}
+`;
+  }
+
+  if (bufferbloat) {
+    const ms = (value: number | null) => (value === null ? "n/a" : `${value.toFixed(0)} ms`);
+    const mbps = (value: number | null) => (value === null ? "n/a" : `${value.toFixed(1)} Mbps`);
+    prompt += `
+### Latency Under Load (Bufferbloat, ${bufferbloat.endpoint})
+- Grade: ${bufferbloat.grade ?? `not graded${bufferbloat.error ? ` - ${bufferbloat.error}` : ""}`}
+- Idle Latency: ${ms(bufferbloat.idle.median_ms)}
+- While Downloading: ${ms(bufferbloat.download.median_ms)} (increase ${ms(bufferbloat.download_increase_ms)}) at ${mbps(bufferbloat.download_mbps)}
+- While Uploading: ${ms(bufferbloat.upload.median_ms)} (increase ${ms(bufferbloat.upload_increase_ms)}) at ${mbps(bufferbloat.upload_mbps)}
+`;
+  }
+
+  if (trace && trace.hops.length > 0) {
+    prompt += `
+### Route to ${trace.host} (${trace.round} rounds${trace.destination ? "" : ", destination did not answer"})
+Loss at an intermediate hop that does not carry on to later hops is usually ICMP rate limiting, not a real problem.${formatTraceHops(trace)}
+`;
+  }
+
+  if (dnsBenchmark && dnsBenchmark.resolvers.length > 0) {
+    prompt += `
+### DNS Resolver Benchmark${formatResolverBenchmarks(dnsBenchmark)}
+${dnsBenchmark.recommendation ? `- Recommendation: ${dnsBenchmark.recommendation}\n` : ""}`;
+  }
+
+  if (lanInventory) {
+    prompt += `
+### Devices on the Local Network
+- Device Count: ${lanInventory.devices.length}${lanInventory.browsed ? "" : " (neighbor table only)"}${formatLanDevices(lanInventory)}
+`;
+  }
+
   prompt += `
 ## Instructions
 Analyze the above data and respond with a JSON object in this exact format:
@@ -116,7 +286,16 @@ Guidelines:
 - Signal strength: -30 to -50 dBm is excellent, -50 to -60 is good, -60 to -70 is fair, below -70 is weak
 - Ping latency: under 20ms is excellent, 20-50ms is good, 50-100ms is acceptable, over 100ms is problematic
 - Any packet loss above 0% is concerning
+- Jitter is the mean difference between consecutive round trips (RFC 3550), not the standard deviation
+- Judge real-time use (video calls, gaming) by tail latency: a p95/p99 far above the median points to intermittent stalls even when the average looks healthy
+- If a target was measured over TCP because ICMP got no replies, the network is filtering ping; do not treat the ICMP failure as an outage
+- If only some internet targets are unreachable, the problem is likely that provider or a filter, not the user's connection
+- Duplicate replies suggest a misbehaving network device or loop
+- A 169.254.x.x address, a missing default gateway or a lease that failed to renew explains "connected but no internet" on its own; address that before Wi-Fi quality
+- If a captive portal is detected, the internet loss is expected until the user signs in; tell them to open the sign-in page rather than blaming Wi-Fi or the ISP
+- If IPv6 is broken while IPv4 works, apps will hang for seconds before falling back; recommend fixing or disabling IPv6 on the router
 - If interference analysis is available, consider channel congestion
+- If devices on the local network are listed, consider whether one of them (a camera, a backup or streaming box) could be using the shared bandwidth, but only as a possibility since their traffic is not measured
 - Respond ONLY with the JSON object, no additional text
 `;
 
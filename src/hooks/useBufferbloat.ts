@@ -0,0 +1,48 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { debug, error as logError } from "@tauri-apps/plugin-log";
+import { BufferbloatResult } from "../types/bufferbloat";
+
+interface UseBufferbloatResult {
+  result: BufferbloatResult | null;
+  loading: boolean;
+  error: string | null;
+  measureBufferbloat: () => Promise<void>;
+  clearResult: () => void;
+}
+
+export function useBufferbloat(endpoint: string | null): UseBufferbloatResult {
+  const [result, setResult] = useState<BufferbloatResult | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const measureBufferbloat = useCallback(async () => {
+    setLoading(true);
+    setError(null);
+    debug(`useBufferbloat: loading ${endpoint ?? "default endpoint"}`);
+    try {
+      const measured = await invoke<BufferbloatResult>("measure_bufferbloat", { endpoint });
+      setResult(measured);
+      debug(`useBufferbloat: complete - grade: ${measured.grade}, error: ${measured.error}`);
+    } catch (e) {
+      const errorMsg = e instanceof Error ? e.message : String(e);
+      logError(`useBufferbloat: failed - ${errorMsg}`);
+      setError(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, [endpoint]);
+
+  const clearResult = useCallback(() => {
+    setResult(null);
+    setError(null);
+  }, []);
+
+  return {
+    result,
+    loading,
+    error,
+    measureBufferbloat,
+    clearResult,
+  };
+}
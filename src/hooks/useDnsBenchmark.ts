@@ -0,0 +1,54 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { debug, error as logError } from "@tauri-apps/plugin-log";
+import { DnsBenchmark, ResolverTarget } from "../types/dns";
+
+interface UseDnsBenchmarkResult {
+  result: DnsBenchmark | null;
+  loading: boolean;
+  error: string | null;
+  runBenchmark: () => Promise<void>;
+  clearResult: () => void;
+}
+
+export function useDnsBenchmark(
+  wifiInterface: string | null,
+  alternatives: ResolverTarget[],
+): UseDnsBenchmarkResult {
+  const [result, setResult] = useState<DnsBenchmark | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const runBenchmark = useCallback(async () => {
+    setLoading(true);
+    setError(null);
+    debug(`useDnsBenchmark: benchmarking system resolvers and ${alternatives.length} alternatives`);
+    try {
+      const benchmark = await invoke<DnsBenchmark>("benchmark_dns", {
+        interface: wifiInterface,
+        alternatives,
+      });
+      setResult(benchmark);
+      debug(`useDnsBenchmark: complete - ${benchmark.resolvers.length} resolvers, recommendation: ${benchmark.recommendation}`);
+    } catch (e) {
+      const errorMsg = e instanceof Error ? e.message : String(e);
+      logError(`useDnsBenchmark: failed - ${errorMsg}`);
+      setError(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, [wifiInterface, alternatives]);
+
+  const clearResult = useCallback(() => {
+    setResult(null);
+    setError(null);
+  }, []);
+
+  return {
+    result,
+    loading,
+    error,
+    runBenchmark,
+    clearResult,
+  };
+}
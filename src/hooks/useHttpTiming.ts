@@ -0,0 +1,48 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { debug, error as logError } from "@tauri-apps/plugin-log";
+import { HttpTiming } from "../types/http";
+
+interface UseHttpTimingResult {
+  timing: HttpTiming | null;
+  loading: boolean;
+  error: string | null;
+  measureTiming: () => Promise<void>;
+  clearTiming: () => void;
+}
+
+export function useHttpTiming(url: string | null): UseHttpTimingResult {
+  const [timing, setTiming] = useState<HttpTiming | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const measureTiming = useCallback(async () => {
+    setLoading(true);
+    setError(null);
+    debug(`useHttpTiming: fetching ${url ?? "default URL"}`);
+    try {
+      const result = await invoke<HttpTiming>("measure_http_timing", { url });
+      setTiming(result);
+      debug(`useHttpTiming: complete - status: ${result.status}, total: ${result.total_ms}ms`);
+    } catch (e) {
+      const errorMsg = e instanceof Error ? e.message : String(e);
+      logError(`useHttpTiming: failed - ${errorMsg}`);
+      setError(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, [url]);
+
+  const clearTiming = useCallback(() => {
+    setTiming(null);
+    setError(null);
+  }, []);
+
+  return {
+    timing,
+    loading,
+    error,
+    measureTiming,
+    clearTiming,
+  };
+}
@@ -2,6 +2,7 @@ import { useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { debug, error as logError } from "@tauri-apps/plugin-log";
 import { InterferenceAnalysis } from "../types/interference";
+import { InternetTarget } from "../types/metrics";
 
 interface UseInterferenceCheckResult {
   analysis: InterferenceAnalysis | null;
@@ -11,7 +12,10 @@ interface UseInterferenceCheckResult {
   clearAnalysis: () => void;
 }
 
-export function useInterferenceCheck(): UseInterferenceCheckResult {
+export function useInterferenceCheck(
+  wifiInterface: string | null,
+  internetTargets: InternetTarget[],
+): UseInterferenceCheckResult {
   const [analysis, setAnalysis] = useState<InterferenceAnalysis | null>(null);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
@@ -21,7 +25,10 @@ export function useInterferenceCheck(): UseInterferenceCheckResult {
     setError(null);
     debug("useInterferenceCheck: starting interference check");
     try {
-      const result = await invoke<InterferenceAnalysis>("check_interference");
+      const result = await invoke<InterferenceAnalysis>("check_interference", {
+        interface: wifiInterface,
+        targets: internetTargets,
+      });
       setAnalysis(result);
       debug(`useInterferenceCheck: complete - level: ${result.interference_level}, nearby: ${result.nearby_networks.length}`);
     } catch (e) {
@@ -31,7 +38,7 @@ export function useInterferenceCheck(): UseInterferenceCheckResult {
     } finally {
       setLoading(false);
     }
-  }, []);
+  }, [wifiInterface, internetTargets]);
 
   const clearAnalysis = useCallback(() => {
     setAnalysis(null);
@@ -0,0 +1,50 @@
+import { useState, useCallback } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { debug, error as logError } from "@tauri-apps/plugin-log";
+import { LanInventory } from "../types/lan";
+
+interface UseLanDiscoveryResult {
+  inventory: LanInventory | null;
+  loading: boolean;
+  error: string | null;
+  discover: () => Promise<void>;
+  clearInventory: () => void;
+}
+
+export function useLanDiscovery(wifiInterface: string | null): UseLanDiscoveryResult {
+  const [inventory, setInventory] = useState<LanInventory | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const discover = useCallback(async () => {
+    setLoading(true);
+    setError(null);
+    debug(`useLanDiscovery: discovering devices on ${wifiInterface ?? "default interface"}`);
+    try {
+      const result = await invoke<LanInventory>("discover_lan", {
+        interface: wifiInterface,
+      });
+      setInventory(result);
+      debug(`useLanDiscovery: complete - ${result.devices.length} devices`);
+    } catch (e) {
+      const errorMsg = e instanceof Error ? e.message : String(e);
+      logError(`useLanDiscovery: failed - ${errorMsg}`);
+      setError(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, [wifiInterface]);
+
+  const clearInventory = useCallback(() => {
+    setInventory(null);
+    setError(null);
+  }, []);
+
+  return {
+    inventory,
+    loading,
+    error,
+    discover,
+    clearInventory,
+  };
+}
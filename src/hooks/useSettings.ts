@@ -2,6 +2,8 @@ import { useState, useEffect, useCallback } from "react";
 import { load } from "@tauri-apps/plugin-store";
 import { debug, error as logError } from "@tauri-apps/plugin-log";
 import { AppSettings, DEFAULT_SETTINGS } from "../types/settings";
+import { InternetTarget } from "../types/metrics";
+import { ResolverTarget } from "../types/dns";
 
 const STORE_NAME = "settings.json";
 const SETTINGS_KEY = "app_settings";
@@ -11,6 +13,12 @@ interface UseSettingsResult {
   loading: boolean;
   saveApiKey: (apiKey: string) => Promise<void>;
   clearApiKey: () => Promise<void>;
+  saveWifiInterface: (wifiInterface: string | null) => Promise<void>;
+  saveInternetTargets: (internetTargets: InternetTarget[]) => Promise<void>;
+  saveHttpTimingUrl: (httpTimingUrl: string | null) => Promise<void>;
+  savePortalCheckUrl: (portalCheckUrl: string | null) => Promise<void>;
+  saveSpeedTestServer: (speedTestServer: string | null) => Promise<void>;
+  saveDnsResolvers: (dnsResolvers: ResolverTarget[]) => Promise<void>;
   hasApiKey: boolean;
 }
 
@@ -25,7 +33,7 @@ export function useSettings(): UseSettingsResult {
         const store = await load(STORE_NAME);
         const savedSettings = await store.get<AppSettings>(SETTINGS_KEY);
         if (savedSettings) {
-          setSettings(savedSettings);
+          setSettings({ ...DEFAULT_SETTINGS, ...savedSettings });
           debug("useSettings: settings loaded successfully");
         }
       } catch (e) {
@@ -73,11 +81,125 @@ export function useSettings(): UseSettingsResult {
     }
   }, [settings]);
 
+  const saveWifiInterface = useCallback(async (wifiInterface: string | null) => {
+    try {
+      debug(`useSettings: saving Wi-Fi interface ${wifiInterface ?? "automatic"}`);
+      const store = await load(STORE_NAME);
+      const newSettings: AppSettings = {
+        ...settings,
+        wifiInterface,
+      };
+      await store.set(SETTINGS_KEY, newSettings);
+      await store.save();
+      setSettings(newSettings);
+      debug("useSettings: Wi-Fi interface saved successfully");
+    } catch (e) {
+      logError(`useSettings: failed to save Wi-Fi interface - ${e}`);
+      throw e;
+    }
+  }, [settings]);
+
+  const saveInternetTargets = useCallback(async (internetTargets: InternetTarget[]) => {
+    try {
+      debug(`useSettings: saving ${internetTargets.length} internet targets`);
+      const store = await load(STORE_NAME);
+      const newSettings: AppSettings = {
+        ...settings,
+        internetTargets,
+      };
+      await store.set(SETTINGS_KEY, newSettings);
+      await store.save();
+      setSettings(newSettings);
+      debug("useSettings: internet targets saved successfully");
+    } catch (e) {
+      logError(`useSettings: failed to save internet targets - ${e}`);
+      throw e;
+    }
+  }, [settings]);
+
+  const saveHttpTimingUrl = useCallback(async (httpTimingUrl: string | null) => {
+    try {
+      debug(`useSettings: saving HTTP timing URL ${httpTimingUrl ?? "default"}`);
+      const store = await load(STORE_NAME);
+      const newSettings: AppSettings = {
+        ...settings,
+        httpTimingUrl,
+      };
+      await store.set(SETTINGS_KEY, newSettings);
+      await store.save();
+      setSettings(newSettings);
+      debug("useSettings: HTTP timing URL saved successfully");
+    } catch (e) {
+      logError(`useSettings: failed to save HTTP timing URL - ${e}`);
+      throw e;
+    }
+  }, [settings]);
+
+  const savePortalCheckUrl = useCallback(async (portalCheckUrl: string | null) => {
+    try {
+      debug(`useSettings: saving portal check URL ${portalCheckUrl ?? "default"}`);
+      const store = await load(STORE_NAME);
+      const newSettings: AppSettings = {
+        ...settings,
+        portalCheckUrl,
+      };
+      await store.set(SETTINGS_KEY, newSettings);
+      await store.save();
+      setSettings(newSettings);
+      debug("useSettings: portal check URL saved successfully");
+    } catch (e) {
+      logError(`useSettings: failed to save portal check URL - ${e}`);
+      throw e;
+    }
+  }, [settings]);
+
+  const saveSpeedTestServer = useCallback(async (speedTestServer: string | null) => {
+    try {
+      debug(`useSettings: saving speed test server ${speedTestServer ?? "default"}`);
+      const store = await load(STORE_NAME);
+      const newSettings: AppSettings = {
+        ...settings,
+        speedTestServer,
+      };
+      await store.set(SETTINGS_KEY, newSettings);
+      await store.save();
+      setSettings(newSettings);
+      debug("useSettings: speed test server saved successfully");
+    } catch (e) {
+      logError(`useSettings: failed to save speed test server - ${e}`);
+      throw e;
+    }
+  }, [settings]);
+
+  const saveDnsResolvers = useCallback(async (dnsResolvers: ResolverTarget[]) => {
+    try {
+      debug(`useSettings: saving ${dnsResolvers.length} DNS resolvers`);
+      const store = await load(STORE_NAME);
+      const newSettings: AppSettings = {
+        ...settings,
+        dnsResolvers,
+      };
+      await store.set(SETTINGS_KEY, newSettings);
+      await store.save();
+      setSettings(newSettings);
+      debug("useSettings: DNS resolvers saved successfully");
+    } catch (e) {
+      logError(`useSettings: failed to save DNS resolvers - ${e}`);
+      throw e;
+    }
+  }, [settings]);
+
   return {
     settings,
     loading,
     saveApiKey,
     clearApiKey,
+    saveWifiInterface,
+    saveInternetTargets,
+    saveHttpTimingUrl,
+    savePortalCheckUrl,
+    saveSpeedTestServer,
+    saveDnsResolvers,
     hasApiKey: !!settings.openaiApiKey,
   };
 }
@@ -1,7 +1,7 @@
 import { useState, useCallback, useRef } from "react";
-import SpeedTest from "@cloudflare/speedtest";
+import { Channel, invoke } from "@tauri-apps/api/core";
 import { debug, info, error as logError } from "@tauri-apps/plugin-log";
-import { SpeedTestResults } from "../types/speedtest";
+import { SpeedTestProgress, SpeedTestResults } from "../types/speedtest";
 
 interface UseSpeedTestResult {
   results: SpeedTestResults | null;
@@ -12,98 +12,61 @@ interface UseSpeedTestResult {
   clearResults: () => void;
 }
 
-export function useSpeedTest(): UseSpeedTestResult {
+function formatProgress({ phase, mbps, latency_ms }: SpeedTestProgress): string {
+  if (phase === "latency") {
+    return latency_ms === null ? "Testing latency..." : `Testing latency... ${latency_ms.toFixed(0)} ms`;
+  }
+  const label = phase === "download" ? "Testing download..." : "Testing upload...";
+  return mbps === null ? label : `${label} ${mbps.toFixed(1)} Mbps`;
+}
+
+export function useSpeedTest(server: string | null): UseSpeedTestResult {
   const [results, setResults] = useState<SpeedTestResults | null>(null);
   const [loading, setLoading] = useState(false);
   const [error, setError] = useState<string | null>(null);
   const [status, setStatus] = useState<string>("");
-  const speedTestRef = useRef<SpeedTest | null>(null);
+  // Bumped by clearResults so a run that is still in flight drops its results.
+  const runIdRef = useRef(0);
 
-  const runSpeedTest = useCallback(() => {
+  const runSpeedTest = useCallback(async () => {
+    const runId = ++runIdRef.current;
+    const isCurrent = () => runId === runIdRef.current;
     setLoading(true);
     setError(null);
     setResults(null);
-    setStatus("Initializing...");
-    info("useSpeedTest: starting speed test");
-
-    try {
-      const speedTest = new SpeedTest({
-        autoStart: false,
-        measurements: [
-          { type: "latency", numPackets: 20 },
-          { type: "download", bytes: 1_000_000, count: 4 },
-          { type: "download", bytes: 10_000_000, count: 4 },
-          { type: "download", bytes: 25_000_000, count: 4 },
-          { type: "upload", bytes: 1_000_000, count: 4 },
-          { type: "upload", bytes: 5_000_000, count: 4 },
-          { type: "upload", bytes: 10_000_000, count: 4 },
-        ],
-      });
-      speedTestRef.current = speedTest;
-
-      speedTest.onResultsChange = ({ type }) => {
-        if (type === "latency") {
-          debug("useSpeedTest: testing latency");
-          setStatus("Testing latency...");
-        } else if (type === "download") {
-          debug("useSpeedTest: testing download");
-          setStatus("Testing download...");
-        } else if (type === "upload") {
-          debug("useSpeedTest: testing upload");
-          setStatus("Testing upload...");
-        }
-      };
+    setStatus("Starting test...");
+    info(`useSpeedTest: starting speed test against ${server ?? "default server"}`);
 
-      speedTest.onFinish = (results) => {
-        setStatus("Processing results...");
-        try {
-          const summary = results.getSummary();
-          const finalResults = {
-            downloadBandwidth: (summary.download ?? 0) / 1_000_000,
-            uploadBandwidth: (summary.upload ?? 0) / 1_000_000,
-            latency: summary.latency ?? 0,
-            jitter: summary.jitter ?? 0,
-          };
-          setResults(finalResults);
-          info(`useSpeedTest: complete - download: ${finalResults.downloadBandwidth.toFixed(1)}Mbps, upload: ${finalResults.uploadBandwidth.toFixed(1)}Mbps, latency: ${finalResults.latency.toFixed(1)}ms`);
-          setStatus("");
-        } catch (e) {
-          logError(`useSpeedTest: error processing results - ${e}`);
-          setError(`Error processing results: ${e}`);
-          setStatus("");
-        }
-        setLoading(false);
-        speedTestRef.current = null;
-      };
+    const onEvent = new Channel<SpeedTestProgress>();
+    onEvent.onmessage = (progress) => {
+      if (isCurrent()) setStatus(formatProgress(progress));
+    };
 
-      speedTest.onError = (err: unknown) => {
-        const errorMsg = err instanceof Error ? err.message : String(err);
-        logError(`useSpeedTest: error - ${errorMsg}`);
-        setError(errorMsg);
-        setStatus("");
-        setLoading(false);
-        speedTestRef.current = null;
-      };
-
-      speedTest.play();
-      setStatus("Starting test...");
+    try {
+      const finalResults = await invoke<SpeedTestResults>("run_speed_test", { server, onEvent });
+      if (!isCurrent()) {
+        debug("useSpeedTest: results arrived after being cleared, discarding");
+        return;
+      }
+      setResults(finalResults);
+      info(`useSpeedTest: complete - download: ${finalResults.downloadBandwidth.toFixed(1)}Mbps, upload: ${finalResults.uploadBandwidth.toFixed(1)}Mbps, latency: ${finalResults.latency.toFixed(1)}ms`);
     } catch (e) {
       const errorMsg = e instanceof Error ? e.message : String(e);
-      logError(`useSpeedTest: initialization error - ${errorMsg}`);
-      setError(errorMsg);
+      logError(`useSpeedTest: error - ${errorMsg}`);
+      if (isCurrent()) setError(errorMsg);
+    } finally {
+      debug("useSpeedTest: finished");
       setStatus("");
       setLoading(false);
     }
-  }, []);
+  }, [server]);
 
+  // The backend test cannot be stopped, so loading stays set until it
+  // finishes and nothing else starts on top of it; only its results are dropped.
   const clearResults = useCallback(() => {
-    if (speedTestRef.current) {
-      speedTestRef.current.pause();
-      speedTestRef.current = null;
-    }
+    runIdRef.current++;
     setResults(null);
     setError(null);
-    setLoading(false);
   }, []);
 
   return {
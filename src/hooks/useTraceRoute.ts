@@ -0,0 +1,60 @@
+import { useState, useCallback } from "react";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import { debug, error as logError } from "@tauri-apps/plugin-log";
+import { TraceUpdate } from "../types/traceroute";
+
+interface UseTraceRouteResult {
+  trace: TraceUpdate | null;
+  loading: boolean;
+  error: string | null;
+  traceRoute: (host: string | null) => Promise<void>;
+  clearTrace: () => void;
+}
+
+export function useTraceRoute(wifiInterface: string | null): UseTraceRouteResult {
+  const [trace, setTrace] = useState<TraceUpdate | null>(null);
+  const [loading, setLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const traceRoute = useCallback(async (host: string | null) => {
+    setLoading(true);
+    setError(null);
+    setTrace(null);
+    debug(`useTraceRoute: tracing ${host ?? "default host"}`);
+
+    const onEvent = new Channel<TraceUpdate>();
+    onEvent.onmessage = (update) => {
+      debug(`useTraceRoute: round ${update.round}/${update.rounds}, ${update.hops.length} hops`);
+      setTrace(update);
+    };
+
+    try {
+      const result = await invoke<TraceUpdate>("trace_route", {
+        host,
+        interface: wifiInterface,
+        onEvent,
+      });
+      setTrace(result);
+      debug(`useTraceRoute: complete - ${result.hops.length} hops, destination: ${result.destination}`);
+    } catch (e) {
+      const errorMsg = e instanceof Error ? e.message : String(e);
+      logError(`useTraceRoute: failed - ${errorMsg}`);
+      setError(errorMsg);
+    } finally {
+      setLoading(false);
+    }
+  }, [wifiInterface]);
+
+  const clearTrace = useCallback(() => {
+    setTrace(null);
+    setError(null);
+  }, []);
+
+  return {
+    trace,
+    loading,
+    error,
+    traceRoute,
+    clearTrace,
+  };
+}
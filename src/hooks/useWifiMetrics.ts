@@ -1,7 +1,7 @@
 import { useState, useEffect, useCallback, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { debug, error as logError } from "@tauri-apps/plugin-log";
-import { NetworkMetrics, MetricHistory } from "../types/metrics";
+import { NetworkMetrics, MetricHistory, InternetTarget } from "../types/metrics";
 import { RingBuffer } from "../utils/RingBuffer";
 
 const HISTORY_LENGTH = 30;
@@ -13,9 +13,11 @@ interface HistoryBuffers {
   noise: RingBuffer;
   routerPing: RingBuffer;
   routerJitter: RingBuffer;
+  routerP95: RingBuffer;
   routerLoss: RingBuffer;
   internetPing: RingBuffer;
   internetJitter: RingBuffer;
+  internetP95: RingBuffer;
   internetLoss: RingBuffer;
   dnsLookup: RingBuffer;
 }
@@ -27,9 +29,11 @@ function createHistoryBuffers(): HistoryBuffers {
     noise: new RingBuffer(HISTORY_LENGTH),
     routerPing: new RingBuffer(HISTORY_LENGTH),
     routerJitter: new RingBuffer(HISTORY_LENGTH),
+    routerP95: new RingBuffer(HISTORY_LENGTH),
     routerLoss: new RingBuffer(HISTORY_LENGTH),
     internetPing: new RingBuffer(HISTORY_LENGTH),
     internetJitter: new RingBuffer(HISTORY_LENGTH),
+    internetP95: new RingBuffer(HISTORY_LENGTH),
     internetLoss: new RingBuffer(HISTORY_LENGTH),
     dnsLookup: new RingBuffer(HISTORY_LENGTH),
   };
@@ -41,14 +45,20 @@ const initialHistory: MetricHistory = {
   noise: [],
   routerPing: [],
   routerJitter: [],
+  routerP95: [],
   routerLoss: [],
   internetPing: [],
   internetJitter: [],
+  internetP95: [],
   internetLoss: [],
   dnsLookup: [],
 };
 
-export function useWifiMetrics() {
+export function useWifiMetrics(
+  wifiInterface: string | null,
+  internetTargets: InternetTarget[],
+  portalCheckUrl: string | null
+) {
   const [metrics, setMetrics] = useState<NetworkMetrics | null>(null);
   const [history, setHistory] = useState<MetricHistory>(initialHistory);
   const [loading, setLoading] = useState(true);
@@ -59,7 +69,11 @@ export function useWifiMetrics() {
   const fetchMetrics = useCallback(async () => {
     try {
       debug("useWifiMetrics: fetching network metrics");
-      const result = await invoke<NetworkMetrics>("get_network_metrics");
+      const result = await invoke<NetworkMetrics>("get_network_metrics", {
+        interface: wifiInterface,
+        targets: internetTargets,
+        portalUrl: portalCheckUrl,
+      });
       if (!isMounted.current) return;
 
       setMetrics(result);
@@ -75,9 +89,11 @@ export function useWifiMetrics() {
       );
       buffers.routerPing.push(result.router_ping?.latency_ms ?? 0);
       buffers.routerJitter.push(result.router_ping?.jitter_ms ?? 0);
+      buffers.routerP95.push(result.router_ping?.p95_ms ?? 0);
       buffers.routerLoss.push(result.router_ping?.packet_loss_percent ?? 0);
       buffers.internetPing.push(result.internet_ping?.latency_ms ?? 0);
       buffers.internetJitter.push(result.internet_ping?.jitter_ms ?? 0);
+      buffers.internetP95.push(result.internet_ping?.p95_ms ?? 0);
       buffers.internetLoss.push(result.internet_ping?.packet_loss_percent ?? 0);
       buffers.dnsLookup.push(result.dns.lookup_latency_ms ?? 0);
 
@@ -87,9 +103,11 @@ export function useWifiMetrics() {
         noise: buffers.noise.toArray(),
         routerPing: buffers.routerPing.toArray(),
         routerJitter: buffers.routerJitter.toArray(),
+        routerP95: buffers.routerP95.toArray(),
         routerLoss: buffers.routerLoss.toArray(),
         internetPing: buffers.internetPing.toArray(),
         internetJitter: buffers.internetJitter.toArray(),
+        internetP95: buffers.internetP95.toArray(),
         internetLoss: buffers.internetLoss.toArray(),
         dnsLookup: buffers.dnsLookup.toArray(),
       });
@@ -105,7 +123,7 @@ export function useWifiMetrics() {
         setLoading(false);
       }
     }
-  }, []);
+  }, [wifiInterface, internetTargets, portalCheckUrl]);
 
   useEffect(() => {
     isMounted.current = true;
@@ -0,0 +1,22 @@
+import { PingResult } from "./metrics";
+
+export interface BufferbloatResult {
+  endpoint: string;
+  idle: PingResult;
+  download: PingResult;
+  upload: PingResult;
+  download_mbps: number | null;
+  upload_mbps: number | null;
+  download_increase_ms: number | null;
+  upload_increase_ms: number | null;
+  grade: string | null;
+  error: string | null;
+}
+
+export type BufferbloatStatus = "good" | "warning" | "bad";
+
+export function getGradeStatus(grade: string | null): BufferbloatStatus {
+  if (grade === "A" || grade === "B") return "good";
+  if (grade === "C") return "warning";
+  return "bad";
+}
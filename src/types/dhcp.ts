@@ -0,0 +1,50 @@
+export interface InterfaceAddress {
+  interface: string;
+  address: string;
+  prefix_len: number;
+  dynamic: boolean;
+  valid_secs: number | null;
+}
+
+export type LeaseSource = "dhclient" | "networkmanager" | "networkd" | "ipconfig";
+
+export interface DhcpLease {
+  source: LeaseSource;
+  address: string | null;
+  server: string | null;
+  router: string | null;
+  lease_time_secs: number | null;
+  remaining_secs: number | null;
+}
+
+export interface SubnetOverlap {
+  subnet: string;
+  other_interface: string;
+  other_subnet: string;
+}
+
+export interface Addressing {
+  ipv4: InterfaceAddress[];
+  ipv6: InterfaceAddress[];
+  lease: DhcpLease | null;
+  gateway: string | null;
+  self_assigned: boolean;
+  missing_gateway: boolean;
+  overlaps: SubnetOverlap[];
+  issues: string[];
+}
+
+export function formatDuration(secs: number | null): string {
+  if (secs === null) return "—";
+  if (secs < 60) return `${secs}s`;
+  if (secs < 3600) return `${Math.floor(secs / 60)}m`;
+  if (secs < 86400) return `${Math.floor(secs / 3600)}h ${Math.floor((secs % 3600) / 60)}m`;
+  return `${Math.floor(secs / 86400)}d ${Math.floor((secs % 86400) / 3600)}h`;
+}
+
+export function getLeaseStatus(lease: DhcpLease | null): "good" | "warning" | "bad" | "neutral" {
+  if (!lease || lease.remaining_secs === null || lease.lease_time_secs === null) return "neutral";
+  if (lease.remaining_secs * 8 < lease.lease_time_secs) return "bad";
+  if (lease.remaining_secs * 2 < lease.lease_time_secs) return "warning";
+  return "good";
+}
@@ -0,0 +1,40 @@
+export type DnsTransport = "udp" | "tcp" | "tls" | "https";
+
+export function getTransportLabel(transport: DnsTransport): string {
+  if (transport === "tls") return "DNS over TLS";
+  if (transport === "https") return "DNS over HTTPS";
+  return transport === "tcp" ? "TCP" : "UDP";
+}
+
+export interface ResolverTarget {
+  label: string;
+  server: string;
+}
+
+export type NxdomainBehavior = "correct" | "hijacked" | "unknown";
+
+export interface ResolverBenchmark {
+  label: string;
+  server: string;
+  system: boolean;
+  cached_median_ms: number | null;
+  cached_p95_ms: number | null;
+  uncached_median_ms: number | null;
+  uncached_p95_ms: number | null;
+  queries: number;
+  failures: number;
+  failure_rate_percent: number | null;
+  nxdomain: NxdomainBehavior;
+  transport: DnsTransport;
+}
+
+export interface DnsBenchmark {
+  resolvers: ResolverBenchmark[];
+  recommendation: string | null;
+}
+
+export const DEFAULT_DNS_RESOLVERS: ResolverTarget[] = [
+  { label: "Cloudflare", server: "1.1.1.1" },
+  { label: "Google", server: "8.8.8.8" },
+  { label: "Quad9", server: "9.9.9.9" },
+];
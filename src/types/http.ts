@@ -0,0 +1,24 @@
+export interface HttpTiming {
+  url: string;
+  remote_address: string | null;
+  status: number | null;
+  dns_ms: number | null;
+  connect_ms: number | null;
+  tls_ms: number | null;
+  first_byte_ms: number | null;
+  transfer_ms: number | null;
+  total_ms: number | null;
+  bytes_received: number;
+  error: string | null;
+}
+
+export const DEFAULT_HTTP_TIMING_URL = "https://www.google.com/generate_204";
+
+export type HttpPhaseStatus = "good" | "warning" | "bad";
+
+export function getHttpPhaseStatus(ms: number | null): HttpPhaseStatus {
+  if (ms === null) return "bad";
+  if (ms <= 100) return "good";
+  if (ms <= 300) return "warning";
+  return "bad";
+}
@@ -2,8 +2,21 @@ import { MetricStatus } from "./metrics";
 
 export interface NearbyNetwork {
   ssid: string;
+  bssid: string | null;
   channel: number;
   frequency_ghz: number;
+  frequency_mhz: number | null;
+  width_mhz: number | null;
+  signal_dbm: number | null;
+}
+
+export interface PathMtu {
+  host: string;
+  interface_mtu: number | null;
+  path_mtu: number | null;
+  reported_mtu: number | null;
+  black_hole: boolean;
+  mismatch: boolean;
 }
 
 export interface InterferenceAnalysis {
@@ -15,6 +28,7 @@ export interface InterferenceAnalysis {
   overlapping_count: number;
   nearby_networks: NearbyNetwork[];
   interference_level: string;
+  path_mtu: PathMtu[];
   suggestions: string[];
 }
 
@@ -38,3 +52,13 @@ export function getSnrStatus(snrDb: number | null): MetricStatus {
   if (snrDb >= 15) return "warning";
   return "bad";
 }
+
+export function formatPathMtu(result: PathMtu): string {
+  if (result.path_mtu === null) return "Not measured";
+  let text = `${result.path_mtu} bytes`;
+  if (result.mismatch && result.interface_mtu !== null) {
+    text += ` (interface ${result.interface_mtu})`;
+  }
+  if (result.black_hole) text += ", silent drops";
+  return text;
+}
@@ -0,0 +1,27 @@
+export type DiscoverySource = "neighbor" | "mdns" | "ssdp";
+
+export interface LanDevice {
+  ip: string;
+  mac: string | null;
+  vendor: string | null;
+  private_mac: boolean;
+  hostname: string | null;
+  model: string | null;
+  services: string[];
+  sources: DiscoverySource[];
+  gateway: boolean;
+}
+
+export interface LanInventory {
+  interface: string | null;
+  devices: LanDevice[];
+  browsed: boolean;
+}
+
+export function getDeviceLabel(device: LanDevice): string {
+  if (device.hostname) return device.hostname;
+  if (device.model) return device.model;
+  if (device.vendor) return `${device.vendor} device`;
+  if (device.private_mac) return "Device with a private address";
+  return "Unknown device";
+}
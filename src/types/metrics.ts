@@ -1,3 +1,7 @@
+import { DnsTransport } from "./dns";
+import { CaptivePortal } from "./portal";
+import { Addressing } from "./dhcp";
+
 export interface WifiInfo {
   connected: boolean;
   ssid: string | null;
@@ -11,20 +15,101 @@ export interface WifiInfo {
 export interface PingResult {
   latency_ms: number | null;
   jitter_ms: number | null;
+  stddev_ms: number | null;
   packet_loss_percent: number | null;
+  min_ms: number | null;
+  max_ms: number | null;
+  median_ms: number | null;
+  p95_ms: number | null;
+  p99_ms: number | null;
+  samples_ms: number[];
+  sent: number | null;
+  received: number | null;
+  duplicates: number | null;
+}
+
+export interface DnsResolver {
+  interface: string | null;
+  servers: string[];
+  search_domains: string[];
+  transport: DnsTransport;
 }
 
 export interface DnsInfo {
   servers: string[];
+  search_domains: string[];
+  resolvers: DnsResolver[];
   lookup_latency_ms: number | null;
+  lookup_rcode: string | null;
+  lookup_answer_count: number | null;
+  lookup_transport: DnsTransport | null;
+  lookup_connect_ms: number | null;
+  lookup_query_ms: number | null;
+}
+
+export type InterfaceKind = "wireless" | "ethernet" | "virtual" | "loopback";
+
+export interface NetworkInterface {
+  name: string;
+  kind: InterfaceKind;
+  up: boolean;
+  mac_address: string | null;
+  service: string | null;
+}
+
+export type ProbeProtocol = "icmp" | "tcp";
+
+export interface InternetTarget {
+  label: string;
+  host: string;
+  protocol: ProbeProtocol;
+  port?: number | null;
+  ipv6_host?: string | null;
+}
+
+export interface TargetResult {
+  target: InternetTarget;
+  protocol: ProbeProtocol;
+  ping: PingResult;
+}
+
+export interface InternetSummary {
+  total_targets: number;
+  reachable_targets: number;
+  best_target: string | null;
+  median_latency_ms: number | null;
+  packet_loss_percent: number | null;
 }
 
 export interface NetworkMetrics {
+  interface: NetworkInterface | null;
   wifi: WifiInfo;
   router_ip: string | null;
   router_ping: PingResult | null;
   internet_ping: PingResult | null;
+  internet_targets: TargetResult[];
+  internet_summary: InternetSummary;
   dns: DnsInfo;
+  dual_stack: DualStack;
+  captive_portal: CaptivePortal;
+  addressing: Addressing;
+}
+
+export interface FamilyMetrics {
+  router_ip: string | null;
+  router_ping: PingResult | null;
+  dns_servers: string[];
+  internet_targets: TargetResult[];
+  internet_summary: InternetSummary;
+}
+
+export type Ipv6Status = "unavailable" | "working" | "degraded" | "broken" | "unknown";
+
+export interface DualStack {
+  ipv4: FamilyMetrics;
+  ipv6: FamilyMetrics;
+  ipv6_status: Ipv6Status;
+  issue: string | null;
 }
 
 export interface MetricHistory {
@@ -33,9 +118,11 @@ export interface MetricHistory {
   noise: number[];
   routerPing: number[];
   routerJitter: number[];
+  routerP95: number[];
   routerLoss: number[];
   internetPing: number[];
   internetJitter: number[];
+  internetP95: number[];
   internetLoss: number[];
   dnsLookup: number[];
 }
@@ -70,6 +157,40 @@ export function getLossStatus(percent: number | null): MetricStatus {
   return "bad";
 }
 
+export function getIpv6Status(status: Ipv6Status): MetricStatus {
+  switch (status) {
+    case "working":
+      return "good";
+    case "degraded":
+      return "warning";
+    case "broken":
+      return "bad";
+    default:
+      return "neutral";
+  }
+}
+
+export function getIpv6StatusLabel(status: Ipv6Status): string {
+  switch (status) {
+    case "unavailable":
+      return "Not available";
+    case "working":
+      return "Working";
+    case "degraded":
+      return "Degraded";
+    case "broken":
+      return "Broken";
+    default:
+      return "Unknown";
+  }
+}
+
+// The comparison is measured in the background, so the first polls carry an
+// empty one. A real result always has an IPv6 router or is "unavailable".
+export function isDualStackPending(dualStack: DualStack): boolean {
+  return dualStack.ipv6_status === "unknown" && dualStack.ipv6.router_ip === null;
+}
+
 export function getLinkRateStatus(mbps: number | null): MetricStatus {
   if (mbps === null) return "neutral";
   if (mbps >= 200) return "good";
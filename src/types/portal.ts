@@ -0,0 +1,27 @@
+export type PortalState = "open" | "portal" | "blocked" | "unknown";
+
+export type PortalSignal = "redirect" | "content_substitution" | "dns_interception";
+
+export interface CaptivePortal {
+  check_url: string;
+  state: PortalState;
+  signal: PortalSignal | null;
+  portal_url: string | null;
+  status: number | null;
+  error: string | null;
+}
+
+export const DEFAULT_PORTAL_CHECK_URL = "http://connectivitycheck.gstatic.com/generate_204";
+
+export function getPortalSignalLabel(signal: PortalSignal | null): string {
+  switch (signal) {
+    case "redirect":
+      return "redirected the check";
+    case "content_substitution":
+      return "replaced the check's reply";
+    case "dns_interception":
+      return "answered the check's name lookup itself";
+    default:
+      return "unknown";
+  }
+}
@@ -1,7 +1,28 @@
+import { InternetTarget } from "./metrics";
+import { DEFAULT_DNS_RESOLVERS, ResolverTarget } from "./dns";
+
 export interface AppSettings {
   openaiApiKey: string | null;
+  wifiInterface: string | null;
+  internetTargets: InternetTarget[];
+  httpTimingUrl: string | null;
+  portalCheckUrl: string | null;
+  speedTestServer: string | null;
+  dnsResolvers: ResolverTarget[];
 }
 
+export const DEFAULT_INTERNET_TARGETS: InternetTarget[] = [
+  { label: "Cloudflare", host: "1.1.1.1", protocol: "icmp", ipv6_host: "2606:4700:4700::1111" },
+  { label: "Google", host: "8.8.8.8", protocol: "icmp", ipv6_host: "2001:4860:4860::8888" },
+  { label: "Quad9", host: "9.9.9.9", protocol: "icmp", ipv6_host: "2620:fe::fe" },
+];
+
 export const DEFAULT_SETTINGS: AppSettings = {
   openaiApiKey: null,
+  wifiInterface: null,
+  internetTargets: DEFAULT_INTERNET_TARGETS,
+  httpTimingUrl: null,
+  portalCheckUrl: null,
+  speedTestServer: null,
+  dnsResolvers: DEFAULT_DNS_RESOLVERS,
 };
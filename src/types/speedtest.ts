@@ -1,10 +1,22 @@
 export interface SpeedTestResults {
+  server: string;
   downloadBandwidth: number;
   uploadBandwidth: number;
   latency: number;
   jitter: number;
 }
 
+export type SpeedTestPhase = "latency" | "download" | "upload";
+
+export interface SpeedTestProgress {
+  phase: SpeedTestPhase;
+  fraction: number;
+  mbps: number | null;
+  latency_ms: number | null;
+}
+
+export const DEFAULT_SPEED_TEST_SERVER = "https://speed.cloudflare.com";
+
 export type SpeedStatus = "good" | "warning" | "bad";
 
 export function getDownloadStatus(mbps: number | null): SpeedStatus {
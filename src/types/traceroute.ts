@@ -0,0 +1,26 @@
+import { PingResult } from "./metrics";
+
+export interface TraceHop {
+  ttl: number;
+  address: string | null;
+  hostname: string | null;
+  ping: PingResult;
+}
+
+export interface TraceUpdate {
+  host: string;
+  destination: string | null;
+  round: number;
+  rounds: number;
+  hops: TraceHop[];
+  complete: boolean;
+}
+
+export type HopStatus = "good" | "warning" | "bad";
+
+export function getHopLossStatus(loss: number | null): HopStatus {
+  if (loss === null) return "bad";
+  if (loss <= 0) return "good";
+  if (loss < 20) return "warning";
+  return "bad";
+}